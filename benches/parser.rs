@@ -0,0 +1,45 @@
+use adcp::parser::Frame;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const CLEAN: &[&str] = &[
+    "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41",
+    "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+    "$PNORC,010526,220800,1,-32.77,-32.77,-32.77,-32.77,46.34,225.0,C,65,64,61,59,40,37,14,22*35",
+    "$PNORC,010526,220800,4,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*26",
+];
+
+const JUNK: &[&str] = &[
+    "\u{0}\u{7f}$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\\r\\n",
+    "noise$PNORC,010526,220800,4,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*26 trailing",
+];
+
+fn parse_all(lines: &[&str]) {
+    for line in lines {
+        black_box(Frame::from_line(black_box(line)).expect("benchmark line parses"));
+    }
+}
+
+fn parse_all_borrowed(lines: &[&str]) {
+    for line in lines {
+        black_box(Frame::parse_borrowed(black_box(line)).expect("benchmark line parses"));
+    }
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_from_line");
+    group.throughput(Throughput::Elements(CLEAN.len() as u64));
+    group.bench_function("clean", |b| b.iter(|| parse_all(CLEAN)));
+    group.throughput(Throughput::Elements(JUNK.len() as u64));
+    group.bench_function("junk", |b| b.iter(|| parse_all(JUNK)));
+    group.finish();
+
+    let mut group = c.benchmark_group("frame_parse_borrowed");
+    group.throughput(Throughput::Elements(CLEAN.len() as u64));
+    group.bench_function("clean", |b| b.iter(|| parse_all_borrowed(CLEAN)));
+    group.throughput(Throughput::Elements(JUNK.len() as u64));
+    group.bench_function("junk", |b| b.iter(|| parse_all_borrowed(JUNK)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_parser);
+criterion_main!(benches);
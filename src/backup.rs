@@ -1,15 +1,23 @@
+use crate::manifest::{OutputKind, RunManifest};
+use crate::persistence::{sync_dir, DataRetention};
+use crate::retention;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::{
     fs::{create_dir_all, File, OpenOptions},
     io::AsyncWriteExt,
+    task::JoinHandle,
 };
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use crate::config::RetentionAction;
+    use chrono::{TimeZone, Utc};
     use tempfile::tempdir;
     use tokio::fs;
 
@@ -26,6 +34,166 @@ mod tests {
         assert!(content.contains("line1"));
         assert!(content.contains("line2"));
     }
+
+    #[tokio::test]
+    async fn per_append_run_id_keeps_sessions_apart() {
+        let tmp = tempdir().expect("tmp");
+        let dir = tmp.path().to_path_buf();
+        let ts = Utc::now();
+
+        let mut first = Backup::new_per_append_with_run_id(&dir).await.expect("first session");
+        first.append("first-run", ts).await.expect("write first");
+        let mut second = Backup::new_per_append_with_run_id(&dir).await.expect("second session");
+        second.append("second-run", ts).await.expect("write second");
+
+        let mut raws = Vec::new();
+        let mut entries = fs::read_dir(&dir).await.expect("read dir");
+        while let Some(entry) = entries.next_entry().await.expect("entry") {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".raw") {
+                raws.push(fs::read_to_string(entry.path()).await.expect("read"));
+            }
+        }
+        raws.sort();
+        assert_eq!(raws, ["first-run\n", "second-run\n"]);
+    }
+
+    #[tokio::test]
+    async fn writer_markers_follow_configuration() {
+        let tmp = tempdir().expect("tmp");
+        let ts = Utc::now();
+        let day = ts.date_naive().format("%Y-%m-%d");
+
+        let handoff = tmp.path().join("handoff");
+        let mut b = Backup::new_per_append(&handoff).await.expect("per append").with_writer_markers(false);
+        b.append("line", ts).await.expect("write");
+        assert!(handoff.join(format!("{day}.raw")).exists());
+        assert!(!handoff.join(format!("{day}.raw.writing")).exists(), "markers disabled");
+
+        let watched = tmp.path().join("backup");
+        let mut b = Backup::new(&watched).await.expect("backup").with_writer_markers(true);
+        let yesterday = ts - chrono::Duration::days(1);
+        b.append("old", yesterday).await.expect("write yesterday");
+        b.append("line", ts).await.expect("write today");
+        assert!(watched.join(format!("{day}.raw.writing")).exists(), "markers enabled");
+        let rolled = format!("{}.raw.writing", yesterday.date_naive().format("%Y-%m-%d"));
+        assert!(!watched.join(rolled).exists(), "marker cleared once the day rolls");
+    }
+
+    #[tokio::test]
+    async fn rolled_backup_is_compressed() {
+        use std::io::Read;
+        let tmp = tempdir().expect("tmp");
+        let dir = tmp.path().to_path_buf();
+        let mut b = Backup::new(&dir).await.expect("new backup").with_compression(true);
+        let day1 = Utc::now() - chrono::Duration::days(1);
+        let day2 = Utc::now();
+        b.append("day1-line", day1).await.expect("write day1");
+        b.append("day1-more", day1).await.expect("write day1");
+        let day1_raw = dir.join(format!("{}.raw", day1.date_naive().format("%Y-%m-%d")));
+        let original = std::fs::read(&day1_raw).expect("read day1 plaintext");
+        b.append("day2-line", day2).await.expect("write day2");
+        b.finish_compression().await;
+
+        let day1_gz = dir.join(format!("{}.raw.gz", day1.date_naive().format("%Y-%m-%d")));
+        assert!(!day1_raw.exists(), "plaintext removed after compression");
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(&day1_gz).expect("open gz"))
+            .read_to_end(&mut decoded)
+            .expect("decode gz");
+        assert_eq!(decoded, original);
+        assert!(dir.join(format!("{}.raw", day2.date_naive().format("%Y-%m-%d"))).exists());
+    }
+
+    #[tokio::test]
+    async fn stepping_back_onto_a_compressing_day_loses_no_lines() {
+        use std::io::Read;
+        let tmp = tempdir().expect("tmp");
+        let dir = tmp.path().to_path_buf();
+        let mut b = Backup::new(&dir).await.expect("new backup").with_compression(true);
+        let day1 = Utc::now() - chrono::Duration::days(1);
+        let day2 = Utc::now();
+        b.append("first", day1).await.expect("write day1");
+        b.append("second", day2).await.expect("write day2");
+        b.append("late", day1).await.expect("write day1 again");
+        b.append("third", day2).await.expect("write day2 again");
+        b.finish_compression().await;
+
+        // The reopened day was compressed again, as a second member of the same archive
+        let day1_gz = dir.join(format!("{}.raw.gz", day1.date_naive().format("%Y-%m-%d")));
+        let mut decoded = String::new();
+        flate2::read::MultiGzDecoder::new(std::fs::File::open(&day1_gz).expect("open gz"))
+            .read_to_string(&mut decoded)
+            .expect("decode gz");
+        assert_eq!(decoded, "first\nlate\n");
+    }
+
+    /// Writes a dated backup last modified `days_ago` days ago.
+    fn backdated_backup(dir: &Path, name: &str, days_ago: u64) {
+        let path = dir.join(name);
+        std::fs::write(&path, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n").expect("write backup");
+        let modified = SystemTime::now() - Duration::from_secs(days_ago * 24 * 60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(modified))
+            .expect("backdate backup");
+    }
+
+    fn raw_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .expect("list backups")
+            .map(|entry| entry.expect("entry").file_name().to_string_lossy().to_string())
+            .filter(|name| name.contains(".raw"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn roll_prunes_oldest_backups_beyond_the_count() {
+        let tmp = tempdir().expect("tmp");
+        let dir = tmp.path().to_path_buf();
+        let mut backup = Backup::new(&dir).await.expect("backup").with_retention(DataRetention {
+            max_files: Some(3),
+            max_age_days: None,
+            action: RetentionAction::Delete,
+        });
+        for (day, name) in ["2026-01-01.raw", "2026-01-02.raw.gz", "2026-01-03.raw", "2026-01-04.raw"]
+            .iter()
+            .enumerate()
+        {
+            backdated_backup(&dir, name, 5 - day as u64);
+        }
+        let archived = dir.join("archive_20251231_000000");
+        std::fs::create_dir_all(&archived).expect("archive folder");
+        backdated_backup(&archived, "2025-12-31.raw", 6);
+
+        let ts = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        backup.append("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", ts).await.expect("append");
+
+        assert_eq!(raw_files(&dir), ["2026-01-03.raw", "2026-01-04.raw", "2026-01-05.raw"]);
+        assert!(archived.join("2025-12-31.raw").exists(), "archive folders are not pruned");
+    }
+
+    #[tokio::test]
+    async fn roll_prunes_backups_older_than_the_age_cap() {
+        let tmp = tempdir().expect("tmp");
+        let dir = tmp.path().to_path_buf();
+        let mut backup = Backup::new(&dir).await.expect("backup").with_retention(DataRetention {
+            max_files: None,
+            max_age_days: Some(7),
+            action: RetentionAction::Delete,
+        });
+        backdated_backup(&dir, "2026-01-01.raw", 10);
+        backdated_backup(&dir, "2026-01-02.raw", 5);
+        backdated_backup(&dir, "2026-01-03.raw", 1);
+
+        let ts = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        backup.append("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", ts).await.expect("append");
+
+        assert_eq!(raw_files(&dir), ["2026-01-02.raw", "2026-01-03.raw", "2026-01-05.raw"]);
+    }
 }
 
 
@@ -38,6 +206,22 @@ pub struct Backup {
     /// This is useful for the processing folder where we must not hold a long-lived
     /// file handle that prevents file rotation and moving by the processing worker.
     per_append: bool,
+    /// Per-run suffix (session start time) added to per-append filenames so a restarted
+    /// recorder never appends into a file left in the processing folder by an earlier run.
+    run_id: Option<String>,
+    /// Gzip the previous day's `.raw` into `.raw.gz` when the buffered backup rolls over.
+    compress_on_roll: bool,
+    /// Background compressions of rolled files, so the append path never waits on gzip.
+    compressions: Vec<(PathBuf, JoinHandle<()>)>,
+    /// Run manifest credited with every appended line.
+    manifest: Option<Arc<RunManifest>>,
+    /// Touch a `{file}.writing` marker on every append so watchers skip the file being
+    /// written. Defaults to on for per-append (handoff) backups and off otherwise.
+    writer_markers: bool,
+    /// Fsync the directory after creating each new file (`fsync_dir_on_rotate`).
+    sync_dir: bool,
+    /// Limits applied to the dated backups each time the buffered backup rolls over.
+    retention: Option<DataRetention>,
 }
 
 impl Backup {
@@ -49,6 +233,13 @@ impl Backup {
         Self::new_with_option(base_dir, true).await
     }
 
+    /// Per-append backup whose files are named `{date}_{run_id}.raw`, keeping runs apart.
+    pub async fn new_per_append_with_run_id(base_dir: impl AsRef<Path>) -> Result<Self> {
+        let mut backup = Self::new_with_option(base_dir, true).await?;
+        backup.run_id = Some(Utc::now().format("%Y%m%dT%H%M%S%6f").to_string());
+        Ok(backup)
+    }
+
     async fn new_with_option(base_dir: impl AsRef<Path>, per_append: bool) -> Result<Self> {
         let base = base_dir.as_ref().to_path_buf();
         create_dir_all(&base)
@@ -63,10 +254,9 @@ impl Backup {
             while let Ok(Some(entry)) = entries.next_entry().await {
                 let path = entry.path();
                 if path.is_file() {
-                    if let Some(ext) = path.extension() {
-                        if ext == "raw" {
-                            files_to_archive.push(path);
-                        }
+                    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                    if name.ends_with(".raw") || name.ends_with(".raw.gz") {
+                        files_to_archive.push(path);
                     }
                 }
             }
@@ -90,23 +280,64 @@ impl Backup {
             current_file: None,
             current_date: None,
             per_append,
+            run_id: None,
+            compress_on_roll: false,
+            compressions: Vec::new(),
+            manifest: None,
+            writer_markers: per_append,
+            sync_dir: false,
+            retention: None,
         })
     }
 
+    pub fn with_compression(mut self, compress_on_roll: bool) -> Self {
+        self.compress_on_roll = compress_on_roll;
+        self
+    }
+
+    pub fn with_manifest(mut self, manifest: Option<Arc<RunManifest>>) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    pub fn with_writer_markers(mut self, writer_markers: bool) -> Self {
+        self.writer_markers = writer_markers;
+        self
+    }
+
+    pub fn with_dir_sync(mut self, sync_dir: bool) -> Self {
+        self.sync_dir = sync_dir;
+        self
+    }
+
+    /// Prunes old dated backups (`max_backup_files`, `max_backup_age_days`) after each roll.
+    pub fn with_retention(mut self, retention: DataRetention) -> Self {
+        self.retention = retention.is_active().then_some(retention);
+        self
+    }
+
     /// Appends a line to the current backup file, rolling to a new file if needed.
     /// If `per_append` is set, this method opens, writes and closes the file every call.
     pub async fn append(&mut self, line: &str, timestamp: DateTime<Utc>) -> Result<()> {
         let date = timestamp.date_naive();
 
         if self.per_append {
-            let filename = format!("{}.raw", date.format("%Y-%m-%d"));
+            let filename = match &self.run_id {
+                Some(run_id) => format!("{}_{}.raw", date.format("%Y-%m-%d"), run_id),
+                None => format!("{}.raw", date.format("%Y-%m-%d")),
+            };
             let path = self.base.join(&filename);
+            // Only a file this call creates needs its directory entry synced
+            let created = self.sync_dir && !tokio::fs::try_exists(&path).await.unwrap_or(false);
             let mut file = OpenOptions::new()
                 .create(true)
                 .append(true)
                 .open(&path)
                 .await
                 .with_context(|| format!("failed to open backup file {}", path.display()))?;
+            if created {
+                sync_dir(&self.base).await?;
+            }
             file.write_all(line.as_bytes())
                 .await
                 .context("failed to write to backup file")?;
@@ -114,21 +345,10 @@ impl Backup {
                 .await
                 .context("failed to write newline to backup file")?;
             file.flush().await.context("failed to flush backup file")?;
-            // Update marker file to signal recent write activity for processors
-            let marker_name = format!("{}.writing", &filename);
-            let marker_path = self.base.join(&marker_name);
-            // Write current unix timestamp into marker (not strictly necessary; touching mtime is sufficient)
-            let mut marker = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&marker_path)
-                .await
-                .with_context(|| format!("failed to update marker {}", marker_path.display()))?;
-            let ts = format!("{}", chrono::Utc::now().timestamp());
-            marker.write_all(ts.as_bytes()).await.context("failed to write marker")?;
-            marker.flush().await.context("failed to flush marker")?;
-            // marker closed when dropped
+            if let Some(manifest) = &self.manifest {
+                manifest.record(OutputKind::Backup, &path, 1);
+            }
+            self.touch_marker(&filename).await?;
             return Ok(());
         }
 
@@ -145,19 +365,69 @@ impl Backup {
                 .await
                 .context("failed to write newline to backup file")?;
             file.flush().await.context("failed to flush backup file")?;
+            let filename = format!("{}.raw", date.format("%Y-%m-%d"));
+            if let Some(manifest) = &self.manifest {
+                manifest.record(OutputKind::Backup, &self.base.join(&filename), 1);
+            }
+            self.touch_marker(&filename).await?;
         }
 
         Ok(())
     }
 
+    /// Updates `{filename}.writing` to signal recent write activity to watchers, when enabled.
+    async fn touch_marker(&self, filename: &str) -> Result<()> {
+        if !self.writer_markers {
+            return Ok(());
+        }
+        let marker_path = self.base.join(format!("{filename}.writing"));
+        // Write current unix timestamp into marker (not strictly necessary; touching mtime is sufficient)
+        let mut marker = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&marker_path)
+            .await
+            .with_context(|| format!("failed to update marker {}", marker_path.display()))?;
+        let ts = format!("{}", chrono::Utc::now().timestamp());
+        marker.write_all(ts.as_bytes()).await.context("failed to write marker")?;
+        marker.flush().await.context("failed to flush marker")?;
+        // marker closed when dropped
+        Ok(())
+    }
+
     async fn roll_to_date(&mut self, date: chrono::NaiveDate) -> Result<()> {
         if let Some(file) = self.current_file.take() {
             // Close previous file if any
             drop(file);
         }
+        if let (true, Some(previous)) = (self.writer_markers, self.current_date) {
+            // The previous day's file is complete; let watchers pick it up
+            let marker = self.base.join(format!("{}.raw.writing", previous.format("%Y-%m-%d")));
+            tokio::fs::remove_file(&marker).await.ok();
+        }
+        self.compressions.retain(|(_, handle)| !handle.is_finished());
+        if let (true, Some(previous)) = (self.compress_on_roll, self.current_date) {
+            let raw = self.base.join(format!("{}.raw", previous.format("%Y-%m-%d")));
+            let handle = tokio::spawn({
+                let raw = raw.clone();
+                async move {
+                    // A failed compression keeps the plaintext file; capture must go on regardless.
+                    if let Err(err) = compress_file(raw.clone()).await {
+                        tracing::warn!(file = %raw.display(), error = %err, "failed to compress rotated backup");
+                    }
+                }
+            });
+            self.compressions.push((raw, handle));
+        }
 
         let filename = format!("{}.raw", date.format("%Y-%m-%d"));
         let path = self.base.join(filename);
+        // The clock stepped back onto a day still being compressed: appending now could lose
+        // lines to the compressor removing the plaintext, so let it finish first
+        if self.compressions.iter().any(|(raw, _)| *raw == path) {
+            self.finish_compression().await;
+        }
 
         let file = OpenOptions::new()
             .create(true)
@@ -165,10 +435,110 @@ impl Backup {
             .open(&path)
             .await
             .with_context(|| format!("failed to open backup file {}", path.display()))?;
+        if self.sync_dir {
+            sync_dir(&self.base).await?;
+        }
 
         self.current_file = Some(file);
         self.current_date = Some(date);
 
+        if let Some(retention) = &self.retention {
+            // Pruning is housekeeping; it must never stop the capture
+            if let Err(err) = self.prune(retention).await {
+                tracing::warn!(dir = %self.base.display(), error = %err, "backup retention pass failed");
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits for the background compressions of rolled files, e.g. before shutting down.
+    pub async fn finish_compression(&mut self) {
+        for (raw, handle) in self.compressions.drain(..) {
+            if let Err(err) = handle.await {
+                tracing::warn!(file = %raw.display(), error = %err, "backup compression task failed");
+            }
+        }
+    }
+
+    /// Retires dated `.raw`/`.raw.gz` backups beyond the count cap (oldest first by the date
+    /// in their name) or last written longer ago than the age cap. The open file, files still
+    /// being compressed and the `archive_*` folders of earlier runs are left alone.
+    async fn prune(&self, retention: &DataRetention) -> Result<()> {
+        let mut dated = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.base)
+            .await
+            .with_context(|| format!("list {}", self.base.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str().filter(|_| metadata.is_file()) else {
+                continue;
+            };
+            if !(name.ends_with(".raw") || name.ends_with(".raw.gz")) {
+                continue;
+            }
+            let Some(date) = name
+                .get(..10)
+                .and_then(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+            // Both the plaintext and the archive of a day being compressed are in flux
+            let compressing = self.compressions.iter().any(|(raw, handle)| {
+                !handle.is_finished()
+                    && raw.file_name().is_some_and(|raw| name.starts_with(&*raw.to_string_lossy()))
+            });
+            if Some(date) != self.current_date && !compressing {
+                dated.push((date, entry.path(), metadata.modified().ok()));
+            }
+        }
+        // Newest first, so everything past the kept count is the oldest
+        dated.sort_by_key(|(date, _, _)| std::cmp::Reverse(*date));
+
+        let keep = retention.max_files.map(|max| max.saturating_sub(1));
+        let cutoff = retention
+            .max_age_days
+            .and_then(|days| SystemTime::now().checked_sub(Duration::from_secs(days * 24 * 60 * 60)));
+        for (index, (_, path, modified)) in dated.into_iter().enumerate() {
+            let over_count = keep.is_some_and(|keep| index >= keep);
+            let too_old = cutoff.zip(modified).is_some_and(|(cutoff, modified)| modified < cutoff);
+            if over_count || too_old {
+                retention::retire(&path, &retention.action).await?;
+            }
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Gzips `path` to `path.gz` and removes the original once the archive is complete. A fresh
+/// archive is written via a temp file; an existing one (same day reopened) gains a new member.
+async fn compress_file(path: PathBuf) -> Result<()> {
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        use std::io::Write;
+        let gz = PathBuf::from(format!("{}.gz", path.display()));
+        let existing = gz.exists();
+        let target = if existing {
+            gz.clone()
+        } else {
+            PathBuf::from(format!("{}.partial", gz.display()))
+        };
+        let mut input = std::fs::File::open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let output = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&target)
+            .with_context(|| format!("failed to open {}", target.display()))?;
+        let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+        std::io::copy(&mut input, &mut encoder).context("failed to compress backup")?;
+        encoder.finish().context("failed to finish gzip stream")?.flush()?;
+        if !existing {
+            std::fs::rename(&target, &gz)
+                .with_context(|| format!("failed to rename {}", target.display()))?;
+        }
+        std::fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        Ok(())
+    })
+    .await
+    .context("compression task failed")?
+}
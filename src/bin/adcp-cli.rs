@@ -1,7 +1,5 @@
+use adcp::bus::{BusLink, LinkState};
 use adcp::telemetry::RecorderStats;
-use adcp::AppConfig;
-use busrt::client::AsyncClient;
-use busrt::ipc::{Client, Config};
 use busrt::rpc::{Rpc, RpcClient, RpcEvent, RpcHandlers, RpcResult};
 use busrt::QoS;
 use crossterm::{
@@ -10,10 +8,10 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::{Backend, CrosstermBackend},
+    backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Style},
-    widgets::{Block, Borders, Row, Table, Cell},
+    widgets::{Block, Borders, Paragraph, Row, Table, Cell},
     Terminal,
 };
 use std::collections::HashMap;
@@ -55,31 +53,18 @@ impl RpcHandlers for CliHandlers {
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Cannot use tracing to stdout as TUI takes over.
-    // tracing_subscriber::fmt::init();
-
-    let name = format!("adcp.cli.{}", std::process::id());
-
-    // Connect to BusRT
-    let bus_config = Config::new("127.0.0.1:7777", &name);
-    let mut client = Client::connect(&bus_config).await?;
-
-    // Subscribe
-    client.subscribe("conf.update", QoS::Processed).await?;
-    client.subscribe("stat/recorder/#", QoS::Processed).await?;
-
-    let state = Arc::new(Mutex::new(AppState {
-        config: None,
-        recorder_stats: HashMap::new(),
-    }));
-
-    let handlers = CliHandlers { state: state.clone() };
-    let rpc_client = RpcClient::new(client, handlers);
+/// Connects to the broker, fetches the current config, and after every disconnect
+/// reconnects with backoff and resubscribes. Handlers are rebuilt for each connection.
+async fn maintain_link(link: BusLink, state: Arc<Mutex<AppState>>) {
+    loop {
+        let rpc = link.connect_with_retry(|| CliHandlers { state: state.clone() }).await;
+        fetch_config(&rpc, &state).await;
+        link.disconnected(&rpc).await;
+    }
+}
 
-    // Initial fetch
-    let response = rpc_client.call(
+async fn fetch_config(rpc: &RpcClient, state: &Mutex<AppState>) {
+    let response = rpc.call(
         "adcp.conf.manager",
         "cmd.conf.get",
         Vec::new().into(),
@@ -94,6 +79,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
     }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Cannot use tracing to stdout as TUI takes over.
+    // tracing_subscriber::fmt::init();
+
+    let name = format!("adcp.cli.{}", std::process::id());
+
+    let state = Arc::new(Mutex::new(AppState {
+        config: None,
+        recorder_stats: HashMap::new(),
+    }));
+
+    // Keep the bus connection alive across broker restarts; the TUI shows the link state
+    let link = BusLink::new("127.0.0.1:7777", &name)
+        .with_topic("conf.update")
+        .with_topic("stat/recorder/#");
+    let link_state = link.state();
+    tokio::spawn(maintain_link(link, state.clone()));
 
     // TUI setup
     enable_raw_mode()?;
@@ -112,6 +117,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .margin(1)
                 .constraints(
                     [
+                        Constraint::Length(1),
                         Constraint::Percentage(50),
                         Constraint::Percentage(50),
                     ]
@@ -119,6 +125,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 )
                 .split(f.size());
 
+            let banner = match *link_state.borrow() {
+                LinkState::Connected => Paragraph::new(""),
+                LinkState::Connecting => Paragraph::new("connecting to broker...").style(Style::default().fg(Color::Yellow)),
+                LinkState::Reconnecting => Paragraph::new("broker disconnected \u{2014} reconnecting (data below may be stale)")
+                    .style(Style::default().fg(Color::White).bg(Color::Red)),
+            };
+            f.render_widget(banner, chunks[0]);
+
             let s = state.lock().unwrap();
 
             // Config Table
@@ -133,7 +147,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let config_rows: Vec<Row> = config_str.lines().map(|l| Row::new(vec![Cell::from(l)])).collect();
             let config_table = Table::new(config_rows, [Constraint::Percentage(100)])
                 .block(Block::default().title("Configuration").borders(Borders::ALL));
-            f.render_widget(config_table, chunks[0]);
+            f.render_widget(config_table, chunks[1]);
 
             // Stats Table
             let header = Row::new(vec![
@@ -164,7 +178,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             ])
             .header(header)
             .block(Block::default().title("Recorder Telemetry").borders(Borders::ALL));
-            f.render_widget(stats_table, chunks[1]);
+            f.render_widget(stats_table, chunks[2]);
 
         })?;
 
@@ -1,13 +1,21 @@
-use adcp::AppConfig;
-use busrt::client::AsyncClient;
+use adcp::{control, AppConfig};
 use busrt::ipc::{Client, Config};
 use busrt::rpc::{Rpc, RpcClient, RpcEvent, RpcError, RpcHandlers, RpcResult, RPC_ERROR_CODE_INTERNAL};
-use std::sync::Arc;
+use busrt::QoS;
+use std::sync::{Arc, Mutex};
 use tokio::signal;
+use tokio::sync::{mpsc, watch};
 use async_trait::async_trait;
 
 struct ConfRpcHandlers {
-    config: Arc<AppConfig>,
+    config: Mutex<Arc<AppConfig>>,
+    config_path: &'static str,
+    updates: mpsc::UnboundedSender<Vec<u8>>,
+    stop: watch::Sender<()>,
+}
+
+fn internal_error(e: impl ToString) -> RpcError {
+    RpcError::new(RPC_ERROR_CODE_INTERNAL, Some(e.to_string().as_bytes().to_vec()))
 }
 
 #[async_trait]
@@ -15,11 +23,25 @@ impl RpcHandlers for ConfRpcHandlers {
     async fn handle_call(&self, event: RpcEvent) -> RpcResult {
         match event.parse_method() {
             Ok("cmd.conf.get") => {
-                let json = serde_json::to_vec(&*self.config).map_err(|e| {
-                    RpcError::new(RPC_ERROR_CODE_INTERNAL, Some(e.to_string().as_bytes().to_vec()))
-                })?;
+                let config = self.config.lock().unwrap().clone();
+                let json = serde_json::to_vec(&*config).map_err(internal_error)?;
+                Ok(Some(json))
+            }
+            // Same config as `cmd.conf.get`, as TOML for diffing against the file on disk
+            Ok("cmd.conf.get_toml") => {
+                let config = self.config.lock().unwrap().clone();
+                let toml = toml::to_string(&*config).map_err(internal_error)?;
+                Ok(Some(toml.into_bytes()))
+            }
+            Ok("cmd.conf.reload") => {
+                // Re-read the file and push it to running services as `conf.update`
+                let config = AppConfig::load(self.config_path).map_err(internal_error)?;
+                let json = serde_json::to_vec(&config).map_err(internal_error)?;
+                *self.config.lock().unwrap() = Arc::new(config);
+                self.updates.send(json.clone()).map_err(internal_error)?;
                 Ok(Some(json))
             }
+            Ok(control::SHUTDOWN_METHOD) => control::handle_shutdown(&self.stop),
             Ok(_) => Err(RpcError::method(None)),
             Err(_) => Err(RpcError::new(busrt::rpc::RPC_ERROR_CODE_PARSE, None)),
         }
@@ -43,16 +65,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let bus_config = Config::new("127.0.0.1:7777", name);
     let client = Client::connect(&bus_config).await?;
 
+    let (updates_tx, mut updates_rx) = mpsc::unbounded_channel();
+    let (stop, mut stop_rx) = watch::channel(());
     let handlers = ConfRpcHandlers {
-        config: config.clone(),
+        config: Mutex::new(config.clone()),
+        config_path,
+        updates: updates_tx,
+        stop,
     };
 
-    let _rpc_client = RpcClient::new(client, handlers);
+    let rpc_client = RpcClient::new(client, handlers);
+    let client = rpc_client.client().clone();
 
     println!("Conf manager started: {}", name);
 
-    // Keep alive
-    signal::ctrl_c().await?;
+    // Keep alive, publishing reloaded configs
+    loop {
+        tokio::select! {
+            Some(payload) = updates_rx.recv() => {
+                let mut c = client.lock().await;
+                if let Err(e) = c.publish("conf.update", payload.into(), QoS::Processed).await {
+                    eprintln!("Failed to publish config update: {}", e);
+                }
+            }
+            _ = signal::ctrl_c() => break,
+            _ = control::shutdown_requested(&mut stop_rx) => break,
+        }
+    }
 
     Ok(())
 }
@@ -1,4 +1,4 @@
-use adcp::telemetry::RecorderStats;
+use adcp::{control, telemetry::RecorderStats};
 use busrt::client::AsyncClient;
 use busrt::ipc::{Client, Config};
 use busrt::rpc::{RpcClient, RpcEvent, RpcHandlers, RpcResult};
@@ -7,10 +7,12 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::signal;
+use tokio::sync::watch;
 use async_trait::async_trait;
 
 struct QaHandlers {
     recorders: Arc<Mutex<HashMap<String, RecorderState>>>,
+    stop: watch::Sender<()>,
 }
 
 struct RecorderState {
@@ -20,8 +22,11 @@ struct RecorderState {
 
 #[async_trait]
 impl RpcHandlers for QaHandlers {
-    async fn handle_call(&self, _event: RpcEvent) -> RpcResult {
-        Ok(None)
+    async fn handle_call(&self, event: RpcEvent) -> RpcResult {
+        match event.parse_method() {
+            Ok(control::SHUTDOWN_METHOD) => control::handle_shutdown(&self.stop),
+            _ => Ok(None),
+        }
     }
     async fn handle_notification(&self, _event: RpcEvent) {}
     async fn handle_frame(&self, frame: busrt::Frame) {
@@ -59,8 +64,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     client.subscribe("stat/recorder/#", QoS::Processed).await?;
 
     let recorders = Arc::new(Mutex::new(HashMap::new()));
+    let (stop, mut stop_rx) = watch::channel(());
     let handlers = QaHandlers {
         recorders: recorders.clone(),
+        stop,
     };
 
     let _rpc_client = RpcClient::new(client, handlers);
@@ -88,7 +95,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    signal::ctrl_c().await?;
+    tokio::select! {
+        res = signal::ctrl_c() => res?,
+        _ = control::shutdown_requested(&mut stop_rx) => {}
+    }
     println!("QA Watchdog stopping...");
 
     Ok(())
@@ -1,6 +1,31 @@
-use tokio::process::Command;
+use adcp::control;
+use busrt::ipc::{Client, Config};
+use busrt::rpc::RpcClient;
+use tokio::process::{Child, Command};
 use tokio::signal;
 use std::process::Stdio;
+use std::time::Duration;
+
+/// How long a service gets to finish its clean shutdown before it is killed.
+const GRACEFUL_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Asks `child` over the bus to stop via `cmd.service.shutdown`, then kills it if it has not
+/// exited within the grace period (or the request could not be delivered).
+async fn stop_service(rpc: Option<&RpcClient>, bus_name: &str, child: &mut Child) {
+    if let Some(rpc) = rpc {
+        match control::request_shutdown(rpc, bus_name, GRACEFUL_STOP_TIMEOUT).await {
+            Ok(()) => {
+                if tokio::time::timeout(GRACEFUL_STOP_TIMEOUT, child.wait()).await.is_ok() {
+                    println!("{} stopped gracefully.", bus_name);
+                    return;
+                }
+                eprintln!("{} did not exit in time; killing.", bus_name);
+            }
+            Err(e) => eprintln!("Graceful stop of {} failed: {}; killing.", bus_name, e),
+        }
+    }
+    let _ = child.kill().await;
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -25,7 +50,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Fallback to cargo run if binary not found (e.g. running from source root without build)
         if !path.exists() {
              Command::new("cargo")
-                .args(&["run", "--bin", name])
+                .args(["run", "--bin", name])
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .spawn()
@@ -62,10 +87,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     signal::ctrl_c().await?;
     println!("Stopping services...");
 
-    // Kill processes
-    let _ = proc_manager.kill().await;
-    let _ = qa.kill().await;
-    let _ = conf_manager.kill().await;
+    // Ask each service to stop over the bus so it can clean up, killing only stragglers
+    let rpc = match Client::connect(&Config::new("127.0.0.1:7777", &format!("adcp.starter.{}", std::process::id()))).await {
+        Ok(client) => Some(RpcClient::new0(client)),
+        Err(e) => {
+            eprintln!("Bus unavailable for graceful stop: {}", e);
+            None
+        }
+    };
+    let pid_name = |prefix: &str, child: &Child| format!("{}.{}", prefix, child.id().unwrap_or_default());
+    let proc_manager_name = pid_name("adcp.proc.manager", &proc_manager);
+    let qa_name = pid_name("adcp.qa", &qa);
+    stop_service(rpc.as_ref(), &proc_manager_name, &mut proc_manager).await;
+    stop_service(rpc.as_ref(), &qa_name, &mut qa).await;
+    stop_service(rpc.as_ref(), "adcp.conf.manager", &mut conf_manager).await;
+    drop(rpc);
     let _ = broker.kill().await;
 
     println!("Stopped.");
@@ -1,17 +1,129 @@
-use adcp::{logging, platform, AppConfig, Service, simulator, config::ServiceMode};
+use adcp::{logging, parser::SentenceKind, merge, selftest, stats, platform, serial, AppConfig, Service, simulator, config::ServiceMode};
 use anyhow::{bail, Context, Result};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct Cli {
     config_path: String,
     replay: Option<String>,
+    max_errors: Option<usize>,
+    exclude: Vec<SentenceKind>,
+    process_once: bool,
+    force_reprocess: bool,
+    count_by_day: bool,
+    merge: Option<MergeArgs>,
+    stats: Option<StatsArgs>,
+    list_ports: bool,
+    selftest: bool,
+}
+
+#[derive(Debug)]
+struct MergeArgs {
+    inputs: Vec<String>,
+    output: String,
+}
+
+impl MergeArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut inputs = Vec::new();
+        let mut output: Option<String> = None;
+        let mut in_inputs = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--inputs" => in_inputs = true,
+                "--output" => {
+                    in_inputs = false;
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--output requires a path"))?;
+                    output = Some(value);
+                }
+                other if in_inputs => inputs.push(other.to_string()),
+                other => bail!("unknown merge argument '{other}'"),
+            }
+        }
+        if inputs.is_empty() {
+            bail!("merge requires at least one path after --inputs");
+        }
+        Ok(Self {
+            inputs,
+            output: output.ok_or_else(|| anyhow::anyhow!("merge requires --output <path>"))?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct StatsArgs {
+    input: String,
+    json: bool,
+}
+
+impl StatsArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut input: Option<String> = None;
+        let mut json = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--input requires a path"))?;
+                    input = Some(value);
+                }
+                "--json" => json = true,
+                other => bail!("unknown stats argument '{other}'"),
+            }
+        }
+        Ok(Self {
+            input: input.ok_or_else(|| anyhow::anyhow!("stats requires --input <path>"))?,
+            json,
+        })
+    }
 }
 
 impl Cli {
     fn parse() -> Result<Self> {
-        let mut args = std::env::args().skip(1);
+        let mut args = std::env::args().skip(1).peekable();
         let mut config_path: Option<String> = None;
         let mut replay: Option<String> = None;
+        let mut max_errors: Option<usize> = None;
+        let mut exclude: Vec<SentenceKind> = Vec::new();
+        let mut process_once = false;
+        let mut force_reprocess = false;
+        let mut count_by_day = false;
+
+        if args.peek().map(String::as_str) == Some("merge") {
+            args.next();
+            return Ok(Self {
+                merge: Some(MergeArgs::parse(args)?),
+                ..Self::default()
+            });
+        }
+
+        if args.peek().map(String::as_str) == Some("stats") {
+            args.next();
+            return Ok(Self {
+                stats: Some(StatsArgs::parse(args)?),
+                ..Self::default()
+            });
+        }
+
+        if args.peek().map(String::as_str) == Some("list-ports") {
+            args.next();
+            reject_extra_args("list-ports", args)?;
+            return Ok(Self {
+                list_ports: true,
+                ..Self::default()
+            });
+        }
+
+        if args.peek().map(String::as_str) == Some("selftest") {
+            args.next();
+            reject_extra_args("selftest", args)?;
+            return Ok(Self {
+                selftest: true,
+                ..Self::default()
+            });
+        }
 
         while let Some(arg) = args.next() {
             match arg.as_str() {
@@ -27,11 +139,43 @@ impl Cli {
                         .ok_or_else(|| anyhow::anyhow!("--replay requires a path"))?;
                     replay = Some(value);
                 }
+                "--max-errors" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--max-errors requires a count"))?;
+                    max_errors = Some(
+                        value
+                            .parse()
+                            .with_context(|| format!("invalid --max-errors '{value}'"))?,
+                    );
+                }
+                "--exclude" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--exclude requires a sentence type"))?;
+                    exclude.push(value.parse()?);
+                }
+                "--process-once" => process_once = true,
+                "--force-reprocess" => force_reprocess = true,
+                "--count-by-day" => count_by_day = true,
                 "--help" | "-h" => {
                     println!(
-                        "Usage: adcp [--config <path>] [--replay <sample>]\n\
+                        "Usage: adcp [--config <path>] [--replay <sample> [--max-errors <n>] [--exclude <type>]...] [--count-by-day] [--process-once] [--force-reprocess]\n\
+                            or: adcp merge --inputs <a> <b>... --output <path>\n\
+                            or: adcp stats --input <capture> [--json]\n\
+                            or: adcp list-ports\n\
+                            or: adcp selftest\n\
                          --config <path>   Path to TOML configuration (default: config/adcp.toml)\n\
-                         --replay <path>   Replay a capture file through the pipeline and exit"
+                         --replay <path>   Replay a capture file through the pipeline and exit\n\
+                         --max-errors <n>  Abort the replay once more than <n> lines fail to parse\n\
+                         --exclude <type>  Parse but do not persist config, sensor, current, echo or bottom_track frames (repeatable)\n\
+                         --count-by-day    With --replay, print frames per day without persisting anything and exit\n\
+                         --process-once    In Processing mode, process every stable file once and exit\n\
+                         --force-reprocess In Processing mode, ignore the watermark of already-processed files\n\
+                         merge             Merge overlapping captures into one time-ordered, de-duplicated file\n\
+                         stats             Print per-hour frame/error counts and a cells-per-ensemble histogram\n\
+                         list-ports        List serial ports available on this machine\n\
+                         selftest          Parse, persist and read back synthetic frames in a temp dir; prints PASS or FAIL"
                     );
                     std::process::exit(0);
                 }
@@ -48,10 +192,24 @@ impl Cli {
         Ok(Self {
             config_path: config_path.unwrap_or_else(|| AppConfig::default_path().into()),
             replay,
+            max_errors,
+            exclude,
+            process_once,
+            force_reprocess,
+            count_by_day,
+            ..Self::default()
         })
     }
 }
 
+/// Fails on anything after a subcommand that takes no arguments.
+fn reject_extra_args(command: &str, mut args: impl Iterator<Item = String>) -> Result<()> {
+    match args.next() {
+        Some(extra) => bail!("{command} takes no arguments, got '{extra}'"),
+        None => Ok(()),
+    }
+}
+
 async fn cleanup_orphans(tmp_dir: &str) {
     if let Ok(rd) = std::fs::read_dir(tmp_dir) {
         let my_pid = std::process::id();
@@ -90,8 +248,67 @@ async fn cleanup_orphans(tmp_dir: &str) {
 async fn main() -> Result<()> {
     let cli = Cli::parse()?;
 
-    let config = AppConfig::load(&cli.config_path)
+    if let Some(args) = cli.merge {
+        let summary = merge::merge_captures(&args.inputs, &args.output).await?;
+        println!(
+            "merged {} frames into {} ({} duplicates removed, {} rejected)",
+            summary.frames_written, args.output, summary.duplicates_removed, summary.rejected
+        );
+        return Ok(());
+    }
+
+    if let Some(args) = cli.stats {
+        let summary = stats::capture_stats(&args.input).await?;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        } else {
+            print!("{summary}");
+        }
+        return Ok(());
+    }
+
+    if cli.list_ports {
+        let ports = serial::describe_available_ports()?;
+        if ports.is_empty() {
+            println!("no serial ports found");
+        }
+        for port in ports {
+            println!("{port}");
+        }
+        return Ok(());
+    }
+
+    if cli.selftest {
+        let dir = std::env::temp_dir().join(format!("adcp-selftest-{}", std::process::id()));
+        let result = selftest::run(&dir).await;
+        let _ = std::fs::remove_dir_all(&dir);
+        match result {
+            Ok(frames) => println!("PASS: {frames} synthetic frames parsed, persisted and read back"),
+            Err(err) => {
+                println!("FAIL: {err:#}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut config = AppConfig::load(&cli.config_path)
         .with_context(|| format!("unable to load configuration from {}", cli.config_path))?;
+    if cli.process_once {
+        config.run_once = true;
+    }
+    if cli.force_reprocess {
+        config.force_reprocess = true;
+    }
+
+    if cli.count_by_day {
+        let sample = cli
+            .replay
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("--count-by-day requires --replay <path>"))?;
+        print!("{}", stats::frames_per_day(sample, &config.dialect).await?);
+        return Ok(());
+    }
 
     let guard = logging::init(&config)?;
     platform::log_platform_guidance();
@@ -116,53 +333,86 @@ async fn main() -> Result<()> {
     std::fs::write(&pid_path, format!("{}", std::process::id()))
         .with_context(|| format!("failed to write pid file {}", pid_path))?;
 
-    // Spawn a task to remove the pid file on SIGINT/SIGTERM (Unix) or ctrl-c (Windows)
-    // and attempt to gracefully shut down child processes by signaling the process group.
-    let pid_path_clone = pid_path.clone();
-    let tmp_dir_clone = tmp_dir.to_string();
-    tokio::spawn(async move {
-        #[cfg(unix)]
-        {
-            use tokio::signal::unix::{signal, SignalKind};
-            use tokio::time::{sleep, Duration};
-            let mut sigint = signal(SignalKind::interrupt()).expect("signal handler");
-            let mut sigterm = signal(SignalKind::terminate()).expect("signal handler");
-            tokio::select! {
-                _ = sigint.recv() => {},
-                _ = sigterm.recv() => {},
+    // A replay drains instead: ctrl-c/SIGTERM lets it finish the frame in hand, flush and
+    // report what it did, rather than killing it mid-line.
+    let replay_stop = if cli.replay.is_some() {
+        let (stop_tx, stop_rx) = tokio::sync::watch::channel(());
+        tokio::spawn(async move {
+            let signal = adcp::service::os_shutdown_signal().await;
+            tracing::warn!(signal, "stopping replay after the current frame");
+            let _ = stop_tx.send(());
+            // A dropped sender reads as "no request", so hold it until the replay is done
+            stop_tx.closed().await;
+        });
+        Some(stop_rx)
+    } else {
+        // Spawn a task to remove the pid file on SIGINT/SIGTERM (Unix) or ctrl-c (Windows)
+        // and attempt to gracefully shut down child processes by signaling the process group.
+        let pid_path_clone = pid_path.clone();
+        let tmp_dir_clone = tmp_dir.to_string();
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            {
+                use tokio::signal::unix::{signal, SignalKind};
+                use tokio::time::{sleep, Duration};
+                let mut sigint = signal(SignalKind::interrupt()).expect("signal handler");
+                let mut sigterm = signal(SignalKind::terminate()).expect("signal handler");
+                tokio::select! {
+                    _ = sigint.recv() => {},
+                    _ = sigterm.recv() => {},
+                }
+                // Remove pid file immediately (best-effort) so tests won't see stale PID
+                let _ = std::fs::remove_file(&pid_path_clone);
+                // Attempt graceful shutdown: send SIGINT to process group
+                let pgid = -(std::process::id() as i32);
+                unsafe { libc::kill(pgid, libc::SIGINT) }; // best-effort
+                // Wait a short while for children to exit
+                sleep(Duration::from_secs(3)).await;
+                // Force kill any remaining processes in the group
+                unsafe { libc::kill(pgid, libc::SIGKILL) };
+
+                // Best-effort: cleanup any leftover adcp-*.pid files in deployment/tmp
+                cleanup_orphans(&tmp_dir_clone).await;
+            }
+            #[cfg(windows)]
+            {
+                // On Windows, best-effort: trigger ctrl-c handler
+                tokio::signal::ctrl_c().await.ok();
+                cleanup_orphans(&tmp_dir_clone).await;
             }
-            // Remove pid file immediately (best-effort) so tests won't see stale PID
             let _ = std::fs::remove_file(&pid_path_clone);
-            // Attempt graceful shutdown: send SIGINT to process group
-            let pgid = -(std::process::id() as i32);
-            unsafe { libc::kill(pgid, libc::SIGINT) }; // best-effort
-            // Wait a short while for children to exit
-            sleep(Duration::from_secs(3)).await;
-            // Force kill any remaining processes in the group
-            unsafe { libc::kill(pgid, libc::SIGKILL) };
-
-            // Best-effort: cleanup any leftover adcp-*.pid files in deployment/tmp
-            cleanup_orphans(&tmp_dir_clone).await;
-        }
-        #[cfg(windows)]
-        {
-            // On Windows, best-effort: trigger ctrl-c handler
-            tokio::signal::ctrl_c().await.ok();
-            cleanup_orphans(&tmp_dir_clone).await;
-        }
-        let _ = std::fs::remove_file(&pid_path_clone);
-    });
+        });
+        None
+    };
 
     if let Some(sample) = cli.replay {
-        let result = simulator::replay_sample(sample, &config).await?;
-        if !result.failures.is_empty() {
-            tracing::warn!("replay encountered {} failures", result.failures.len());
+        let options = simulator::ReplayOptions {
+            max_errors: cli.max_errors,
+            exclude_types: cli.exclude,
+            manifest: config.write_manifest.then(|| std::sync::Arc::new(adcp::manifest::RunManifest::new())),
+            stop: replay_stop,
+            failures_path: None,
+            tag_source_file: false,
+            ..Default::default()
+        };
+        let result = simulator::replay_sample_with_options(sample, &config, &options).await?;
+        if let Some(manifest) = &options.manifest {
+            manifest.write(&config.data_directory)?;
+        }
+        if result.failure_count > 0 {
+            tracing::warn!("replay encountered {} failures", result.failure_count);
+        }
+        if result.interrupted {
+            print!("replay interrupted\n{result}");
         }
         let _ = std::fs::remove_file(&pid_path);
+        if result.aborted {
+            bail!("replay aborted after {} parse errors", result.parse_errors);
+        }
         return Ok(());
     }
 
-    let res = Service::new(config).run().await;
+    let res = Service::new(config).with_config_path(&cli.config_path).run().await;
     // Attempt to remove pid file on exit (best-effort)
     let _ = std::fs::remove_file(&pid_path);
     // Drop the tracing_appender guard to flush logs
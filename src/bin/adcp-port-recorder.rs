@@ -1,24 +1,56 @@
-use adcp::{AppConfig, telemetry::RecorderStats};
+use adcp::{control, metrics::{self, Metrics}, reconfig, serial::RecordSplitter, service::RecordingSinks, AppConfig, telemetry::{PublishQueue, RecorderStats, SensorCache}};
 use busrt::ipc::{Client, Config};
-use busrt::rpc::{Rpc, RpcClient, RpcHandlers, RpcEvent, RpcResult};
+use busrt::rpc::{Rpc, RpcClient, RpcError, RpcHandlers, RpcEvent, RpcResult, RPC_ERROR_CODE_INTERNAL};
 use busrt::QoS;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::time::interval;
 use tokio::signal;
+use tokio::sync::watch;
 use async_trait::async_trait;
-use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tokio_serial::SerialPortBuilderExt;
 use tokio::io::AsyncReadExt;
 
-struct RecorderRpcHandlers;
+/// Longest run of bytes without a record delimiter kept as a pending line; past this the
+/// partial line is dropped (counted in `oversized_lines`) instead of growing without bound.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+struct RecorderRpcHandlers {
+    sensors: Arc<SensorCache>,
+    live: watch::Sender<Arc<AppConfig>>,
+    stop: watch::Sender<()>,
+}
 
 #[async_trait]
 impl RpcHandlers for RecorderRpcHandlers {
-    async fn handle_call(&self, _event: RpcEvent) -> RpcResult {
-        Ok(None)
+    async fn handle_call(&self, event: RpcEvent) -> RpcResult {
+        match event.parse_method() {
+            Ok("cmd.recorder.sensors") => {
+                let json = self.sensors.to_json().map_err(|e| {
+                    RpcError::new(RPC_ERROR_CODE_INTERNAL, Some(e.to_string().as_bytes().to_vec()))
+                })?;
+                Ok(Some(json))
+            }
+            Ok(control::SHUTDOWN_METHOD) => control::handle_shutdown(&self.stop),
+            Ok(_) => Err(RpcError::method(None)),
+            Err(_) => Err(RpcError::new(busrt::rpc::RPC_ERROR_CODE_PARSE, None)),
+        }
     }
     async fn handle_notification(&self, _event: RpcEvent) {}
-    async fn handle_frame(&self, _frame: busrt::Frame) {}
+    async fn handle_frame(&self, frame: busrt::Frame) {
+        if frame.topic() != Some("conf.update") {
+            return;
+        }
+        // Apply what can change without reopening the port; the rest waits for a restart
+        match serde_json::from_slice::<AppConfig>(frame.payload()) {
+            Ok(incoming) => {
+                if let Err(e) = reconfig::apply_update(&self.live, &incoming, reconfig::PORT_RECORDER_RUNTIME_FIELDS) {
+                    eprintln!("Failed to apply config update: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Ignoring malformed config update: {}", e),
+        }
+    }
 }
 
 #[tokio::main]
@@ -35,8 +67,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let bus_config = Config::new("127.0.0.1:7777", &client_name);
     let client = Client::connect(&bus_config).await?;
 
-    let rpc_client = RpcClient::new(client, RecorderRpcHandlers);
+    let sensors = Arc::new(SensorCache::new());
+    let (live, live_rx) = watch::channel(Arc::new(config.clone()));
+    let (stop, mut stop_rx) = watch::channel(());
+    let rpc_client = RpcClient::new(client, RecorderRpcHandlers { sensors: sensors.clone(), live, stop });
     let client = rpc_client.client().clone();
+    client.lock().await.subscribe("conf.update", QoS::Processed).await?;
+
+    // Health monitor follows the live config, so pushed idle thresholds apply immediately
+    let metrics = Arc::new(Metrics::new());
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    let health_handle = tokio::spawn(metrics::monitor_health(
+        Arc::new(client_name.clone()),
+        metrics.clone(),
+        shutdown_rx,
+        live_rx.clone(),
+    ));
 
     // 3. Shared Stats
     let stats = Arc::new(Mutex::new(RecorderStats::default()));
@@ -45,12 +91,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         s.port_name = port_name.clone();
     }
 
-    // 4. Reporting Loop
-    let stats_clone = stats.clone();
+    // 4. Publisher: a single task drains a bounded queue so a slow broker drops stale
+    // messages instead of stalling the recorder
+    let publish_queue = Arc::new(PublishQueue::<(String, Vec<u8>)>::new(config.bus_publish_queue_depth));
+    let queue_clone = publish_queue.clone();
     let client_clone = client.clone();
-    let port_name_clone = port_name.clone();
+    // Stops the publisher between messages, so one it already took is still sent
+    let (publisher_stop, mut publisher_stop_rx) = watch::channel(());
+    let publisher_handle = tokio::spawn(async move {
+        loop {
+            let (topic, payload) = tokio::select! {
+                biased;
+                _ = publisher_stop_rx.changed() => break,
+                item = queue_clone.next() => item,
+            };
+            let mut c = client_clone.lock().await;
+            if let Err(e) = c.publish(&topic, payload.into(), QoS::No).await {
+                eprintln!("Failed to publish stats: {}", e);
+            }
+        }
+    });
+
+    // 5. Reporting Loop
+    let stats_clone = stats.clone();
+    let queue_clone = publish_queue.clone();
+    // Sanitize port name for topic
+    let stats_topic = format!("stat/recorder/{}", port_name.replace('/', "_"));
+    let topic_clone = stats_topic.clone();
 
-    tokio::spawn(async move {
+    let reporter_handle = tokio::spawn(async move {
         let mut interval = interval(Duration::from_secs(1));
         let start_time = Instant::now();
 
@@ -60,24 +129,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let payload = {
                 let mut s = stats_clone.lock().unwrap();
                 s.uptime_seconds = start_time.elapsed().as_secs();
+                s.publishes_dropped = queue_clone.dropped();
                 serde_json::to_vec(&*s).unwrap_or_default()
             };
 
-            // Sanitize port name for topic
-            let safe_port_name = port_name_clone.replace('/', "_");
-            let topic = format!("stat/recorder/{}", safe_port_name);
-
-            let mut c = client_clone.lock().await;
-            if let Err(e) = c.publish(&topic, payload.into(), QoS::No).await {
-                eprintln!("Failed to publish stats: {}", e);
-            }
+            queue_clone.push((topic_clone.clone(), payload));
         }
     });
 
+    // Lines go through the same backup, parsing, validation and persistence as `adcp` recording
+    let sinks = RecordingSinks::new(&config).await?.with_sensor_cache(sensors.clone());
+
     println!("Starting recorder on port: {}", port_name);
     let baud_rate = config.baud_rate;
 
-    // 5. Data Acquisition Loop with tokio-serial
+    // 6. Data Acquisition Loop with tokio-serial
     // We attempt to open the port. If it fails (e.g. no device), we log and maybe retry or exit.
     // For this implementation task, we implement the real logic.
     // In a test environment without the device, this will fail.
@@ -93,6 +159,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let start_time = Instant::now();
             let mut last_second = start_time;
             let mut bytes_in_second = 0;
+            let mut splitter = RecordSplitter::new(config.record_delimiter, MAX_LINE_BYTES);
 
             loop {
                 tokio::select! {
@@ -115,8 +182,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     last_second = Instant::now();
                                 }
 
-                                // Here we would write to disk (dual write)
-                                // Stubbing the write part for simplicity as requested "Implement using tokio-serial" refers to reading.
+                                for line in splitter.push(&buf[..n]) {
+                                    let live = live_rx.borrow().clone();
+                                    sinks.record_line(&line, &live, &metrics).await;
+                                }
+                                let oversized = splitter.take_oversized();
+                                for _ in 0..oversized {
+                                    metrics.record_parse_error();
+                                }
+                                if oversized > 0 {
+                                    stats.lock().unwrap().oversized_lines += oversized;
+                                }
                             }
                             Ok(_) => {
                                 // EOF
@@ -136,6 +212,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("Recorder stopping (signal)...");
                         break;
                     }
+                    _ = control::shutdown_requested(&mut stop_rx) => {
+                        println!("Recorder stopping (bus request)...");
+                        break;
+                    }
                 }
             }
         }
@@ -149,6 +229,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("Entering simulation mode.");
                 let stats_clone2 = stats.clone();
                 let mut interval = interval(Duration::from_millis(100));
+                // One listener for the whole loop so a ctrl-c between ticks is not missed
+                let mut ctrl_c = std::pin::pin!(signal::ctrl_c());
                 loop {
                     tokio::select! {
                         _ = interval.tick() => {
@@ -157,7 +239,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             s.bytes_per_second = 1000;
                             s.last_packet_time = Some(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs());
                         }
-                        _ = signal::ctrl_c() => {
+                        _ = &mut ctrl_c => {
+                            println!("Recorder stopping (signal)...");
+                            break;
+                        }
+                        _ = control::shutdown_requested(&mut stop_rx) => {
                             break;
                         }
                     }
@@ -165,10 +251,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             } else {
                 // In production, we might retry loop here.
                 println!("Waiting for shutdown...");
-                signal::ctrl_c().await?;
+                tokio::select! {
+                    res = signal::ctrl_c() => res?,
+                    _ = control::shutdown_requested(&mut stop_rx) => {}
+                }
             }
         }
     }
 
+    sinks.close(&config).await;
+
+    // Flush what is still queued, then tell monitors this recorder is gone
+    reporter_handle.abort();
+    publisher_stop.send(()).ok();
+    publisher_handle.await.ok();
+    let terminal = {
+        let mut s = stats.lock().unwrap();
+        s.publishes_dropped = publish_queue.dropped();
+        serde_json::to_vec(&s.terminal()).unwrap_or_default()
+    };
+    {
+        let mut c = client.lock().await;
+        for (topic, payload) in publish_queue.drain() {
+            if let Err(e) = c.publish(&topic, payload.into(), QoS::No).await {
+                eprintln!("Failed to publish stats: {}", e);
+            }
+        }
+        match c.publish(&stats_topic, terminal.into(), QoS::Processed).await {
+            Ok(Some(confirm)) => {
+                confirm.await.ok();
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to publish terminal stats: {}", e),
+        }
+    }
+
+    shutdown_tx.send(()).ok();
+    health_handle.await??;
+
     Ok(())
 }
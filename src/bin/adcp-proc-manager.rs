@@ -1,20 +1,43 @@
-use adcp::{simulator, AppConfig};
-use busrt::client::AsyncClient;
+use adcp::{control, processing, simulator, AppConfig};
 use busrt::ipc::{Client, Config};
-use busrt::rpc::{RpcClient, RpcEvent, RpcHandlers, RpcResult};
-use std::sync::{Arc, Mutex};
+use busrt::rpc::{RpcClient, RpcError, RpcEvent, RpcHandlers, RpcResult, RPC_ERROR_CODE_INTERNAL};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
 use async_trait::async_trait;
 use std::fs;
 use std::path::{Path, PathBuf};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
 
-struct ProcHandlers;
+struct ProcHandlers {
+    processed_folder: PathBuf,
+    stop: watch::Sender<()>,
+}
 
 #[async_trait]
 impl RpcHandlers for ProcHandlers {
-    async fn handle_call(&self, _event: RpcEvent) -> RpcResult {
-        Ok(None)
+    async fn handle_call(&self, event: RpcEvent) -> RpcResult {
+        let method = event
+            .parse_method()
+            .map_err(|_| RpcError::new(busrt::rpc::RPC_ERROR_CODE_PARSE, None))?;
+        let (name, query) = method.split_once('?').unwrap_or((method, ""));
+        match name {
+            // cmd.data.list[?since=<unix_ts>]: processed files, optionally only newer ones
+            "cmd.data.list" => {
+                let since = processing::parse_list_query(query).map_err(|e| {
+                    RpcError::params(Some(e.to_string().into_bytes()))
+                })?;
+                let files = processing::list_processed(&self.processed_folder, since)
+                    .await
+                    .map_err(|e| RpcError::new(RPC_ERROR_CODE_INTERNAL, Some(e.to_string().into_bytes())))?;
+                let json = serde_json::to_vec(&files)
+                    .map_err(|e| RpcError::new(RPC_ERROR_CODE_INTERNAL, Some(e.to_string().into_bytes())))?;
+                Ok(Some(json))
+            }
+            control::SHUTDOWN_METHOD => control::handle_shutdown(&self.stop),
+            _ => Err(RpcError::method(None)),
+        }
     }
     async fn handle_notification(&self, _event: RpcEvent) {}
     async fn handle_frame(&self, _frame: busrt::Frame) {}
@@ -35,7 +58,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let bus_config = Config::new("127.0.0.1:7777", &name);
     let client = Client::connect(&bus_config).await?;
 
-    let _rpc_client = RpcClient::new(client, ProcHandlers);
+    let (stop, mut stop_rx) = watch::channel(());
+    let _rpc_client = RpcClient::new(
+        client,
+        ProcHandlers {
+            processed_folder: PathBuf::from(&config.processed_folder),
+            stop,
+        },
+    );
 
     println!("Processing Manager started");
     println!("Watching: {}", config.data_process_folder);
@@ -46,36 +76,57 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir_all(&processed_folder)?;
 
     let stability_sec = config.file_stability_seconds;
+    let permits = processing::replay_permits(&config);
+    println!("Processing concurrency: {}", permits.available_permits());
 
     // Watch Loop
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(2));
         loop {
             interval.tick().await;
-            if let Err(e) = scan_and_process(&process_folder, &processed_folder, stability_sec, &app_config).await {
+            if let Err(e) = scan_and_process(&process_folder, &processed_folder, stability_sec, &app_config, &permits).await {
                 eprintln!("Processing scan error: {}", e);
             }
         }
     });
 
-    signal::ctrl_c().await?;
+    tokio::select! {
+        res = signal::ctrl_c() => res?,
+        _ = control::shutdown_requested(&mut stop_rx) => {}
+    }
     println!("Processing Manager stopping...");
 
     Ok(())
 }
 
-async fn scan_and_process(src: &Path, dst: &Path, stability_sec: u64, config: &AppConfig) -> std::io::Result<()> {
-    let mut entries = fs::read_dir(src)?;
+async fn scan_and_process(
+    src: &Path,
+    dst: &Path,
+    stability_sec: u64,
+    config: &Arc<AppConfig>,
+    permits: &Arc<Semaphore>,
+) -> std::io::Result<()> {
+    processing::recover_stuck_claims(src, config.processing_stuck_seconds).await;
+    let entries = fs::read_dir(src)?;
     let now = std::time::SystemTime::now();
-
-    while let Some(entry) = entries.next() {
-        let entry = entry?;
+    let mut workers = JoinSet::new();
+    // Errors are held until every spawned replay has finished: returning early would drop
+    // the JoinSet and abort replays halfway through their files
+    let mut first_error: Option<std::io::Error> = None;
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                first_error = Some(e);
+                break;
+            }
+        };
         let path = entry.path();
 
         if path.is_file() {
-            // Check if .writing marker exists
             let file_name = path.file_name().unwrap().to_string_lossy();
-            if file_name.ends_with(".writing") {
+            if !processing::is_replay_candidate(&file_name) {
                 continue;
             }
 
@@ -86,30 +137,66 @@ async fn scan_and_process(src: &Path, dst: &Path, stability_sec: u64, config: &A
             }
 
             // Check stability (mtime)
-            let metadata = fs::metadata(&path)?;
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    eprintln!("Failed to stat {:?}: {}", path, e);
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            };
             if let Ok(mtime) = metadata.modified() {
                 if let Ok(age) = now.duration_since(mtime) {
                     if age.as_secs() >= stability_sec {
-                        println!("Processing file: {:?}", path);
-
-                        match simulator::replay_sample(&path, config).await {
-                            Ok(_) => {
-                                println!("Processing successful.");
-                                let dest_path = dst.join(path.file_name().unwrap());
-                                fs::rename(&path, &dest_path)?;
-                                println!("Moved to: {:?}", dest_path);
-                            }
-                            Err(e) => {
-                                eprintln!("Processing failed for {:?}: {}", path, e);
-                                let dest_path = dst.join(format!("{}.failed", path.file_name().unwrap().to_string_lossy()));
-                                fs::rename(&path, &dest_path)?;
-                                println!("Moved to: {:?}", dest_path);
-                            }
-                        }
+                        // Bound the number of concurrent replays to the configured pool size
+                        let permit = permits.clone().acquire_owned().await.expect("replay semaphore closed");
+                        let config = config.clone();
+                        let dst = dst.to_path_buf();
+                        workers.spawn(async move {
+                            let _permit = permit;
+                            process_file(&path, &dst, &config).await
+                        });
                     }
                 }
             }
         }
     }
+
+    // Wait for this scan's workers so the next scan does not pick up in-flight files
+    while let Some(joined) = workers.join_next().await {
+        match joined {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                eprintln!("Processing worker failed: {}", e);
+                first_error.get_or_insert(e);
+            }
+            Err(e) => eprintln!("Processing worker panicked: {}", e),
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+async fn process_file(path: &Path, dst: &Path, config: &AppConfig) -> std::io::Result<()> {
+    println!("Processing file: {:?}", path);
+    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+    // Claim the file; if this worker dies, the claim is reverted after processing_stuck_seconds
+    let claimed = path.with_file_name(format!("{}{}", name, processing::PROCESSING_SUFFIX));
+    fs::rename(path, &claimed)?;
+
+    match simulator::replay_sample(&claimed, config).await {
+        Ok(_) => {
+            println!("Processing successful.");
+            let dest_path = processing::processed_destination(dst, &name, &config.processed_naming);
+            fs::rename(&claimed, &dest_path)?;
+            println!("Moved to: {:?}", dest_path);
+        }
+        Err(e) => {
+            eprintln!("Processing failed for {:?}: {}", path, e);
+            let dest_path = processing::processed_destination(dst, &format!("{}.failed", name), &config.processed_naming);
+            fs::rename(&claimed, &dest_path)?;
+            println!("Moved to: {:?}", dest_path);
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,270 @@
+use crate::parser::CurrentSentence;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+
+/// Time constant of the rolling average compared against the baseline.
+const RECENT_WINDOW_HOURS: f64 = 24.0;
+/// Instrument time a beam must cover before its baseline is trusted.
+const WARMUP: Duration = Duration::days(2);
+/// Amplitude count reported by a beam pegged at the top of its range.
+pub const SATURATED_AMPLITUDE: u8 = 255;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct BeamTrend {
+    recent: f64,
+    baseline: f64,
+    first_at: Option<DateTime<Utc>>,
+    last_at: Option<DateTime<Utc>>,
+    suspected: bool,
+}
+
+impl BeamTrend {
+    /// Folds one ensemble's mean amplitude in, weighting by the instrument time since the
+    /// previous ensemble so the averages span hours and days however fast the instrument pings.
+    fn fold(&mut self, at: DateTime<Utc>, amplitude: f64, baseline_hours: f64) {
+        let (Some(first_at), Some(last_at)) = (self.first_at, self.last_at) else {
+            *self = BeamTrend {
+                recent: amplitude,
+                baseline: amplitude,
+                first_at: Some(at),
+                last_at: Some(at),
+                suspected: false,
+            };
+            return;
+        };
+        // Out-of-order timestamps carry no elapsed time
+        let hours = (at - last_at).num_milliseconds().max(0) as f64 / 3_600_000.0;
+        self.recent += (1.0 - (-hours / RECENT_WINDOW_HOURS).exp()) * (amplitude - self.recent);
+        self.baseline += (1.0 - (-hours / baseline_hours).exp()) * (amplitude - self.baseline);
+        self.first_at = Some(first_at.min(at));
+        self.last_at = Some(last_at.max(at));
+    }
+
+    fn warmed_up(&self) -> bool {
+        matches!((self.first_at, self.last_at), (Some(first), Some(last)) if last - first >= WARMUP)
+    }
+}
+
+/// Beam amplitude sums over the cells of the ensemble being received.
+#[derive(Debug, Clone, Copy)]
+struct Ensemble {
+    sent_at: DateTime<Utc>,
+    sums: [f64; 4],
+    cells: [u32; 4],
+}
+
+/// Tracks per-beam `$PNORC` amplitude against its long-term baseline. A sustained drop of the
+/// rolling average below a fraction of the baseline usually means transducer biofouling.
+/// Cells of one ensemble are averaged first, and both averages decay with instrument time:
+/// about a day for the rolling average and `biofouling_baseline_days` for the baseline.
+#[derive(Debug, Default)]
+pub struct AmplitudeTrend {
+    beams: [BeamTrend; 4],
+    ensemble: Option<Ensemble>,
+}
+
+impl AmplitudeTrend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Folds one current sentence into the trend and returns the (1-based) beams whose
+    /// rolling average has just fallen below `alert_fraction` of their baseline. An ensemble
+    /// is evaluated once the first cell of the next one arrives.
+    pub fn observe(&mut self, current: &CurrentSentence, alert_fraction: f64, baseline_days: u64) -> Vec<usize> {
+        let mut newly_suspected = Vec::new();
+        match &mut self.ensemble {
+            Some(ensemble) if ensemble.sent_at == current.sent_at => {
+                ensemble.add(current);
+                return newly_suspected;
+            }
+            _ => {}
+        }
+        let finished = self.ensemble.replace(Ensemble::start(current));
+        let Some(finished) = finished else {
+            return newly_suspected;
+        };
+        let baseline_hours = baseline_days.max(1) as f64 * 24.0;
+        for (idx, beam) in self.beams.iter_mut().enumerate() {
+            if finished.cells[idx] == 0 {
+                continue;
+            }
+            let amplitude = finished.sums[idx] / f64::from(finished.cells[idx]);
+            beam.fold(finished.sent_at, amplitude, baseline_hours);
+            if !beam.warmed_up() || beam.baseline <= 0.0 {
+                continue;
+            }
+            let degraded = beam.recent < beam.baseline * alert_fraction;
+            if degraded && !beam.suspected {
+                newly_suspected.push(idx + 1);
+            }
+            beam.suspected = degraded;
+        }
+        newly_suspected
+    }
+
+    /// True while any beam's rolling average sits below the alert fraction of its baseline.
+    pub fn suspected(&self) -> bool {
+        self.beams.iter().any(|beam| beam.suspected)
+    }
+}
+
+impl Ensemble {
+    fn start(current: &CurrentSentence) -> Self {
+        let mut ensemble = Ensemble { sent_at: current.sent_at, sums: [0.0; 4], cells: [0; 4] };
+        ensemble.add(current);
+        ensemble
+    }
+
+    fn add(&mut self, current: &CurrentSentence) {
+        for (idx, amplitude) in amplitudes(current).into_iter().enumerate() {
+            if let Some(amplitude) = amplitude {
+                self.sums[idx] += f64::from(amplitude);
+                self.cells[idx] += 1;
+            }
+        }
+    }
+}
+
+fn amplitudes(current: &CurrentSentence) -> [Option<u8>; 4] {
+    [
+        current.amplitude_beam_1,
+        current.amplitude_beam_2,
+        current.amplitude_beam_3,
+        current.amplitude_beam_4,
+    ]
+}
+
+/// The (1-based) beams of `current` whose amplitude is pegged at `SATURATED_AMPLITUDE`.
+pub fn pegged_beams(current: &CurrentSentence) -> Vec<usize> {
+    amplitudes(current)
+        .iter()
+        .enumerate()
+        .filter(|(_, amplitude)| **amplitude == Some(SATURATED_AMPLITUDE))
+        .map(|(idx, _)| idx + 1)
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct BeamSaturation {
+    /// Whether each of the last `window` samples was pegged, oldest first.
+    samples: VecDeque<bool>,
+    pegged: usize,
+    saturated: bool,
+}
+
+/// Tracks how often each beam reports a pegged amplitude over the last `window` samples.
+/// A beam stuck at its maximum usually points to a hardware fault or interference.
+#[derive(Debug, Default)]
+pub struct SaturationWindow {
+    beams: [BeamSaturation; 4],
+}
+
+impl SaturationWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Folds one current sentence into the window and returns the (1-based) beams whose
+    /// saturated fraction has just exceeded `alert_fraction`. Nothing is flagged until a beam
+    /// has filled its window.
+    pub fn observe(&mut self, current: &CurrentSentence, window: usize, alert_fraction: f64) -> Vec<usize> {
+        let window = window.max(1);
+        let mut newly_saturated = Vec::new();
+        for (idx, (beam, amplitude)) in self.beams.iter_mut().zip(amplitudes(current)).enumerate() {
+            let Some(amplitude) = amplitude else {
+                continue;
+            };
+            let pegged = amplitude == SATURATED_AMPLITUDE;
+            beam.samples.push_back(pegged);
+            beam.pegged += usize::from(pegged);
+            while beam.samples.len() > window {
+                if beam.samples.pop_front() == Some(true) {
+                    beam.pegged -= 1;
+                }
+            }
+            if beam.samples.len() < window {
+                continue;
+            }
+            let saturated = beam.pegged as f64 / window as f64 > alert_fraction;
+            if saturated && !beam.saturated {
+                newly_saturated.push(idx + 1);
+            }
+            beam.saturated = saturated;
+        }
+        newly_saturated
+    }
+
+    /// True while any beam's saturated fraction exceeds the alert fraction.
+    pub fn saturated(&self) -> bool {
+        self.beams.iter().any(|beam| beam.saturated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Frame, Payload};
+
+    fn current_with_amplitude(amplitude: u8) -> CurrentSentence {
+        let frame = Frame::from_line(
+            "$PNORC,010526,220800,4,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*26",
+        )
+        .expect("parse current");
+        let Payload::Current(mut current) = frame.payload else {
+            panic!("expected current payload");
+        };
+        current.amplitude_beam_1 = Some(amplitude);
+        current.amplitude_beam_2 = Some(120);
+        current
+    }
+
+    /// One ensemble of `cells` cells every ten minutes from 2026-01-05, with beam 1 at
+    /// `amplitude(day)` and the other beams steady.
+    fn ensembles(days: f64, cells: u32, amplitude: impl Fn(f64) -> f64) -> Vec<CurrentSentence> {
+        let template = current_with_amplitude(0);
+        let start = template.sent_at;
+        let mut sentences = Vec::new();
+        for step in 0..(days * 144.0) as i64 {
+            let sent_at = start + Duration::minutes(10 * step);
+            let day = (sent_at - start).num_minutes() as f64 / 1440.0;
+            for cell in 0..cells {
+                let mut current = template.clone();
+                current.amplitude_beam_1 = Some(amplitude(day).round() as u8);
+                current.sent_at = sent_at;
+                current.cell_number = cell as u16 + 1;
+                sentences.push(current);
+            }
+        }
+        sentences
+    }
+
+    #[test]
+    fn slow_decline_over_weeks_flags_biofouling() {
+        // Steady for ten days, then the echo fades by about 1.5 counts a day
+        let decline = |day: f64| if day < 10.0 { 150.0 } else { 150.0 - 1.5 * (day - 10.0) };
+        let mut trend = AmplitudeTrend::new();
+        let mut fired_on = None;
+        for current in ensembles(70.0, 20, decline) {
+            let sent_at = current.sent_at;
+            if !trend.observe(&current, 0.7, 30).is_empty() && fired_on.is_none() {
+                fired_on = Some(sent_at);
+            }
+        }
+        let fired_on = fired_on.expect("fouling flagged");
+        let day = (fired_on - current_with_amplitude(0).sent_at).num_days();
+        assert!((20..60).contains(&day), "flagged on day {day}");
+        assert!(trend.suspected());
+    }
+
+    #[test]
+    fn steady_amplitude_with_daily_swings_is_not_flagged() {
+        // Tides and day/night plankton move the echo without any fouling
+        let swing = |day: f64| 150.0 + 20.0 * (day * std::f64::consts::TAU).sin();
+        let mut trend = AmplitudeTrend::new();
+        for current in ensembles(30.0, 20, swing) {
+            assert!(trend.observe(&current, 0.7, 30).is_empty());
+        }
+        assert!(!trend.suspected());
+    }
+}
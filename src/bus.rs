@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use busrt::client::AsyncClient;
+use busrt::ipc::{Client, Config};
+use busrt::rpc::{Rpc, RpcClient, RpcHandlers};
+use busrt::QoS;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+/// How often a live connection is checked for a broker that went away.
+const LIVENESS_POLL: Duration = Duration::from_millis(250);
+
+/// First pause between connection attempts; doubles up to `BusLink::with_max_backoff`.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Connection state of a `BusLink`, e.g. for a "reconnecting" banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    /// No connection has been made yet.
+    Connecting,
+    Connected,
+    /// The broker went away; retrying with backoff.
+    Reconnecting,
+}
+
+/// A bus client connection that is re-established, with its subscriptions renewed, after the
+/// broker restarts. Each connection takes fresh handlers, as `RpcClient` owns them.
+pub struct BusLink {
+    path: String,
+    name: String,
+    topics: Vec<String>,
+    max_backoff: Duration,
+    state: watch::Sender<LinkState>,
+}
+
+impl BusLink {
+    pub fn new(path: &str, name: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            name: name.to_string(),
+            topics: Vec::new(),
+            max_backoff: Duration::from_secs(10),
+            state: watch::channel(LinkState::Connecting).0,
+        }
+    }
+
+    /// Subscribes every connection to `topic`. Repeats are ignored, so a topic is never
+    /// subscribed twice on one connection.
+    pub fn with_topic(mut self, topic: &str) -> Self {
+        if !self.topics.iter().any(|t| t == topic) {
+            self.topics.push(topic.to_string());
+        }
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn state(&self) -> watch::Receiver<LinkState> {
+        self.state.subscribe()
+    }
+
+    /// Makes one connection attempt, returning once the broker has confirmed every
+    /// subscription so no publish after it can be missed.
+    pub async fn connect<H>(&self, handlers: H) -> Result<RpcClient>
+    where
+        H: RpcHandlers + Send + Sync + 'static,
+    {
+        let mut client = Client::connect(&Config::new(&self.path, &self.name))
+            .await
+            .with_context(|| format!("connect to bus at {}", self.path))?;
+        for topic in &self.topics {
+            let confirm = client
+                .subscribe(topic, QoS::Processed)
+                .await
+                .with_context(|| format!("subscribe to {topic}"))?;
+            if let Some(confirm) = confirm {
+                confirm
+                    .await
+                    .context("bus closed before confirming subscription")?
+                    .with_context(|| format!("subscribe to {topic}"))?;
+            }
+        }
+        Ok(RpcClient::new(client, handlers))
+    }
+
+    /// Connects, retrying with doubling backoff until the broker is reachable, and marks the
+    /// link connected.
+    pub async fn connect_with_retry<H>(&self, make_handlers: impl Fn() -> H) -> RpcClient
+    where
+        H: RpcHandlers + Send + Sync + 'static,
+    {
+        let mut backoff = INITIAL_BACKOFF.min(self.max_backoff);
+        loop {
+            match self.connect(make_handlers()).await {
+                Ok(rpc) => {
+                    self.state.send_replace(LinkState::Connected);
+                    return rpc;
+                }
+                Err(err) => {
+                    tracing::warn!(error = %err, retry_in = ?backoff, "bus connection failed");
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                }
+            }
+        }
+    }
+
+    /// Resolves once `rpc` has lost the broker, marking the link as reconnecting.
+    pub async fn disconnected(&self, rpc: &RpcClient) {
+        while rpc.is_connected() {
+            sleep(LIVENESS_POLL).await;
+        }
+        tracing::warn!(bus = %self.path, "bus connection lost");
+        self.state.send_replace(LinkState::Reconnecting);
+    }
+}
@@ -1,8 +1,14 @@
-use anyhow::{Context, Result};
+use crate::parser::DialectConfig;
+use crate::validation::ValidationRule;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub enum ServiceMode {
     Recording,
     Processing,
@@ -10,27 +16,144 @@ pub enum ServiceMode {
     Simulator,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Period each persisted log covers, from the frame timestamps (UTC).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum SplitMode {
+    /// `adcp-%Y-%m-%dT%H.log`
+    Hourly,
+    /// `adcp-%Y-%m-%d.log`, or `filename_date_format`
     Daily,
+    /// `adcp-%G-W%V.log`, ISO weeks starting on Monday
     Weekly,
+    /// `adcp-%Y-%m.log`
+    Monthly,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl SplitMode {
+    /// Start of the period containing `at`; frames rotate to a new file when it changes.
+    pub fn bucket_start(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        let date = at.date_naive();
+        match self {
+            SplitMode::Hourly => date.and_hms_opt(at.hour(), 0, 0).expect("hour of a valid time"),
+            SplitMode::Daily => date.and_time(NaiveTime::MIN),
+            SplitMode::Weekly => {
+                let monday = date - Duration::days(i64::from(date.weekday().num_days_from_monday()));
+                monday.and_time(NaiveTime::MIN)
+            }
+            SplitMode::Monthly => date.with_day(1).expect("every month has a first day").and_time(NaiveTime::MIN),
+        }
+    }
+
+    /// strftime pattern naming a period's file; `daily` is the pattern used in daily mode.
+    pub fn file_pattern<'a>(&self, daily: &'a str) -> &'a str {
+        match self {
+            SplitMode::Hourly => "%Y-%m-%dT%H",
+            SplitMode::Daily => daily,
+            SplitMode::Weekly => "%G-W%V",
+            SplitMode::Monthly => "%Y-%m",
+        }
+    }
+}
+
+/// Encoding of persisted frame logs.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum OutputFormat {
+    /// One JSON frame per line in `adcp-<date>.log`.
+    Json,
+    /// One file per sentence type, `adcp-<type>-<date>.csv`, each with its own header row.
+    Csv,
+}
+
+/// Daily window (UTC, like file rotation) during which idle alerts are suppressed, e.g. while
+/// the instrument is powered off overnight. `start` after `end` wraps past midnight.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// How processed files are named in `processed_folder`, so same-named sources from different
+/// runs do not overwrite each other.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum ProcessedNaming {
+    /// Keep the source name; a later file with the same name replaces the earlier one.
+    Original,
+    /// Prefix the name with the processing time.
+    Timestamped,
+    /// Keep the source name, appending `_1`, `_2`, ... on collision.
+    Sequential,
+}
+
+/// How often the service's own tracing log file (not the data files) starts a new file.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum TracingRotation {
+    Daily,
+    Hourly,
+    /// One ever-growing file, e.g. for short test runs.
+    Never,
+}
+
+/// What every retention sweep does with files it expires.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum RetentionAction {
+    Delete,
+    /// Move expired files into this directory instead, for deployments that may not delete data.
+    Archive(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct AppConfig {
     pub service_name: String,
     #[serde(default = "default_log_level")]
     pub log_level: String,
+    #[serde(default = "default_tracing_rotation")]
+    pub tracing_rotation: TracingRotation,
     #[serde(default = "default_data_dir")]
     pub data_directory: String,
+    /// Directory on a second disk that recording switches the primary frame log to after
+    /// repeated out-of-space errors in `data_directory`.
+    #[serde(default)]
+    pub fallback_data_directory: Option<String>,
+    /// Serial device, FIFO, capture file, or `tcp://host:port` terminal server to record from.
     #[serde(default)]
     pub serial_port: Option<String>,
     #[serde(default = "default_baud_rate")]
     pub baud_rate: u32,
+    /// Byte that ends each record on the serial stream; instruments framing records with a
+    /// control byte (e.g. `3` for ETX) set it instead of newline.
+    #[serde(default = "default_record_delimiter")]
+    pub record_delimiter: u8,
+    /// strftime pattern for the date in persisted log names (`adcp-{date}.log`), e.g.
+    /// `%Y%m%d` to match an existing archive's naming.
+    #[serde(default = "default_filename_date_format")]
+    pub filename_date_format: String,
+    /// How long recording waits for a writer to send data when `serial_port` is a FIFO.
+    #[serde(default = "default_fifo_open_timeout_secs")]
+    pub fifo_open_timeout_seconds: u64,
+    /// How long recording waits for a `tcp://` terminal server to accept the connection.
+    #[serde(default = "default_tcp_connect_timeout_secs")]
+    pub tcp_connect_timeout_seconds: u64,
+    /// Longest wait between attempts to reopen a serial port or FIFO that closed or failed;
+    /// the wait doubles from one second up to this cap.
+    #[serde(default = "default_reconnect_max_backoff_secs")]
+    pub reconnect_max_backoff_seconds: u64,
     #[serde(default = "default_idle_threshold_secs")]
     pub idle_threshold_seconds: u64,
     #[serde(default)]
     pub alert_webhook: Option<String>,
+    /// Suppress idle alerts inside this daily window.
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
     #[serde(default = "default_mode")]
     pub mode: ServiceMode,
     #[serde(default = "default_backup_folder")]
@@ -39,13 +162,289 @@ pub struct AppConfig {
     pub data_process_folder: String,
     #[serde(default = "default_processed_folder")]
     pub processed_folder: String,
+    #[serde(default = "default_processed_naming")]
+    pub processed_naming: ProcessedNaming,
     #[serde(default = "default_split_mode")]
     pub split_mode: SplitMode,
+    /// Encoding of the frame logs in `data_directory`; quarantine and rejected logs stay as they are.
+    #[serde(default = "default_output_format")]
+    pub output_format: OutputFormat,
     pub max_backup_files: Option<usize>,
     pub max_backup_age_days: Option<u64>,
+    /// Keep at most this many dated logs per prefix (`adcp`, `quarantine`, `rejected`) in
+    /// `data_directory`; older ones go through `retention_action` when a new day opens.
+    pub max_data_files: Option<usize>,
+    /// Expire dated logs in `data_directory` older than this many days.
+    pub max_data_age_days: Option<u64>,
+    /// Persist frames under `data_directory/<head_id>/`, following the head_id of the latest
+    /// `$PNORI`, for several instruments interleaved on one port.
+    #[serde(default)]
+    pub split_by_head_id: bool,
+    /// Gzip each day's backup `.raw` to `.raw.gz` once the backup rolls to the next day, in
+    /// the background. Also accepted as `compress_rotated_backups`.
+    #[serde(default, alias = "compress_rotated_backups")]
+    pub compress_backups: bool,
+    /// Fsync the directory after persistence or a backup opens a new file, so the new entry
+    /// survives a crash right after rotation (Unix only).
+    #[serde(default)]
+    pub fsync_dir_on_rotate: bool,
+    /// Suffix per-append processing files with the recorder's start time so restarts never
+    /// append into a file left over from an earlier run.
+    #[serde(default)]
+    pub data_process_run_id: bool,
+    /// Touch a `.writing` marker next to the `backup_folder` file on every append, for
+    /// external tools watching the backups.
+    #[serde(default)]
+    pub backup_writer_markers: bool,
+    /// Touch a `.writing` marker next to the `data_process_folder` file on every append.
+    /// Disable when consumers rely on atomic renames instead.
+    #[serde(default = "default_data_process_writer_markers")]
+    pub data_process_writer_markers: bool,
     #[serde(default = "default_file_stability_secs")]
     pub file_stability_seconds: u64,
+    /// A `.processing` claim untouched this long is treated as left by a crashed worker and
+    /// renamed back so the file is retried.
+    #[serde(default = "default_processing_stuck_secs")]
+    pub processing_stuck_seconds: u64,
+    /// Processing writes a `{name}.qc.json` data-quality summary next to each processed file.
+    #[serde(default)]
+    pub write_qc_summary: bool,
+    /// Times the orchestrator restarts a job with a stale heartbeat before giving up on it,
+    /// so a misconfigured child (e.g. a missing `sample_file`) does not crash-loop. 0 = no cap.
+    #[serde(default = "default_watchdog_max_restarts")]
+    pub watchdog_max_restarts: u32,
+    /// Orchestrator mode: the adcp binary spawned for the simulator, recorder and processor.
+    #[serde(default = "default_child_binary_path")]
+    pub child_binary_path: String,
     pub sample_file: Option<String>,
+    /// Simulator playback rate: gaps between capture timestamps are divided by this factor.
+    #[serde(default = "default_replay_speed")]
+    pub replay_speed: f64,
+    /// Simulator forwards only lines that parse, logging the rejects, instead of writing the
+    /// capture verbatim (e.g. to feed a downstream system in hardware-in-the-loop tests).
+    #[serde(default)]
+    pub simulator_filter_invalid: bool,
+    /// Consecutive failed scans of `data_process_folder` before the processor raises an alert.
+    #[serde(default = "default_read_dir_failure_threshold")]
+    pub read_dir_failure_threshold: u32,
+    /// Stop the processing loop with an error once the failure threshold is reached,
+    /// so an external supervisor can restart it.
+    #[serde(default)]
+    pub exit_on_read_dir_failure: bool,
+    /// Later scans that retry a file whose replay failed before it is renamed `.failed`.
+    #[serde(default = "default_max_processing_retries")]
+    pub max_processing_retries: u32,
+    /// Site-specific QC bounds applied to every parsed frame.
+    #[serde(default)]
+    pub validation_rules: Vec<ValidationRule>,
+    /// Persist the host arrival time as `ingested_at` next to `recorded_at`.
+    #[serde(default)]
+    pub include_ingested_at: bool,
+    /// Skip frames whose `sent_at` is more than this many days before host time.
+    #[serde(default)]
+    pub max_frame_age_days: Option<u64>,
+    /// Skip frames whose `sent_at` is less than this many days before host time.
+    #[serde(default)]
+    pub min_frame_age_days: Option<u64>,
+    /// Time every successful parse and report per-sentence histograms in the health heartbeat.
+    #[serde(default)]
+    pub profile_parsing: bool,
+    /// Instrument type code (`4` = Signature) the `$PNORI` frames on this port must report.
+    #[serde(default)]
+    pub expected_instrument_type: Option<u8>,
+    /// Route frames that violate a validation rule to `quarantine-<date>.log` instead of the main log.
+    #[serde(default)]
+    pub quarantine_invalid_frames: bool,
+    /// Append lines the parser rejects during recording to `rejected-<date>.log` for auditing.
+    #[serde(default)]
+    pub persist_rejected: bool,
+    /// Maximum number of files the processing manager replays at once (defaults to the CPU count).
+    #[serde(default = "default_processing_concurrency")]
+    pub processing_concurrency: usize,
+    /// Consecutive empty/whitespace-only serial lines tolerated within the window before warning.
+    #[serde(default = "default_empty_line_threshold")]
+    pub empty_line_threshold: u32,
+    #[serde(default = "default_empty_line_window_secs")]
+    pub empty_line_window_seconds: u64,
+    /// Consecutive lines not starting with `$` after cleanly framed data before a serial
+    /// buffer overrun is suspected; 0 disables the check.
+    #[serde(default = "default_overrun_burst_lines")]
+    pub overrun_burst_lines: u32,
+    /// Consecutive timestamped frames sharing one `sent_at` before the instrument clock is
+    /// reported frozen. `$PNORC` cells of one ensemble share a timestamp, so keep this well
+    /// above the cell count.
+    #[serde(default = "default_frozen_clock_threshold")]
+    pub frozen_clock_threshold: u32,
+    /// Ensembles whose deepest reported cell falls below this fraction of the `$PNORI` cell
+    /// count are counted as range-limited (e.g. low backscatter).
+    #[serde(default = "default_min_cells_fraction")]
+    pub min_cells_fraction: f64,
+    /// Consecutive range-limited ensembles before `range_degraded` is flagged.
+    #[serde(default = "default_range_degraded_ensembles")]
+    pub range_degraded_ensembles: u32,
+    /// Seconds of instrument time without a `$PNORI` before the cell-count checks report
+    /// themselves disabled (the instrument started before the recorder connected). 0 never warns.
+    #[serde(default = "default_config_frame_grace_seconds")]
+    pub config_frame_grace_seconds: u64,
+    /// Flag suspected biofouling when a beam's rolling amplitude falls below this fraction of its baseline.
+    #[serde(default = "default_biofouling_alert_fraction")]
+    pub biofouling_alert_fraction: f64,
+    /// Days of instrument time the biofouling baseline averages over; fouling that builds up
+    /// faster than this shows as a drop below it.
+    #[serde(default = "default_biofouling_baseline_days")]
+    pub biofouling_baseline_days: u64,
+    /// Recent `$PNORC` samples per beam over which pegged (255) amplitudes are counted.
+    #[serde(default = "default_saturation_window")]
+    pub saturation_window: usize,
+    /// Flag `amplitude_saturated` when more than this fraction of a beam's window is pegged.
+    #[serde(default = "default_saturation_alert_fraction")]
+    pub saturation_alert_fraction: f64,
+    /// Scale `$PNORC` velocities by the latest `$PNORS` sound speed over
+    /// `reference_sound_speed_m_s` before persisting.
+    #[serde(default)]
+    pub sound_speed_correction: bool,
+    /// Sound speed (m/s) the instrument assumes when computing velocities.
+    #[serde(default = "default_reference_sound_speed")]
+    pub reference_sound_speed_m_s: f32,
+    /// Rotate `$PNORC` velocities reported in XYZ or BEAM coordinates into ENU during replay.
+    #[serde(default)]
+    pub convert_to_enu: bool,
+    /// Undated frames (e.g. `$PNORI`) waiting this long for a dated frame are written to a host-dated file.
+    #[serde(default = "default_pending_flush_secs")]
+    pub pending_flush_seconds: u64,
+    /// Fsync the open frame logs once no frame has been written for this many seconds, so the
+    /// tail of a burst reaches the disk promptly when the instrument goes quiet.
+    #[serde(default)]
+    pub sync_on_idle_seconds: Option<u64>,
+    /// Messages the recorder buffers for the bus before dropping the oldest.
+    #[serde(default = "default_bus_publish_queue_depth")]
+    pub bus_publish_queue_depth: usize,
+    /// Field delimiter, checksum notation, sentinels and field limits of the feed; defaults to
+    /// standard NMEA.
+    #[serde(default)]
+    pub dialect: DialectConfig,
+    /// Processing makes one pass over `data_process_folder` and exits instead of polling forever.
+    #[serde(default)]
+    pub run_once: bool,
+    /// Processing ignores the watermark of already-processed files and replays everything
+    /// in `data_process_folder`.
+    #[serde(default)]
+    pub force_reprocess: bool,
+    /// Bytes of serial lines the recorder may hold while persistence catches up; lines arriving
+    /// beyond this budget are dropped and counted so the reader never stalls.
+    #[serde(default = "default_max_inflight_bytes")]
+    pub max_inflight_bytes: usize,
+    /// StatsD collector (`host:port`) the health monitor pushes frame/error counters and idle
+    /// time to over UDP every heartbeat.
+    #[serde(default)]
+    pub statsd_address: Option<String>,
+    /// Address (`host:port`) of an HTTP endpoint serving `GET /health` as JSON in recording
+    /// and processing modes: 200 while frames keep arriving, 503 once the last one is older
+    /// than `idle_threshold_seconds`.
+    #[serde(default)]
+    pub metrics_listen_addr: Option<String>,
+    /// Write `manifest-{run_ts}.json` into `data_directory` at shutdown, listing every
+    /// persisted, backup and processed file the run touched with its line/frame count.
+    #[serde(default)]
+    pub write_manifest: bool,
+    /// Whether retention sweeps delete expired files or move them to an archive directory.
+    #[serde(default = "default_retention_action")]
+    pub retention_action: RetentionAction,
+}
+
+/// The configuration an empty file (apart from `service_name`) loads as.
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            service_name: "adcp-supervisor".to_string(),
+            log_level: default_log_level(),
+            tracing_rotation: default_tracing_rotation(),
+            data_directory: default_data_dir(),
+            fallback_data_directory: None,
+            serial_port: None,
+            baud_rate: default_baud_rate(),
+            record_delimiter: default_record_delimiter(),
+            filename_date_format: default_filename_date_format(),
+            fifo_open_timeout_seconds: default_fifo_open_timeout_secs(),
+            tcp_connect_timeout_seconds: default_tcp_connect_timeout_secs(),
+            reconnect_max_backoff_seconds: default_reconnect_max_backoff_secs(),
+            idle_threshold_seconds: default_idle_threshold_secs(),
+            alert_webhook: None,
+            quiet_hours: None,
+            mode: default_mode(),
+            backup_folder: default_backup_folder(),
+            data_process_folder: default_data_process_folder(),
+            processed_folder: default_processed_folder(),
+            processed_naming: default_processed_naming(),
+            split_mode: default_split_mode(),
+            output_format: default_output_format(),
+            max_backup_files: None,
+            max_backup_age_days: None,
+            max_data_files: None,
+            max_data_age_days: None,
+            split_by_head_id: false,
+            compress_backups: false,
+            fsync_dir_on_rotate: false,
+            data_process_run_id: false,
+            backup_writer_markers: false,
+            data_process_writer_markers: default_data_process_writer_markers(),
+            file_stability_seconds: default_file_stability_secs(),
+            processing_stuck_seconds: default_processing_stuck_secs(),
+            write_qc_summary: false,
+            watchdog_max_restarts: default_watchdog_max_restarts(),
+            child_binary_path: default_child_binary_path(),
+            sample_file: None,
+            replay_speed: default_replay_speed(),
+            simulator_filter_invalid: false,
+            read_dir_failure_threshold: default_read_dir_failure_threshold(),
+            exit_on_read_dir_failure: false,
+            max_processing_retries: default_max_processing_retries(),
+            validation_rules: Vec::new(),
+            include_ingested_at: false,
+            max_frame_age_days: None,
+            min_frame_age_days: None,
+            profile_parsing: false,
+            expected_instrument_type: None,
+            quarantine_invalid_frames: false,
+            persist_rejected: false,
+            processing_concurrency: default_processing_concurrency(),
+            empty_line_threshold: default_empty_line_threshold(),
+            empty_line_window_seconds: default_empty_line_window_secs(),
+            overrun_burst_lines: default_overrun_burst_lines(),
+            frozen_clock_threshold: default_frozen_clock_threshold(),
+            min_cells_fraction: default_min_cells_fraction(),
+            range_degraded_ensembles: default_range_degraded_ensembles(),
+            config_frame_grace_seconds: default_config_frame_grace_seconds(),
+            biofouling_alert_fraction: default_biofouling_alert_fraction(),
+            biofouling_baseline_days: default_biofouling_baseline_days(),
+            saturation_window: default_saturation_window(),
+            saturation_alert_fraction: default_saturation_alert_fraction(),
+            sound_speed_correction: false,
+            reference_sound_speed_m_s: default_reference_sound_speed(),
+            convert_to_enu: false,
+            pending_flush_seconds: default_pending_flush_secs(),
+            sync_on_idle_seconds: None,
+            bus_publish_queue_depth: default_bus_publish_queue_depth(),
+            dialect: DialectConfig::default(),
+            run_once: false,
+            force_reprocess: false,
+            max_inflight_bytes: default_max_inflight_bytes(),
+            statsd_address: None,
+            metrics_listen_addr: None,
+            write_manifest: false,
+            retention_action: default_retention_action(),
+        }
+    }
+}
+
+/// Serial speeds instruments and adapters commonly support; others still work but are flagged.
+const STANDARD_BAUD_RATES: &[u32] = &[
+    300, 600, 1200, 2400, 4800, 9600, 14400, 19200, 38400, 57600, 115200, 230400, 460800, 921600,
+    1_000_000, 1_250_000,
+];
+
+fn default_tracing_rotation() -> TracingRotation {
+    TracingRotation::Daily
 }
 
 fn default_log_level() -> String {
@@ -64,6 +463,18 @@ fn default_baud_rate() -> u32 {
     115200
 }
 
+fn default_fifo_open_timeout_secs() -> u64 {
+    30
+}
+
+fn default_tcp_connect_timeout_secs() -> u64 {
+    30
+}
+
+fn default_reconnect_max_backoff_secs() -> u64 {
+    60
+}
+
 fn default_idle_threshold_secs() -> u64 {
     30
 }
@@ -84,10 +495,140 @@ fn default_processed_folder() -> String {
     "./deployment/processed".to_string()
 }
 
+fn default_processed_naming() -> ProcessedNaming {
+    ProcessedNaming::Original
+}
+
+fn default_retention_action() -> RetentionAction {
+    RetentionAction::Delete
+}
+
 fn default_split_mode() -> SplitMode {
     SplitMode::Daily
 }
 
+fn default_output_format() -> OutputFormat {
+    OutputFormat::Json
+}
+
+fn default_data_process_writer_markers() -> bool {
+    true
+}
+
+fn default_record_delimiter() -> u8 {
+    b'\n'
+}
+
+fn default_filename_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+/// Rejects a `filename_date_format` that chrono cannot render, that would put a path
+/// separator into file names, or that loses the date so retention could not read it back.
+fn check_filename_date_format(pattern: &str) -> Result<()> {
+    use chrono::format::{Item, StrftimeItems};
+    if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+        bail!("'{pattern}' is not a valid strftime pattern");
+    }
+    let sample = NaiveDate::from_ymd_opt(2026, 1, 5).expect("valid sample date");
+    let rendered = sample.format(pattern).to_string();
+    if rendered.contains(['/', '\\']) || rendered.contains("..") {
+        bail!("'{pattern}' renders '{rendered}', which is not a plain file name");
+    }
+    if NaiveDate::parse_from_str(&rendered, pattern).ok() != Some(sample) {
+        bail!("'{pattern}' must identify the day (year, month and day)");
+    }
+    Ok(())
+}
+
+fn default_watchdog_max_restarts() -> u32 {
+    5
+}
+
+fn default_child_binary_path() -> String {
+    "./target/release/adcp".to_string()
+}
+
+fn default_processing_stuck_secs() -> u64 {
+    600
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn default_read_dir_failure_threshold() -> u32 {
+    5
+}
+
+fn default_max_processing_retries() -> u32 {
+    3
+}
+
+fn default_overrun_burst_lines() -> u32 {
+    3
+}
+
+fn default_empty_line_threshold() -> u32 {
+    20
+}
+
+fn default_empty_line_window_secs() -> u64 {
+    60
+}
+
+fn default_frozen_clock_threshold() -> u32 {
+    1000
+}
+
+fn default_min_cells_fraction() -> f64 {
+    0.5
+}
+
+fn default_range_degraded_ensembles() -> u32 {
+    3
+}
+
+fn default_config_frame_grace_seconds() -> u64 {
+    60
+}
+
+fn default_biofouling_alert_fraction() -> f64 {
+    0.7
+}
+
+fn default_biofouling_baseline_days() -> u64 {
+    30
+}
+
+fn default_saturation_window() -> usize {
+    100
+}
+
+fn default_saturation_alert_fraction() -> f64 {
+    0.5
+}
+
+fn default_reference_sound_speed() -> f32 {
+    1500.0
+}
+
+fn default_pending_flush_secs() -> u64 {
+    300
+}
+
+fn default_bus_publish_queue_depth() -> usize {
+    64
+}
+
+fn default_max_inflight_bytes() -> usize {
+    4 * 1024 * 1024
+}
+
+fn default_processing_concurrency() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
 impl AppConfig {
     pub fn default_path() -> &'static str {
         "config/adcp.toml"
@@ -97,19 +638,127 @@ impl AppConfig {
         let path_ref = path.as_ref();
         let raw = fs::read_to_string(path_ref)
             .with_context(|| format!("failed to read configuration from {}", path_ref.display()))?;
-        let mut config: Self = toml::from_str(&raw).with_context(|| {
+        // Deployment tooling may generate JSON; anything else is read as TOML.
+        let parsed = match path_ref.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&raw).map_err(anyhow::Error::from),
+            Some("toml") => toml::from_str(&raw).map_err(anyhow::Error::from),
+            other => {
+                tracing::warn!(
+                    path = %path_ref.display(),
+                    extension = other.unwrap_or_default(),
+                    "unrecognised config extension; parsing as TOML"
+                );
+                toml::from_str(&raw).map_err(anyhow::Error::from)
+            }
+        };
+        let mut config: Self = parsed.with_context(|| {
             format!("failed to parse configuration from {}", path_ref.display())
         })?;
         if config.service_name.trim().is_empty() {
             config.service_name = "adcp-supervisor".to_string();
         }
+        if !config.replay_speed.is_finite() || config.replay_speed <= 0.0 {
+            bail!("replay_speed in {} must be positive", path_ref.display());
+        }
+        if let Err(err) = check_filename_date_format(&config.filename_date_format) {
+            bail!("filename_date_format in {}: {err}", path_ref.display());
+        }
+        if let Some(rule) = config.validation_rules.iter().find(|r| !r.is_known_field()) {
+            bail!(
+                "validation rule in {} references unknown field '{}'",
+                path_ref.display(),
+                rule.field
+            );
+        }
+        for warning in config.validate() {
+            tracing::warn!(path = %path_ref.display(), "{warning}");
+        }
         Ok(config)
     }
+
+    /// How long opening `port` may wait for its source: `tcp_connect_timeout_seconds` for a
+    /// `tcp://` terminal server, `fifo_open_timeout_seconds` otherwise.
+    pub fn open_timeout(&self, port: &str) -> std::time::Duration {
+        let seconds = if port.starts_with(crate::serial::TCP_SCHEME) {
+            self.tcp_connect_timeout_seconds
+        } else {
+            self.fifo_open_timeout_seconds
+        };
+        std::time::Duration::from_secs(seconds)
+    }
+
+    /// Checks for settings that are allowed but probably mistakes, returning one message per
+    /// finding for the caller to log. Nothing here is fatal.
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !STANDARD_BAUD_RATES.contains(&self.baud_rate) {
+            // A dropped or doubled digit is the usual culprit, e.g. 11520 for 115200
+            let likely = [self.baud_rate.saturating_mul(10), self.baud_rate / 10]
+                .into_iter()
+                .find(|rate| STANDARD_BAUD_RATES.contains(rate));
+            let hint = match likely {
+                Some(rate) => format!("; did you mean {rate}?"),
+                None => String::new(),
+            };
+            warnings.push(format!(
+                "baud_rate {} is not a standard serial speed and will likely read garbage{hint}",
+                self.baud_rate
+            ));
+        }
+        warnings
+    }
+
+    /// Writes the fully-resolved config as `run-config-{timestamp}.toml` into `dir`, so the
+    /// settings that produced a run's data can be reproduced later. Only the newest
+    /// `RUN_SNAPSHOTS_KEPT` snapshots are kept; older ones are removed.
+    pub fn write_run_snapshot<P: AsRef<Path>>(&self, dir: P) -> Result<PathBuf> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+        let path = dir.join(format!("run-config-{stamp}.toml"));
+        let rendered = toml::to_string(self).context("failed to serialize run config")?;
+        fs::write(&path, rendered)
+            .with_context(|| format!("failed to write run config {}", path.display()))?;
+        prune_run_snapshots(dir);
+        Ok(path)
+    }
+}
+
+/// Run config snapshots `write_run_snapshot` leaves in a directory.
+pub const RUN_SNAPSHOTS_KEPT: usize = 20;
+
+/// Removes all but the newest `RUN_SNAPSHOTS_KEPT` snapshots in `dir`. The timestamps in their
+/// names sort chronologically. Failures are logged; a stale snapshot is not worth failing a start.
+fn prune_run_snapshots(dir: &Path) {
+    let mut snapshots: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("run-config-") && name.ends_with(".toml"))
+            })
+            .collect(),
+        Err(err) => {
+            tracing::warn!(dir = %dir.display(), error = %err, "failed to list run config snapshots");
+            return;
+        }
+    };
+    snapshots.sort();
+    let stale = snapshots.len().saturating_sub(RUN_SNAPSHOTS_KEPT);
+    for path in &snapshots[..stale] {
+        if let Err(err) = fs::remove_file(path) {
+            tracing::warn!(path = %path.display(), error = %err, "failed to remove old run config snapshot");
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::DEFAULT_INVALID_SENTINELS;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -126,18 +775,242 @@ serial_port = \"/dev/null\""
         assert_eq!(config.service_name, "test-dummy");
         assert_eq!(config.serial_port.as_deref(), Some("/dev/null"));
         assert_eq!(config.log_level, "info");
+        assert_eq!(config.tracing_rotation, TracingRotation::Daily);
         assert_eq!(config.data_directory, "./deployment/data");
+        assert!(config.fallback_data_directory.is_none());
         assert_eq!(config.baud_rate, 115200);
+        assert_eq!(config.fifo_open_timeout_seconds, 30);
+        assert_eq!(config.tcp_connect_timeout_seconds, 30);
+        assert_eq!(config.reconnect_max_backoff_seconds, 60);
         assert_eq!(config.idle_threshold_seconds, 30);
         assert!(config.alert_webhook.is_none());
+        assert!(config.quiet_hours.is_none());
         // New defaults
         assert!(matches!(config.mode, ServiceMode::Recording));
         assert_eq!(config.backup_folder, "./deployment/backup");
         assert_eq!(config.data_process_folder, "./deployment/to_process");
         assert_eq!(config.processed_folder, "./deployment/processed");
+        assert_eq!(config.processed_naming, ProcessedNaming::Original);
         assert!(matches!(config.split_mode, SplitMode::Daily));
+        assert_eq!(config.output_format, OutputFormat::Json);
         assert!(config.max_backup_files.is_none());
         assert!(config.max_backup_age_days.is_none());
+        assert!(config.max_data_files.is_none());
+        assert!(config.max_data_age_days.is_none());
+        assert!(!config.split_by_head_id);
+        assert!(!config.fsync_dir_on_rotate);
+        assert!(!config.compress_backups);
         assert_eq!(config.file_stability_seconds, 5);
+        assert_eq!(config.processing_stuck_seconds, 600);
+        assert_eq!(config.watchdog_max_restarts, 5);
+        assert_eq!(config.child_binary_path, "./target/release/adcp");
+        assert!(!config.write_qc_summary);
+        assert_eq!(config.record_delimiter, b'\n');
+        assert_eq!(config.filename_date_format, "%Y-%m-%d");
+        assert!(!config.data_process_run_id);
+        assert!(!config.backup_writer_markers);
+        assert!(config.data_process_writer_markers);
+        assert_eq!(config.replay_speed, 1.0);
+        assert!(!config.simulator_filter_invalid);
+        assert_eq!(config.read_dir_failure_threshold, 5);
+        assert_eq!(config.max_processing_retries, 3);
+        assert!(!config.exit_on_read_dir_failure);
+        assert!(config.validation_rules.is_empty());
+        assert!(!config.quarantine_invalid_frames);
+        assert!(config.expected_instrument_type.is_none());
+        assert!(!config.persist_rejected);
+        assert!(!config.include_ingested_at);
+        assert!(!config.profile_parsing);
+        assert!(config.max_frame_age_days.is_none());
+        assert!(config.min_frame_age_days.is_none());
+        assert_eq!(config.processing_concurrency, default_processing_concurrency());
+        assert_eq!(config.empty_line_threshold, 20);
+        assert_eq!(config.overrun_burst_lines, 3);
+        assert_eq!(config.empty_line_window_seconds, 60);
+        assert_eq!(config.frozen_clock_threshold, 1000);
+        assert_eq!(config.min_cells_fraction, 0.5);
+        assert_eq!(config.range_degraded_ensembles, 3);
+        assert_eq!(config.config_frame_grace_seconds, 60);
+        assert_eq!(config.biofouling_alert_fraction, 0.7);
+        assert_eq!(config.biofouling_baseline_days, 30);
+        assert_eq!(config.saturation_window, 100);
+        assert_eq!(config.saturation_alert_fraction, 0.5);
+        assert!(!config.sound_speed_correction);
+        assert_eq!(config.reference_sound_speed_m_s, 1500.0);
+        assert!(!config.convert_to_enu);
+        assert_eq!(config.pending_flush_seconds, 300);
+        assert!(config.sync_on_idle_seconds.is_none());
+        assert_eq!(config.bus_publish_queue_depth, 64);
+        assert_eq!(config.dialect.invalid_sentinels, DEFAULT_INVALID_SENTINELS);
+        assert_eq!(config.dialect.max_field_bytes, 1024);
+        assert_eq!(config.dialect, DialectConfig::default());
+        assert!(!config.run_once);
+        assert!(!config.force_reprocess);
+        assert_eq!(config.max_inflight_bytes, 4 * 1024 * 1024);
+        assert_eq!(config.statsd_address, None);
+        assert_eq!(config.metrics_listen_addr, None);
+        assert!(!config.write_manifest);
+        assert_eq!(config.retention_action, RetentionAction::Delete);
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let t = |s: &str| s.parse::<NaiveTime>().unwrap();
+        let overnight = QuietHours { start: t("22:00"), end: t("06:00") };
+        assert!(overnight.contains(t("23:30")));
+        assert!(overnight.contains(t("02:00")));
+        assert!(!overnight.contains(t("06:00")));
+        assert!(!overnight.contains(t("12:00")));
+
+        let daytime = QuietHours { start: t("09:00"), end: t("17:00") };
+        assert!(daytime.contains(t("09:00")));
+        assert!(!daytime.contains(t("17:30")));
+    }
+
+    #[test]
+    fn parses_every_tracing_rotation() {
+        for (name, expected) in [
+            ("Daily", TracingRotation::Daily),
+            ("Hourly", TracingRotation::Hourly),
+            ("Never", TracingRotation::Never),
+        ] {
+            let config: AppConfig =
+                toml::from_str(&format!("service_name = \"rot\"\ntracing_rotation = \"{name}\""))
+                    .expect("parse config");
+            assert_eq!(config.tracing_rotation, expected);
+        }
+    }
+
+    #[test]
+    fn warns_about_nonstandard_baud_rates() {
+        let mut config: AppConfig = toml::from_str("service_name = \"baud\"").expect("parse config");
+        config.baud_rate = 115200;
+        assert!(config.validate().is_empty());
+
+        config.baud_rate = 11520;
+        let warnings = config.validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("11520") && warnings[0].contains("did you mean 115200"), "{}", warnings[0]);
+    }
+
+    #[test]
+    fn rejects_filename_date_formats_that_break_names() {
+        for (pattern, accepted) in [
+            ("%Y%m%d", true),
+            ("%Y-%m-%d", true),
+            ("%Y/%m/%d", false),
+            ("%Y-%m", false),
+            ("%Q", false),
+        ] {
+            let mut file = NamedTempFile::new().expect("create temp config");
+            writeln!(
+                file,
+                "service_name = \"dates\"\nserial_port = \"/dev/null\"\nfilename_date_format = \"{pattern}\""
+            )
+            .unwrap();
+            assert_eq!(AppConfig::load(file.path()).is_ok(), accepted, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn json_and_toml_configs_load_identically() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let toml_path = dir.path().join("adcp.toml");
+        let json_path = dir.path().join("adcp.json");
+        std::fs::write(
+            &toml_path,
+            "service_name = \"both\"
+serial_port = \"/dev/ttyUSB0\"
+mode = \"Processing\"
+compress_rotated_backups = true
+quiet_hours = { start = \"22:00\", end = \"06:00\" }
+
+[[validation_rules]]
+field = \"temperature_c\"
+max = 40.0",
+        )
+        .unwrap();
+        std::fs::write(
+            &json_path,
+            r#"{
+  "service_name": "both",
+  "serial_port": "/dev/ttyUSB0",
+  "mode": "Processing",
+  "compress_backups": true,
+  "quiet_hours": { "start": "22:00", "end": "06:00" },
+  "validation_rules": [{ "field": "temperature_c", "max": 40.0 }]
+}"#,
+        )
+        .unwrap();
+
+        let from_toml = AppConfig::load(&toml_path).expect("load toml");
+        let from_json = AppConfig::load(&json_path).expect("load json");
+        assert_eq!(from_toml, from_json);
+        assert!(from_toml.compress_backups, "alias accepted");
+        assert_eq!(from_json.mode, ServiceMode::Processing);
+    }
+
+    #[test]
+    fn default_matches_a_minimal_file() {
+        let mut file = NamedTempFile::new().expect("create temp config");
+        writeln!(file, "service_name = \"adcp-supervisor\"").unwrap();
+        assert_eq!(AppConfig::load(file.path()).expect("load config"), AppConfig::default());
+    }
+
+    #[test]
+    fn open_timeout_depends_on_the_kind_of_port() {
+        let config = AppConfig {
+            fifo_open_timeout_seconds: 5,
+            tcp_connect_timeout_seconds: 10,
+            ..AppConfig::default()
+        };
+        assert_eq!(config.open_timeout("/tmp/adcp.fifo"), std::time::Duration::from_secs(5));
+        assert_eq!(config.open_timeout("tcp://10.0.0.5:4001"), std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn run_snapshot_round_trips() {
+        let mut file = NamedTempFile::new().expect("create temp config");
+        writeln!(
+            file,
+            "service_name = \"snapshot\"
+alert_webhook = \"https://example.invalid/hook\"
+mode = \"Processing\"
+quiet_hours = {{ start = \"22:00\", end = \"06:00\" }}
+
+[[validation_rules]]
+field = \"temperature_c\"
+min = -5.0
+max = 40.0"
+        )
+        .unwrap();
+        let config = AppConfig::load(file.path()).expect("load config");
+        let dir = tempfile::tempdir().expect("temp dir");
+
+        let path = config.write_run_snapshot(dir.path()).expect("write snapshot");
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("run-config-") && name.ends_with(".toml"));
+        assert_eq!(AppConfig::load(&path).expect("reload snapshot"), config);
+    }
+
+    #[test]
+    fn run_snapshots_are_pruned_to_the_newest() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        for day in 1..=RUN_SNAPSHOTS_KEPT {
+            fs::write(dir.path().join(format!("run-config-200001{day:02}T000000.000Z.toml")), "").expect("write old snapshot");
+        }
+        fs::write(dir.path().join("adcp-2026-01-05.log"), "").expect("write log");
+
+        let newest = AppConfig::default().write_run_snapshot(dir.path()).expect("write snapshot");
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .expect("list dir")
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("run-config-"))
+            .collect();
+        assert_eq!(names.len(), RUN_SNAPSHOTS_KEPT);
+        assert!(!names.contains(&"run-config-20000101T000000.000Z.toml".to_string()), "oldest removed");
+        assert!(newest.exists());
+        assert!(dir.path().join("adcp-2026-01-05.log").exists(), "other files untouched");
     }
 }
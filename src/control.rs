@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use busrt::rpc::{Rpc, RpcError, RpcEvent, RpcHandlers, RpcResult};
+use busrt::QoS;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Bus method every service answers by running its own clean shutdown path.
+pub const SHUTDOWN_METHOD: &str = "cmd.service.shutdown";
+
+/// Handles `cmd.service.shutdown` for a service's `RpcHandlers::handle_call` by signalling
+/// `shutdown`; the reply is sent before the service starts tearing down.
+pub fn handle_shutdown(shutdown: &watch::Sender<()>) -> RpcResult {
+    tracing::info!("shutdown requested over the bus");
+    shutdown.send_replace(());
+    Ok(None)
+}
+
+/// Resolves once `trigger` fires; a dropped sender never resolves, so it cannot be mistaken
+/// for a request.
+pub async fn shutdown_requested(trigger: &mut watch::Receiver<()>) {
+    if trigger.changed().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Minimal handlers exposing only `cmd.service.shutdown`, e.g. for a `Service` run on the bus.
+pub struct ShutdownHandlers {
+    shutdown: watch::Sender<()>,
+}
+
+impl ShutdownHandlers {
+    pub fn new(shutdown: watch::Sender<()>) -> Self {
+        Self { shutdown }
+    }
+}
+
+#[async_trait]
+impl RpcHandlers for ShutdownHandlers {
+    async fn handle_call(&self, event: RpcEvent) -> RpcResult {
+        match event.parse_method() {
+            Ok(SHUTDOWN_METHOD) => handle_shutdown(&self.shutdown),
+            Ok(_) => Err(RpcError::method(None)),
+            Err(_) => Err(RpcError::new(busrt::rpc::RPC_ERROR_CODE_PARSE, None)),
+        }
+    }
+    async fn handle_notification(&self, _event: RpcEvent) {}
+    async fn handle_frame(&self, _frame: busrt::Frame) {}
+}
+
+/// Asks the bus client `target` to shut down gracefully, giving up after `timeout` so the
+/// caller can fall back to killing the process.
+pub async fn request_shutdown(rpc: &impl Rpc, target: &str, timeout: Duration) -> anyhow::Result<()> {
+    tokio::time::timeout(timeout, rpc.call(target, SHUTDOWN_METHOD, Vec::new().into(), QoS::Processed))
+        .await
+        .map_err(|_| anyhow::anyhow!("{target} did not answer {SHUTDOWN_METHOD} in time"))?
+        .map_err(|e| anyhow::anyhow!("{target} rejected {SHUTDOWN_METHOD}: {e:?}"))?;
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use crate::parser::{AmplitudeUnit, CoordinateSystem, Frame, Payload, SentenceKind};
+use chrono::SecondsFormat;
+use std::fmt::Write;
+
+/// Header row of the CSV file holding frames of `kind`.
+pub fn header(kind: SentenceKind) -> &'static str {
+    match kind {
+        SentenceKind::Config => {
+            "recorded_at,instrument_type,head_id,beams,cells,blanking_m,cell_size_m,coordinate_system"
+        }
+        SentenceKind::Sensor => {
+            "recorded_at,sent_at,error_code_hex,status_code_hex,battery_voltage_v,sound_speed_m_s,heading_deg,\
+             pitch_deg,roll_deg,pressure_dbar,temperature_c,analog_input_1,analog_input_2"
+        }
+        SentenceKind::Current => {
+            "recorded_at,sent_at,cell_number,velocity_1_m_s,velocity_2_m_s,velocity_3_m_s,velocity_4_m_s,speed_m_s,\
+             direction_deg,amplitude_unit,amplitude_beam_1,amplitude_beam_2,amplitude_beam_3,amplitude_beam_4,\
+             correlation_beam_1_pct,correlation_beam_2_pct,correlation_beam_3_pct,correlation_beam_4_pct"
+        }
+        SentenceKind::Echo => "recorded_at,sent_at,distance_m,quality",
+        SentenceKind::Event => "recorded_at,sent_at,code,message",
+        SentenceKind::BottomTrack => {
+            "recorded_at,sent_at,range_beam_1_m,range_beam_2_m,range_beam_3_m,range_beam_4_m,velocity_1_m_s,\
+             velocity_2_m_s,velocity_3_m_s,velocity_4_m_s"
+        }
+    }
+}
+
+/// `frame` as one row under `header(frame.payload.kind())`. Masked values are empty cells,
+/// timestamps RFC 3339 in UTC and status words the hex digits sent by the instrument.
+pub fn row(frame: &Frame) -> String {
+    let mut row = Row(timestamp(frame.recorded_at));
+    match &frame.payload {
+        Payload::Config(config) => {
+            row.push(config.instrument_type.code());
+            row.push_text(&config.head_id);
+            row.push(config.beams);
+            row.push(config.cells);
+            row.push(config.blanking_m);
+            row.push(config.cell_size_m);
+            row.push_text(&coordinate_system(&config.coordinate_system));
+        }
+        Payload::Sensor(sensor) => {
+            row.push(timestamp(sensor.sent_at));
+            row.push(format_args!("{:08X}", sensor.error_code_hex));
+            row.push(format_args!("{:08X}", sensor.status_code_hex));
+            for value in [
+                sensor.battery_voltage_v,
+                sensor.sound_speed_m_s,
+                sensor.heading_deg,
+                sensor.pitch_deg,
+                sensor.roll_deg,
+                sensor.pressure_dbar,
+                sensor.temperature_c,
+                sensor.analog_input_1,
+                sensor.analog_input_2,
+            ] {
+                row.push_opt(value);
+            }
+        }
+        Payload::Current(current) => {
+            row.push(timestamp(current.sent_at));
+            row.push(current.cell_number);
+            for value in [
+                current.velocity_1_m_s,
+                current.velocity_2_m_s,
+                current.velocity_3_m_s,
+                current.velocity_4_m_s,
+                current.speed_m_s,
+                current.direction_deg,
+            ] {
+                row.push_opt(value);
+            }
+            match &current.amplitude_unit {
+                AmplitudeUnit::Counts => row.push("counts"),
+                AmplitudeUnit::Unknown(unit) => row.push_text(unit),
+            }
+            for value in [
+                current.amplitude_beam_1,
+                current.amplitude_beam_2,
+                current.amplitude_beam_3,
+                current.amplitude_beam_4,
+                current.correlation_beam_1_pct,
+                current.correlation_beam_2_pct,
+                current.correlation_beam_3_pct,
+                current.correlation_beam_4_pct,
+            ] {
+                row.push_opt(value);
+            }
+        }
+        Payload::Echo(echo) => {
+            row.push(timestamp(echo.sent_at));
+            row.push_opt(echo.distance_m);
+            row.push_opt(echo.quality);
+        }
+        Payload::Event(event) => {
+            row.push(timestamp(event.sent_at));
+            row.push_text(&event.code);
+            row.push_text(&event.message);
+        }
+        Payload::BottomTrack(bottom) => {
+            row.push(timestamp(bottom.sent_at));
+            for value in [
+                bottom.range_beam_1_m,
+                bottom.range_beam_2_m,
+                bottom.range_beam_3_m,
+                bottom.range_beam_4_m,
+                bottom.velocity_1_m_s,
+                bottom.velocity_2_m_s,
+                bottom.velocity_3_m_s,
+                bottom.velocity_4_m_s,
+            ] {
+                row.push_opt(value);
+            }
+        }
+    }
+    row.0
+}
+
+struct Row(String);
+
+impl Row {
+    fn push(&mut self, value: impl std::fmt::Display) {
+        let _ = write!(self.0, ",{value}");
+    }
+
+    fn push_opt(&mut self, value: Option<impl std::fmt::Display>) {
+        match value {
+            Some(value) => self.push(value),
+            None => self.0.push(','),
+        }
+    }
+
+    /// Free text from the instrument, quoted when it would otherwise break the row.
+    fn push_text(&mut self, value: &str) {
+        if value.contains([',', '"', '\n', '\r']) {
+            self.push(format_args!("\"{}\"", value.replace('"', "\"\"")));
+        } else {
+            self.push(value);
+        }
+    }
+}
+
+fn timestamp(at: chrono::DateTime<chrono::Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn coordinate_system(system: &CoordinateSystem) -> String {
+    match system {
+        CoordinateSystem::Enu => "enu".to_string(),
+        CoordinateSystem::Xyz => "xyz".to_string(),
+        CoordinateSystem::Beam => "beam".to_string(),
+        CoordinateSystem::Unknown(code) => code.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_line_up_with_their_header() {
+        let lines = [
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41",
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+            "$PNORC,010526,220800,1,-32.77,-32.77,-32.77,-32.77,46.34,225.0,C,65,64,61,59,40,37,14,22*35",
+        ];
+        for line in lines {
+            let frame = Frame::from_line(line).expect("parse");
+            let columns = header(frame.payload.kind()).split(',').count();
+            assert_eq!(row(&frame).split(',').count(), columns, "{line}");
+        }
+    }
+
+    #[test]
+    fn quotes_free_text_with_separators() {
+        let mut row = Row(String::new());
+        row.push_text("head,\"7\"");
+        assert_eq!(row.0, ",\"head,\"\"7\"\"\"");
+    }
+}
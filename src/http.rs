@@ -0,0 +1,190 @@
+use crate::config::AppConfig;
+use crate::metrics::{HealthSnapshot, Metrics};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+/// Largest request head read before the connection is answered with 400.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// How long a client gets to send its request head.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Body of `GET /health`.
+#[derive(Debug, Serialize)]
+pub struct HealthReport<'a> {
+    pub service: &'a str,
+    pub uptime_seconds: u64,
+    /// False once the last frame is older than `idle_threshold_seconds` (served as HTTP 503).
+    pub healthy: bool,
+    #[serde(flatten)]
+    pub snapshot: HealthSnapshot,
+}
+
+/// Whether `snapshot` is within `idle_threshold`. A service that has not seen a frame yet is
+/// healthy, as for the idle alert of the health heartbeat.
+pub fn is_healthy(snapshot: &HealthSnapshot, idle_threshold: Duration) -> bool {
+    snapshot.last_frame_age.is_none_or(|age| age <= idle_threshold)
+}
+
+/// Binds `metrics_listen_addr`, failing startup on a bad or taken address rather than
+/// running without the probe.
+pub async fn bind(address: &str) -> Result<TcpListener> {
+    TcpListener::bind(address)
+        .await
+        .with_context(|| format!("bind health endpoint on {address}"))
+}
+
+/// Serves `GET /health` on `listener` until `shutdown` fires: the `metrics` snapshot as JSON
+/// with 200, or 503 once frames stopped for longer than the live `idle_threshold_seconds`.
+pub async fn serve_health(
+    listener: TcpListener,
+    service: Arc<String>,
+    metrics: Arc<Metrics>,
+    config: watch::Receiver<Arc<AppConfig>>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let started = Instant::now();
+    if let Ok(address) = listener.local_addr() {
+        tracing::info!(service = %service, address = %address, "health endpoint listening");
+    }
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let service = service.clone();
+                    let metrics = metrics.clone();
+                    let idle_threshold = Duration::from_secs(config.borrow().idle_threshold_seconds);
+                    tokio::spawn(async move {
+                        let uptime = started.elapsed();
+                        if let Err(err) = respond(stream, &service, &metrics, idle_threshold, uptime).await {
+                            tracing::debug!(service = %service, error = %err, "health request failed");
+                        }
+                    });
+                }
+                Err(err) => tracing::warn!(service = %service, error = %err, "failed to accept health connection"),
+            }
+        }
+    }
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    service: &str,
+    metrics: &Metrics,
+    idle_threshold: Duration,
+    uptime: Duration,
+) -> Result<()> {
+    let head = timeout(REQUEST_TIMEOUT, read_request_head(&mut stream))
+        .await
+        .context("request timed out")??;
+    let request_line = head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/health") => {
+            let snapshot = metrics.snapshot();
+            let healthy = is_healthy(&snapshot, idle_threshold);
+            let report = HealthReport {
+                service,
+                uptime_seconds: uptime.as_secs(),
+                healthy,
+                snapshot,
+            };
+            let status = if healthy { "200 OK" } else { "503 Service Unavailable" };
+            (status, serde_json::to_string(&report).context("serialize health report")?)
+        }
+        (_, "/health") => ("405 Method Not Allowed", r#"{"error":"method not allowed"}"#.to_string()),
+        _ if head.is_empty() => ("400 Bad Request", r#"{"error":"bad request"}"#.to_string()),
+        _ => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await.context("write response")?;
+    stream.shutdown().await.ok();
+    Ok(())
+}
+
+/// Reads up to the blank line ending the request head; the probe ignores any body. An
+/// oversized or non-UTF-8 head comes back empty, to be answered with 400.
+async fn read_request_head(stream: &mut TcpStream) -> Result<String> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_BYTES {
+            return Ok(String::new());
+        }
+        let read = stream.read(&mut chunk).await.context("read request")?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&chunk[..read]);
+    }
+    Ok(String::from_utf8(head).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(address: std::net::SocketAddr, path: &str) -> (String, serde_json::Value) {
+        let mut stream = TcpStream::connect(address).await.expect("connect");
+        stream
+            .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+            .await
+            .expect("send request");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.expect("read response");
+        let (head, body) = response.split_once("\r\n\r\n").expect("response head");
+        let status = head.lines().next().expect("status line").to_string();
+        (status, serde_json::from_str(body).expect("json body"))
+    }
+
+    #[tokio::test]
+    async fn health_turns_503_once_frames_stop_for_the_idle_threshold() {
+        let listener = bind("127.0.0.1:0").await.expect("bind");
+        let address = listener.local_addr().expect("address");
+        let metrics = Arc::new(Metrics::new());
+        let mut config: AppConfig = toml::from_str("service_name = \"probe\"").expect("parse config");
+        config.idle_threshold_seconds = 1;
+        let (_config_tx, config_rx) = watch::channel(Arc::new(config));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let server = tokio::spawn(serve_health(
+            listener,
+            Arc::new("probe".to_string()),
+            metrics.clone(),
+            config_rx,
+            shutdown_rx,
+        ));
+
+        let (status, body) = get(address, "/health").await;
+        assert_eq!(status, "HTTP/1.1 200 OK", "no frame yet is healthy");
+        metrics.record_frame();
+        let (status, body_after_frame) = get(address, "/health").await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body["service"], "probe");
+        assert_eq!(body_after_frame["frames"], 1);
+        assert_eq!(body_after_frame["healthy"], true);
+
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        let (status, body) = get(address, "/health").await;
+        assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+        assert_eq!(body["healthy"], false);
+        assert!(body["last_frame_age"].as_f64().expect("idle seconds") > 1.0);
+
+        let (status, _) = get(address, "/metrics").await;
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        shutdown_tx.send(()).expect("shutdown");
+        server.await.expect("server stops");
+    }
+}
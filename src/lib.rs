@@ -1,6 +1,13 @@
 pub mod backup;
+pub mod biofouling;
+pub mod bus;
 pub mod config;
+pub mod control;
+pub mod csv;
+pub mod http;
 pub mod logging;
+pub mod manifest;
+pub mod merge;
 pub mod metrics;
 pub mod parser;
 pub mod persistence;
@@ -8,8 +15,14 @@ pub mod platform;
 pub mod serial;
 pub mod service;
 pub mod simulator;
+pub mod stats;
 pub mod processing;
+pub mod qc;
+pub mod reconfig;
+pub mod retention;
+pub mod selftest;
 
-pub use config::{AppConfig, ServiceMode, SplitMode};
+pub use config::{AppConfig, OutputFormat, ProcessedNaming, QuietHours, RetentionAction, ServiceMode, SplitMode, TracingRotation};
 pub use service::Service;
 pub mod telemetry;
+pub mod validation;
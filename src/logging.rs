@@ -3,30 +3,61 @@ use tracing_subscriber::{fmt, EnvFilter, registry::Registry};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, TracingRotation};
 use std::path::Path;
 use std::io::stdout;
-use tracing_appender::rolling;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_appender::non_blocking::WorkerGuard;
 
-pub fn init(config: &AppConfig) -> Result<WorkerGuard> {
-    let filter =
-        EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.log_level))?;
+/// Directory the per-service rolling log files are written to.
+pub const LOG_DIR: &str = "./deployment/log";
+
+/// Logs to stdout and to a daily rolling file under `LOG_DIR`. The returned guard flushes the
+/// file writer on drop; it is `None` when file logging was unavailable.
+pub fn init(config: &AppConfig) -> Result<Option<WorkerGuard>> {
+    init_with_dir(config, Path::new(LOG_DIR))
+}
 
-    // Ensure deployment log directory exists and set up a rolling file appender per service
-    let log_dir = Path::new("./deployment/log");
-    std::fs::create_dir_all(log_dir).map_err(|e| Error::msg(e))?;
-    let safe_name = config.service_name.replace(' ', "_");
+/// Maps `tracing_rotation` onto the appender's rotation.
+pub fn rotation(kind: &TracingRotation) -> Rotation {
+    match kind {
+        TracingRotation::Daily => Rotation::DAILY,
+        TracingRotation::Hourly => Rotation::HOURLY,
+        TracingRotation::Never => Rotation::NEVER,
+    }
+}
+
+/// Creates `log_dir` and the rolling appender for this service; files are named like
+/// `<service>.YYYY-MM-DD` (daily), `<service>.YYYY-MM-DD-HH` (hourly) or `<service>` (never).
+fn file_appender(config: &AppConfig, log_dir: &Path) -> Result<RollingFileAppender> {
+    std::fs::create_dir_all(log_dir)?;
+    Ok(RollingFileAppender::builder()
+        .rotation(rotation(&config.tracing_rotation))
+        .filename_prefix(config.service_name.replace(' ', "_"))
+        .build(log_dir)?)
+}
 
-    // Use daily rotation; file names will be like <service>.log.YYYY-MM-DD
-    let file_appender = rolling::daily(log_dir, &safe_name);
-    let (file_non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+/// Like `init`, but writes log files under `log_dir`. If that directory or the log file cannot
+/// be created (e.g. a read-only install directory), the service still starts with stdout-only
+/// logging and a warning instead of failing.
+pub fn init_with_dir(config: &AppConfig, log_dir: &Path) -> Result<Option<WorkerGuard>> {
+    let filter =
+        EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.log_level))?;
+    let (file_writer, guard, file_error) = match file_appender(config, log_dir) {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(writer), Some(guard), None)
+        }
+        Err(err) => (None, None, Some(err)),
+    };
 
     // File layer: plain, no ANSI, less span noise
-    let file_layer = fmt::layer()
-        .with_ansi(false)
-        .with_writer(file_non_blocking)
-        .with_span_events(fmt::format::FmtSpan::NONE);
+    let file_layer = file_writer.map(|writer| {
+        fmt::layer()
+            .with_ansi(false)
+            .with_writer(writer)
+            .with_span_events(fmt::format::FmtSpan::NONE)
+    });
 
     // Stdout layer: ansi on for terminal readability
     let stdout_layer = fmt::layer()
@@ -40,7 +71,45 @@ pub fn init(config: &AppConfig) -> Result<WorkerGuard> {
         .with(file_layer)
         .with(stdout_layer)
         .try_init()
-        .map_err(|err| Error::msg(err))?;
+        .map_err(Error::msg)?;
 
+    if let Some(err) = file_error {
+        tracing::warn!(
+            log_dir = %log_dir.display(),
+            error = %err,
+            "cannot write log files; logging to stdout only"
+        );
+    }
     Ok(guard)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn builds_an_appender_for_every_rotation() {
+        for kind in [TracingRotation::Daily, TracingRotation::Hourly, TracingRotation::Never] {
+            let tmp = tempfile::tempdir().expect("temp dir");
+            let mut config: AppConfig = toml::from_str("service_name = \"rot test\"").expect("parse config");
+            config.tracing_rotation = kind.clone();
+            let mut appender = file_appender(&config, tmp.path()).expect("appender");
+            appender.write_all(b"line\n").expect("write");
+            appender.flush().expect("flush");
+
+            let names: Vec<String> = std::fs::read_dir(tmp.path())
+                .expect("read log dir")
+                .map(|e| e.expect("entry").file_name().to_string_lossy().into_owned())
+                .collect();
+            assert_eq!(names.len(), 1);
+            let expected_len = match kind {
+                TracingRotation::Daily => "rot_test.YYYY-MM-DD".len(),
+                TracingRotation::Hourly => "rot_test.YYYY-MM-DD-HH".len(),
+                TracingRotation::Never => "rot_test".len(),
+            };
+            assert!(names[0].starts_with("rot_test"), "{names:?}");
+            assert_eq!(names[0].len(), expected_len, "{kind:?}: {names:?}");
+        }
+    }
+}
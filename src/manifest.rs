@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Role of an output file in a run's manifest.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    /// Dated NDJSON frame logs (including quarantine and rejected logs).
+    Persisted,
+    /// Raw serial backup files.
+    Backup,
+    /// Source files moved into `processed_folder`; the count is the frames replayed from them.
+    Processed,
+}
+
+/// One file touched by the run.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ManifestEntry {
+    pub kind: OutputKind,
+    pub path: String,
+    pub lines: u64,
+}
+
+#[derive(Serialize)]
+struct ManifestDocument<'a> {
+    run_started: DateTime<Utc>,
+    run_finished: DateTime<Utc>,
+    files: &'a [ManifestEntry],
+}
+
+/// Accumulates every output file a recording or processing run writes, with line counts,
+/// so auditors get a single `manifest-{run_ts}.json` per run.
+#[derive(Debug)]
+pub struct RunManifest {
+    started: DateTime<Utc>,
+    files: Mutex<BTreeMap<(OutputKind, PathBuf), u64>>,
+}
+
+impl Default for RunManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunManifest {
+    pub fn new() -> Self {
+        Self {
+            started: Utc::now(),
+            files: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds `lines` written to `path`.
+    pub fn record(&self, kind: OutputKind, path: &Path, lines: u64) {
+        if let Ok(mut files) = self.files.lock() {
+            *files.entry((kind, path.to_path_buf())).or_insert(0) += lines;
+        }
+    }
+
+    /// Files recorded so far, grouped by kind and sorted by path.
+    pub fn entries(&self) -> Vec<ManifestEntry> {
+        self.files
+            .lock()
+            .map(|files| {
+                files
+                    .iter()
+                    .map(|((kind, path), lines)| ManifestEntry {
+                        kind: *kind,
+                        path: path.display().to_string(),
+                        lines: *lines,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Writes `manifest-{run_ts}.json` into `dir`, `run_ts` being the run's start time.
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = dir.join(format!("manifest-{}.json", self.started.format("%Y%m%dT%H%M%S%.3fZ")));
+        let files = self.entries();
+        let document = ManifestDocument {
+            run_started: self.started,
+            run_finished: Utc::now(),
+            files: &files,
+        };
+        let rendered = serde_json::to_string_pretty(&document).context("failed to serialize manifest")?;
+        std::fs::write(&path, rendered).with_context(|| format!("failed to write manifest {}", path.display()))?;
+        tracing::info!(manifest = %path.display(), files = files.len(), "run manifest written");
+        Ok(path)
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{parser::Frame, simulator::normalize_capture};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::fs;
+
+/// Outcome of merging several capture files into one.
+#[derive(Debug, Default)]
+pub struct MergeSummary {
+    pub frames_written: usize,
+    pub duplicates_removed: usize,
+    pub rejected: usize,
+}
+
+struct MergeEntry {
+    sort_at: DateTime<Utc>,
+    dated: bool,
+    line: String,
+}
+
+/// Merges overlapping capture files into a single time-ordered, de-duplicated capture.
+///
+/// Dated frames are ordered by their payload timestamp. Undated frames (e.g. `$PNORI`)
+/// stay attached to the dated frame that follows them in their source file, so a
+/// configuration header keeps preceding the burst it describes. Exact duplicate lines
+/// at the same position in time are written once; lines that fail to parse are dropped.
+pub async fn merge_captures(
+    inputs: &[impl AsRef<Path>],
+    output: impl AsRef<Path>,
+) -> Result<MergeSummary> {
+    let mut summary = MergeSummary::default();
+    let mut entries = Vec::new();
+
+    for input in inputs {
+        let input = input.as_ref();
+        let raw = fs::read_to_string(input)
+            .await
+            .with_context(|| format!("open capture {}", input.display()))?;
+
+        let mut undated: Vec<String> = Vec::new();
+        let mut last_dated: Option<DateTime<Utc>> = None;
+        for line in normalize_capture(&raw) {
+            match Frame::from_line(&line) {
+                Ok(frame) => match frame.payload.sent_at() {
+                    Some(sent_at) => {
+                        for pending in undated.drain(..) {
+                            entries.push(MergeEntry { sort_at: sent_at, dated: false, line: pending });
+                        }
+                        entries.push(MergeEntry { sort_at: sent_at, dated: true, line });
+                        last_dated = Some(sent_at);
+                    }
+                    None => undated.push(line),
+                },
+                Err(err) => {
+                    summary.rejected += 1;
+                    tracing::warn!(error = %err, file = %input.display(), frame = %line, "merge input frame rejected");
+                }
+            }
+        }
+        // Trailing undated frames stay after the last dated frame of their file.
+        let tail_at = last_dated.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        for pending in undated {
+            entries.push(MergeEntry { sort_at: tail_at, dated: true, line: pending });
+        }
+    }
+
+    // Stable sort keeps file order for frames sharing a timestamp; undated headers sort first.
+    entries.sort_by_key(|e| (e.sort_at, e.dated));
+
+    let mut seen = HashSet::new();
+    let mut merged = String::new();
+    for entry in entries {
+        if !seen.insert((entry.sort_at, entry.line.clone())) {
+            summary.duplicates_removed += 1;
+            continue;
+        }
+        merged.push_str(&entry.line);
+        merged.push('\n');
+        summary.frames_written += 1;
+    }
+
+    let output = output.as_ref();
+    fs::write(output, merged)
+        .await
+        .with_context(|| format!("write merged capture {}", output.display()))?;
+
+    tracing::info!(
+        frames = summary.frames_written,
+        duplicates = summary.duplicates_removed,
+        rejected = summary.rejected,
+        output = %output.display(),
+        "capture merge completed"
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn merges_overlapping_captures_in_order_without_duplicates() {
+        let tmp = tempdir().expect("temp dir");
+        let output = tmp.path().join("merged.data");
+
+        let summary = merge_captures(
+            &["tests/fixtures/merge_a.data", "tests/fixtures/merge_b.data"],
+            &output,
+        )
+        .await
+        .expect("merge captures");
+        assert_eq!(summary.frames_written, 6);
+        assert_eq!(summary.duplicates_removed, 2);
+        assert_eq!(summary.rejected, 0);
+
+        let merged = fs::read_to_string(&output).await.expect("read merged");
+        let lines: Vec<&str> = merged.lines().collect();
+        assert!(lines[0].starts_with("$PNORI"));
+        let times: Vec<&str> = lines[1..]
+            .iter()
+            .map(|l| l.split(',').nth(2).expect("time field"))
+            .collect();
+        assert_eq!(times, ["220800", "220800", "220805", "220805", "220810"]);
+        assert!(lines[1].starts_with("$PNORS") && lines[2].starts_with("$PNORC"));
+    }
+}
@@ -1,26 +1,161 @@
+use crate::config::AppConfig;
+use crate::parser::SentenceKind;
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::{
-    atomic::{AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc, Mutex,
 };
 use std::time::{Duration, Instant};
 use tokio::sync::watch;
 use tokio::time::interval;
 
+/// Discarded junk is grouped by this many leading characters, so e.g. a framing offset that
+/// leaves the same partial sentence prefix each time collapses into one pattern.
+const JUNK_PATTERN_CHARS: usize = 32;
+/// Distinct junk patterns remembered; the rarest is evicted to make room for a new one.
+const JUNK_PATTERNS_TRACKED: usize = 32;
+/// Junk patterns reported in the health heartbeat.
+const TOP_JUNK_REPORTED: usize = 3;
+
+/// Upper bounds (microseconds) of the parse-time histogram buckets; one more bucket counts
+/// everything slower.
+pub const PARSE_BUCKET_BOUNDS_US: [u64; 10] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000];
+
+/// Time spent parsing one sentence type, recorded when `profile_parsing` is on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParseHistogram {
+    /// Samples per bucket of `PARSE_BUCKET_BOUNDS_US`; the last holds the slower ones.
+    pub buckets: [u64; PARSE_BUCKET_BOUNDS_US.len() + 1],
+    pub samples: u64,
+    /// Serialized in seconds.
+    #[serde(serialize_with = "seconds")]
+    pub total: Duration,
+}
+
+fn seconds<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn optional_seconds<S: serde::Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => seconds(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl ParseHistogram {
+    pub fn record(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_micros();
+        let bucket = PARSE_BUCKET_BOUNDS_US
+            .iter()
+            .position(|bound| micros <= u128::from(*bound))
+            .unwrap_or(PARSE_BUCKET_BOUNDS_US.len());
+        self.buckets[bucket] += 1;
+        self.samples += 1;
+        self.total += elapsed;
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        u32::try_from(self.samples).ok().filter(|n| *n > 0).map(|n| self.total / n)
+    }
+
+    /// Upper bound (microseconds) of the bucket holding the `quantile` sample, or `None`
+    /// when it falls in the overflow bucket or nothing was recorded.
+    pub fn quantile_bound_us(&self, quantile: f64) -> Option<u64> {
+        if self.samples == 0 {
+            return None;
+        }
+        let rank = ((self.samples as f64 * quantile).ceil() as u64).clamp(1, self.samples);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return PARSE_BUCKET_BOUNDS_US.get(bucket).copied();
+            }
+        }
+        None
+    }
+}
+
+/// One-line rendering of per-sentence parse timings for the heartbeat, e.g.
+/// `current n=1200 mean=3.1us p99<=10us; sensor n=20 mean=2.0us p99<=5us`.
+pub fn parse_timing_summary(timings: &HashMap<SentenceKind, ParseHistogram>) -> String {
+    let mut kinds: Vec<_> = timings.iter().collect();
+    kinds.sort_by_key(|(kind, _)| kind.as_str());
+    kinds
+        .into_iter()
+        .map(|(kind, histogram)| {
+            let mean = histogram.mean().unwrap_or_default().as_secs_f64() * 1e6;
+            let p99 = match histogram.quantile_bound_us(0.99) {
+                Some(bound) => format!("<={bound}us"),
+                None => format!(">{}us", PARSE_BUCKET_BOUNDS_US[PARSE_BUCKET_BOUNDS_US.len() - 1]),
+            };
+            format!("{} n={} mean={mean:.1}us p99{p99}", kind.as_str(), histogram.samples)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Aggregates telemetry counters that the health monitor can report on.
 pub struct Metrics {
     frames: AtomicU64,
     parse_errors: AtomicU64,
+    oversized_fields: AtomicU64,
     persistence_errors: AtomicU64,
+    backup_errors: AtomicU64,
+    data_process_errors: AtomicU64,
+    empty_lines: AtomicU64,
+    empty_line_bursts: AtomicU64,
+    suspected_overruns: AtomicU64,
+    lines_dropped: AtomicU64,
+    idle_alerts: AtomicU64,
+    instrument_mismatches: AtomicU64,
+    out_of_horizon: AtomicU64,
+    biofouling_suspected: AtomicBool,
+    saturated_samples: [AtomicU64; 4],
+    amplitude_saturated: AtomicBool,
+    clock_frozen: AtomicBool,
+    range_degraded: AtomicBool,
+    config_frame_missing: AtomicBool,
+    rule_violations: Mutex<HashMap<String, u64>>,
+    parse_timings: Mutex<HashMap<SentenceKind, ParseHistogram>>,
+    junk_patterns: Mutex<HashMap<String, u64>>,
     last_frame: Mutex<Option<Instant>>,
 }
 
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Metrics {
     pub fn new() -> Self {
         Self {
             frames: AtomicU64::new(0),
             parse_errors: AtomicU64::new(0),
+            oversized_fields: AtomicU64::new(0),
             persistence_errors: AtomicU64::new(0),
+            backup_errors: AtomicU64::new(0),
+            data_process_errors: AtomicU64::new(0),
+            empty_lines: AtomicU64::new(0),
+            empty_line_bursts: AtomicU64::new(0),
+            suspected_overruns: AtomicU64::new(0),
+            lines_dropped: AtomicU64::new(0),
+            idle_alerts: AtomicU64::new(0),
+            instrument_mismatches: AtomicU64::new(0),
+            out_of_horizon: AtomicU64::new(0),
+            biofouling_suspected: AtomicBool::new(false),
+            saturated_samples: Default::default(),
+            amplitude_saturated: AtomicBool::new(false),
+            clock_frozen: AtomicBool::new(false),
+            range_degraded: AtomicBool::new(false),
+            config_frame_missing: AtomicBool::new(false),
+            rule_violations: Mutex::new(HashMap::new()),
+            parse_timings: Mutex::new(HashMap::new()),
+            junk_patterns: Mutex::new(HashMap::new()),
             last_frame: Mutex::new(None),
         }
     }
@@ -36,10 +171,113 @@ impl Metrics {
         self.parse_errors.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn record_oversized_field(&self) {
+        self.oversized_fields.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn record_persistence_error(&self) {
         self.persistence_errors.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Folds in the totals of a replayed file, so processing mode reports what it handled.
+    pub fn record_replayed(&self, frames: u64, parse_errors: u64, persistence_errors: u64) {
+        self.frames.fetch_add(frames, Ordering::Relaxed);
+        self.parse_errors.fetch_add(parse_errors, Ordering::Relaxed);
+        self.persistence_errors.fetch_add(persistence_errors, Ordering::Relaxed);
+    }
+
+    pub fn record_backup_error(&self) {
+        self.backup_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_data_process_error(&self) {
+        self.data_process_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_empty_line(&self) {
+        self.empty_lines.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_empty_line_burst(&self) {
+        self.empty_line_bursts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_suspected_overrun(&self) {
+        self.suspected_overruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_line_dropped(&self) {
+        self.lines_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_idle_alert(&self) {
+        self.idle_alerts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_instrument_mismatch(&self) {
+        self.instrument_mismatches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_out_of_horizon(&self) {
+        self.out_of_horizon.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_biofouling_suspected(&self, suspected: bool) {
+        self.biofouling_suspected.store(suspected, Ordering::Relaxed);
+    }
+
+    /// Counts one pegged amplitude sample on `beam` (1-based).
+    pub fn record_saturated_sample(&self, beam: usize) {
+        if let Some(counter) = beam.checked_sub(1).and_then(|idx| self.saturated_samples.get(idx)) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn set_amplitude_saturated(&self, saturated: bool) {
+        self.amplitude_saturated.store(saturated, Ordering::Relaxed);
+    }
+
+    pub fn set_clock_frozen(&self, frozen: bool) {
+        self.clock_frozen.store(frozen, Ordering::Relaxed);
+    }
+
+    pub fn set_range_degraded(&self, degraded: bool) {
+        self.range_degraded.store(degraded, Ordering::Relaxed);
+    }
+
+    pub fn set_config_frame_missing(&self, missing: bool) {
+        self.config_frame_missing.store(missing, Ordering::Relaxed);
+    }
+
+    pub fn record_rule_violation(&self, field: &str) {
+        if let Ok(mut guard) = self.rule_violations.lock() {
+            *guard.entry(field.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Adds one parse duration for `kind` to its histogram (see `profile_parsing`).
+    pub fn record_parse_time(&self, kind: SentenceKind, elapsed: Duration) {
+        if let Ok(mut guard) = self.parse_timings.lock() {
+            guard.entry(kind).or_default().record(elapsed);
+        }
+    }
+
+    /// Counts one piece of junk the parser discarded around a sentence.
+    pub fn record_discarded(&self, junk: &str) {
+        let pattern: String = junk.trim().chars().take(JUNK_PATTERN_CHARS).collect();
+        if pattern.is_empty() {
+            return;
+        }
+        if let Ok(mut guard) = self.junk_patterns.lock() {
+            if !guard.contains_key(&pattern) && guard.len() >= JUNK_PATTERNS_TRACKED {
+                if let Some(rarest) = guard.iter().min_by_key(|(_, count)| **count).map(|(k, _)| k.clone()) {
+                    guard.remove(&rarest);
+                }
+            }
+            *guard.entry(pattern).or_insert(0) += 1;
+        }
+    }
+
     pub fn snapshot(&self) -> HealthSnapshot {
         let last_frame_age = self.last_frame.lock().ok().and_then(|guard| {
             guard.map(|instant| Instant::now().saturating_duration_since(instant))
@@ -47,41 +285,225 @@ impl Metrics {
         HealthSnapshot {
             frames: self.frames.load(Ordering::Relaxed),
             parse_errors: self.parse_errors.load(Ordering::Relaxed),
+            oversized_fields: self.oversized_fields.load(Ordering::Relaxed),
             persistence_errors: self.persistence_errors.load(Ordering::Relaxed),
+            backup_errors: self.backup_errors.load(Ordering::Relaxed),
+            data_process_errors: self.data_process_errors.load(Ordering::Relaxed),
+            empty_lines: self.empty_lines.load(Ordering::Relaxed),
+            empty_line_bursts: self.empty_line_bursts.load(Ordering::Relaxed),
+            suspected_overruns: self.suspected_overruns.load(Ordering::Relaxed),
+            lines_dropped: self.lines_dropped.load(Ordering::Relaxed),
+            idle_alerts: self.idle_alerts.load(Ordering::Relaxed),
+            instrument_mismatches: self.instrument_mismatches.load(Ordering::Relaxed),
+            out_of_horizon: self.out_of_horizon.load(Ordering::Relaxed),
+            biofouling_suspected: self.biofouling_suspected.load(Ordering::Relaxed),
+            saturated_samples: std::array::from_fn(|idx| self.saturated_samples[idx].load(Ordering::Relaxed)),
+            amplitude_saturated: self.amplitude_saturated.load(Ordering::Relaxed),
+            clock_frozen: self.clock_frozen.load(Ordering::Relaxed),
+            range_degraded: self.range_degraded.load(Ordering::Relaxed),
+            config_frame_missing: self.config_frame_missing.load(Ordering::Relaxed),
+            rule_violations: self
+                .rule_violations
+                .lock()
+                .map(|guard| guard.clone())
+                .unwrap_or_default(),
+            parse_timings: self
+                .parse_timings
+                .lock()
+                .map(|guard| guard.clone())
+                .unwrap_or_default(),
+            top_junk: self
+                .junk_patterns
+                .lock()
+                .map(|guard| {
+                    let mut top: Vec<(String, u64)> = guard.iter().map(|(k, v)| (k.clone(), *v)).collect();
+                    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                    top.truncate(TOP_JUNK_REPORTED);
+                    top
+                })
+                .unwrap_or_default(),
             last_frame_age,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
 pub struct HealthSnapshot {
     pub frames: u64,
     pub parse_errors: u64,
+    /// Lines rejected because one field exceeded `max_field_bytes` (also counted in `parse_errors`).
+    pub oversized_fields: u64,
     pub persistence_errors: u64,
+    pub backup_errors: u64,
+    pub data_process_errors: u64,
+    /// Empty or whitespace-only lines received from the instrument.
+    pub empty_lines: u64,
+    /// Times consecutive empty lines exceeded the configured threshold within its window.
+    pub empty_line_bursts: u64,
+    /// Bursts of lines not starting with `$` right after cleanly framed data, the signature of
+    /// a serial buffer overrun dropping bytes mid-line.
+    pub suspected_overruns: u64,
+    /// Serial lines dropped because persistence fell `max_inflight_bytes` behind the reader.
+    pub lines_dropped: u64,
+    /// Heartbeats that found the last frame older than the idle threshold.
+    pub idle_alerts: u64,
+    /// `$PNORI` frames whose instrument type differs from `expected_instrument_type`.
+    pub instrument_mismatches: u64,
+    /// Frames skipped because their `sent_at` fell outside `min_frame_age_days`..`max_frame_age_days`.
+    pub out_of_horizon: u64,
+    /// A beam's rolling amplitude has dropped well below its long-term baseline.
+    pub biofouling_suspected: bool,
+    /// `$PNORC` samples per beam (index 0 is beam 1) whose amplitude was pegged at 255.
+    pub saturated_samples: [u64; 4],
+    /// A beam's pegged fraction over `saturation_window` exceeds `saturation_alert_fraction`.
+    pub amplitude_saturated: bool,
+    /// Instrument timestamps stopped advancing (RTC frozen).
+    pub clock_frozen: bool,
+    /// Recent ensembles report far fewer cells than configured (profiling range lost).
+    pub range_degraded: bool,
+    /// No `$PNORI` arrived within `config_frame_grace_seconds`, so cell-count checks are idle.
+    pub config_frame_missing: bool,
+    /// Validation rule violations keyed by the offending field.
+    pub rule_violations: HashMap<String, u64>,
+    /// Parse time per sentence type; empty unless `profile_parsing` is on.
+    pub parse_timings: HashMap<SentenceKind, ParseHistogram>,
+    /// Most frequent discarded-junk patterns with their counts, most common first.
+    pub top_junk: Vec<(String, u64)>,
+    /// Time since the last frame; serialized in seconds.
+    #[serde(serialize_with = "optional_seconds")]
     pub last_frame_age: Option<Duration>,
 }
 
+/// Session totals logged once as a service stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShutdownReport {
+    pub frames: u64,
+    pub parse_errors: u64,
+    pub persistence_errors: u64,
+    pub uptime: Duration,
+    /// Distinct output files written, rotated into or moved during the run.
+    pub files_produced: usize,
+}
+
+impl ShutdownReport {
+    pub fn new(snapshot: &HealthSnapshot, uptime: Duration, files_produced: usize) -> Self {
+        Self {
+            frames: snapshot.frames,
+            parse_errors: snapshot.parse_errors,
+            persistence_errors: snapshot.persistence_errors,
+            uptime,
+            files_produced,
+        }
+    }
+
+    pub fn log(&self, service: &str) {
+        tracing::info!(
+            service = %service,
+            frames = self.frames,
+            parse_errors = self.parse_errors,
+            persistence_errors = self.persistence_errors,
+            uptime_seconds = self.uptime.as_secs(),
+            files_produced = self.files_produced,
+            "shutdown summary"
+        );
+    }
+}
+
+/// Renders one StatsD packet for `snapshot`: frames and parse errors as counters holding the
+/// increase since `sent` (the totals at the previous push), idle time as a gauge.
+pub fn statsd_packet(service: &str, snapshot: &HealthSnapshot, sent: (u64, u64)) -> String {
+    let prefix: String = service
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let mut packet = format!(
+        "adcp.{prefix}.frames:{}|c\nadcp.{prefix}.parse_errors:{}|c",
+        snapshot.frames.saturating_sub(sent.0),
+        snapshot.parse_errors.saturating_sub(sent.1),
+    );
+    if let Some(age) = snapshot.last_frame_age {
+        packet.push_str(&format!("\nadcp.{prefix}.idle_seconds:{}|g", age.as_secs()));
+    }
+    packet
+}
+
+fn send_statsd(address: &str, packet: &str) -> std::io::Result<()> {
+    use std::net::ToSocketAddrs;
+    let target = address.to_socket_addrs()?.next().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, format!("{address} did not resolve"))
+    })?;
+    let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    std::net::UdpSocket::bind(local)?.send_to(packet.as_bytes(), target)?;
+    Ok(())
+}
+
 pub async fn monitor_health(
     supervisor_name: Arc<String>,
     metrics: Arc<Metrics>,
     mut shutdown: watch::Receiver<()>,
-    idle_threshold: Duration,
-    alert_webhook: Option<String>,
+    config: watch::Receiver<Arc<AppConfig>>,
 ) -> Result<()> {
     let mut ticker = interval(Duration::from_secs(60));
+    let mut statsd_sent = (0, 0);
     loop {
         tokio::select! {
             _ = shutdown.changed() => break,
             _ = ticker.tick() => {
                 let snapshot = metrics.snapshot();
+                // Re-read on every tick so bus-pushed reconfiguration takes effect without restart.
+                let (idle_threshold, alert_webhook, statsd_address, quiet) = {
+                    let config = config.borrow();
+                    (
+                        Duration::from_secs(config.idle_threshold_seconds),
+                        config.alert_webhook.clone(),
+                        config.statsd_address.clone(),
+                        config
+                            .quiet_hours
+                            .as_ref()
+                            .is_some_and(|quiet| quiet.contains(chrono::Utc::now().time())),
+                    )
+                };
                 tracing::info!(
                     service = %supervisor_name,
                     frames = snapshot.frames,
                     parse_errors = snapshot.parse_errors,
+                    oversized_fields = snapshot.oversized_fields,
                     persistence_errors = snapshot.persistence_errors,
+                    backup_errors = snapshot.backup_errors,
+                    data_process_errors = snapshot.data_process_errors,
+                    empty_lines = snapshot.empty_lines,
+                    suspected_overruns = snapshot.suspected_overruns,
+                    lines_dropped = snapshot.lines_dropped,
+                    instrument_mismatches = snapshot.instrument_mismatches,
+                    out_of_horizon = snapshot.out_of_horizon,
+                    biofouling_suspected = snapshot.biofouling_suspected,
+                    saturated_samples = ?snapshot.saturated_samples,
+                    amplitude_saturated = snapshot.amplitude_saturated,
+                    clock_frozen = snapshot.clock_frozen,
+                    range_degraded = snapshot.range_degraded,
+                    config_frame_missing = snapshot.config_frame_missing,
+                    rule_violations = snapshot.rule_violations.values().sum::<u64>(),
+                    top_junk = ?snapshot.top_junk,
+                    parse_timings = %parse_timing_summary(&snapshot.parse_timings),
                     "health heartbeat"
                 );
+                if let Some(address) = &statsd_address {
+                    // Best effort: a missing collector must never disturb capture
+                    let packet = statsd_packet(&supervisor_name, &snapshot, statsd_sent);
+                    match send_statsd(address, &packet) {
+                        Ok(()) => statsd_sent = (snapshot.frames, snapshot.parse_errors),
+                        Err(err) => tracing::warn!(service = %supervisor_name, address = %address, error = %err, "statsd push failed"),
+                    }
+                }
                 if let Some(age) = snapshot.last_frame_age {
-                    if age > idle_threshold {
+                    if age > idle_threshold && quiet {
+                        tracing::debug!(
+                            service = %supervisor_name,
+                            idle_seconds = ?age.as_secs_f64(),
+                            "idle alert suppressed during quiet hours"
+                        );
+                    } else if age > idle_threshold {
+                        metrics.record_idle_alert();
                         tracing::warn!(
                             service = %supervisor_name,
                             idle_seconds = ?age.as_secs_f64(),
@@ -102,3 +524,116 @@ pub async fn monitor_health(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::QuietHours;
+    use chrono::{TimeDelta, Utc};
+
+    async fn idle_alerts_with(quiet_hours: Option<QuietHours>) -> u64 {
+        let mut config: AppConfig = toml::from_str(
+            "service_name = \"quiet\"\nidle_threshold_seconds = 0\nalert_webhook = \"https://example.invalid/hook\"",
+        )
+        .expect("parse config");
+        config.quiet_hours = quiet_hours;
+        let (_live, config_rx) = watch::channel(Arc::new(config));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let metrics = Arc::new(Metrics::new());
+        metrics.record_frame();
+
+        let monitor = tokio::spawn(monitor_health(
+            Arc::new("quiet".to_string()),
+            metrics.clone(),
+            shutdown_rx,
+            config_rx,
+        ));
+        tokio::time::sleep(Duration::from_secs(61)).await;
+        shutdown_tx.send(()).ok();
+        monitor.await.expect("join monitor").expect("monitor result");
+        metrics.snapshot().idle_alerts
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pushes_statsd_metrics_over_udp() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").expect("bind receiver");
+        receiver
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .expect("read timeout");
+        let mut config: AppConfig = toml::from_str("service_name = \"statsd\"").expect("parse config");
+        config.statsd_address = Some(receiver.local_addr().expect("addr").to_string());
+        let (_live, config_rx) = watch::channel(Arc::new(config));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let metrics = Arc::new(Metrics::new());
+        for _ in 0..3 {
+            metrics.record_frame();
+        }
+        metrics.record_parse_error();
+
+        let monitor = tokio::spawn(monitor_health(
+            Arc::new("adcp recorder".to_string()),
+            metrics.clone(),
+            shutdown_rx,
+            config_rx,
+        ));
+        let packet = tokio::task::spawn_blocking(move || {
+            let mut buf = [0u8; 1024];
+            let (len, _) = receiver.recv_from(&mut buf).expect("statsd packet");
+            String::from_utf8_lossy(&buf[..len]).into_owned()
+        })
+        .await
+        .expect("receiver task");
+        shutdown_tx.send(()).ok();
+        monitor.await.expect("join monitor").expect("monitor result");
+
+        let lines: Vec<&str> = packet.lines().collect();
+        assert_eq!(lines[0], "adcp.adcp_recorder.frames:3|c");
+        assert_eq!(lines[1], "adcp.adcp_recorder.parse_errors:1|c");
+        assert!(lines[2].starts_with("adcp.adcp_recorder.idle_seconds:") && lines[2].ends_with("|g"));
+    }
+
+    #[test]
+    fn statsd_counters_report_increase_since_last_push() {
+        let metrics = Metrics::new();
+        for _ in 0..10 {
+            metrics.record_frame();
+        }
+        let packet = statsd_packet("svc", &metrics.snapshot(), (7, 0));
+        assert!(packet.starts_with("adcp.svc.frames:3|c\nadcp.svc.parse_errors:0|c"));
+    }
+
+    #[test]
+    fn parse_histogram_buckets_by_upper_bound() {
+        let mut histogram = ParseHistogram::default();
+        for micros in [0, 1, 3, 3, 3, 40, 5_000] {
+            histogram.record(Duration::from_micros(micros));
+        }
+        assert_eq!(histogram.buckets, [2, 0, 3, 0, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(histogram.samples, 7);
+        assert_eq!(histogram.quantile_bound_us(0.5), Some(5));
+        assert_eq!(histogram.quantile_bound_us(0.99), None, "slowest sample overflows");
+
+        let metrics = Metrics::new();
+        metrics.record_parse_time(SentenceKind::Current, Duration::from_micros(4));
+        metrics.record_parse_time(SentenceKind::Current, Duration::from_micros(6));
+        let summary = parse_timing_summary(&metrics.snapshot().parse_timings);
+        assert_eq!(summary, "current n=2 mean=5.0us p99<=10us");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn quiet_hours_suppress_idle_alerts() {
+        let now = Utc::now().time();
+        let covering = QuietHours {
+            start: now - TimeDelta::hours(1),
+            end: now + TimeDelta::hours(1),
+        };
+        let elsewhere = QuietHours {
+            start: now + TimeDelta::hours(1),
+            end: now + TimeDelta::hours(2),
+        };
+
+        assert_eq!(idle_alerts_with(Some(covering)).await, 0);
+        assert!(idle_alerts_with(Some(elsewhere)).await > 0);
+        assert!(idle_alerts_with(None).await > 0);
+    }
+}
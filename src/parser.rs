@@ -1,13 +1,29 @@
 use anyhow::{anyhow, bail, Context, Result};
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 /// A validated NMEA frame captured from the ADCP stream.
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Frame {
     /// When the service received the line (uses payload timestamp when present).
     pub recorded_at: DateTime<Utc>,
+    /// Host clock time the line arrived, set by the recorder when `include_ingested_at` is on.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ingested_at: Option<DateTime<Utc>>,
+    /// Basename of the capture a replay read this frame from, set when the replay's
+    /// `tag_source_file` option is on.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub source_file: Option<String>,
     pub raw: String,
+    /// Factor the recorder scaled `$PNORC` velocities and speed by to correct for the
+    /// measured sound speed (see `sound_speed_correction`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sound_speed_ratio: Option<f32>,
+    /// Coordinate system `$PNORC` velocities were reported in before the recorder rotated
+    /// them into ENU (see `convert_to_enu`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub original_coordinate_system: Option<CoordinateSystem>,
     pub checksum: Checksum,
     pub payload: Payload,
     /// Parts of the raw line that were discarded during parsing.
@@ -15,22 +31,73 @@ pub struct Frame {
     pub discarded: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Checksum {
     pub provided: u8,
     pub computed: u8,
     pub valid: bool,
+    /// The checksum digits exactly as received (case and leading zeros kept), retained only
+    /// when the dialect sets `keep_raw_checksum`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provided_raw: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+/// A parsed frame whose text borrows from the input line, so hot paths such as bulk replay
+/// skip the per-line string allocations of `Frame`. The payload is owned; only `$PNORI`
+/// (head id) and `$PNORE` events (code and message) allocate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameRef<'a> {
+    pub recorded_at: DateTime<Utc>,
+    /// Borrowed unless the line held non-UTF-8 bytes (only possible via `from_bytes`).
+    pub raw: Cow<'a, str>,
+    pub checksum: ChecksumRef<'a>,
+    pub payload: Payload,
+    pub discarded: Vec<Cow<'a, str>>,
+}
+
+/// `Checksum` borrowing the received digits from the input line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChecksumRef<'a> {
+    pub provided: u8,
+    pub computed: u8,
+    pub valid: bool,
+    pub provided_raw: Option<&'a str>,
+}
+
+impl FrameRef<'_> {
+    /// Copies the borrowed text into an owned `Frame`.
+    pub fn into_owned(self) -> Frame {
+        Frame {
+            recorded_at: self.recorded_at,
+            ingested_at: None,
+            source_file: None,
+            raw: self.raw.into_owned(),
+            sound_speed_ratio: None,
+            original_coordinate_system: None,
+            checksum: Checksum {
+                provided: self.checksum.provided,
+                computed: self.checksum.computed,
+                valid: self.checksum.valid,
+                provided_raw: self.checksum.provided_raw.map(str::to_string),
+            },
+            payload: self.payload,
+            discarded: self.discarded.into_iter().map(Cow::into_owned).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Payload {
     Config(ConfigSentence),
     Sensor(SensorSentence),
     Current(CurrentSentence),
+    Echo(EchoSentence),
+    BottomTrack(BottomTrackSentence),
+    Event(EventSentence),
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ConfigSentence {
     pub instrument_type: InstrumentType,
     pub head_id: String,
@@ -41,14 +108,24 @@ pub struct ConfigSentence {
     pub coordinate_system: CoordinateSystem,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum InstrumentType {
     Signature,
     Other(u8),
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+impl InstrumentType {
+    /// The numeric type code as sent in the first `$PNORI` field.
+    pub fn code(&self) -> u8 {
+        match self {
+            InstrumentType::Signature => 4,
+            InstrumentType::Other(code) => *code,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum CoordinateSystem {
     Enu,
@@ -57,7 +134,7 @@ pub enum CoordinateSystem {
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SensorSentence {
     pub sent_at: DateTime<Utc>,
     pub error_code_hex: u32,
@@ -73,7 +150,7 @@ pub struct SensorSentence {
     pub analog_input_2: Option<f32>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CurrentSentence {
     pub sent_at: DateTime<Utc>,
     pub cell_number: u16,
@@ -94,115 +171,607 @@ pub struct CurrentSentence {
     pub correlation_beam_4_pct: Option<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+/// Altimeter/echo-sounder reading emitted by instruments with the altimeter option.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EchoSentence {
+    pub sent_at: DateTime<Utc>,
+    pub distance_m: Option<f32>,
+    pub quality: Option<u16>,
+}
+
+/// Fault or event report that some firmware emits as `$PNORE` in place of an echo reading.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventSentence {
+    pub sent_at: DateTime<Utc>,
+    pub code: String,
+    /// Free text; delimiters inside it are kept, as the rest of the sentence is the message.
+    pub message: String,
+}
+
+/// Bottom-track reading: distance to the seabed along each beam and the instrument's
+/// velocity over ground, emitted by Signature instruments with bottom tracking enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BottomTrackSentence {
+    pub sent_at: DateTime<Utc>,
+    pub range_beam_1_m: Option<f32>,
+    pub range_beam_2_m: Option<f32>,
+    pub range_beam_3_m: Option<f32>,
+    pub range_beam_4_m: Option<f32>,
+    pub velocity_1_m_s: Option<f32>,
+    pub velocity_2_m_s: Option<f32>,
+    pub velocity_3_m_s: Option<f32>,
+    pub velocity_4_m_s: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum AmplitudeUnit {
     Counts,
     Unknown(String),
 }
 
+/// How the checksum after `*` is written.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumRadix {
+    /// Two hex digits, as standard NMEA.
+    #[default]
+    Hex,
+    /// One to three decimal digits (0-255).
+    Decimal,
+}
+
+/// Wire format of a feed: the field delimiter and checksum notation. The default is
+/// standard NMEA; gateways that rewrite sentences (e.g. `;` separators, decimal checksums)
+/// get their own dialect instead of a parser fork.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DialectConfig {
+    pub delimiter: char,
+    pub checksum_radix: ChecksumRadix,
+    /// Keep the checksum digits as received in `Checksum::provided_raw` for exact auditing.
+    pub keep_raw_checksum: bool,
+    pub coordinate_codes: CoordinateCodes,
+    /// Accepted data field counts per sentence type, to tighten acceptance to a firmware version.
+    pub field_counts: FieldCounts,
+    /// Exact field values treated as "no measurement".
+    pub invalid_sentinels: Vec<String>,
+    /// Lines with a single field longer than this are rejected, bounding memory per line.
+    pub max_field_bytes: usize,
+}
+
+/// Data fields (after the identifier) a sentence type may carry. An unset `min` is the
+/// parser's own minimum, which a configured one cannot go below; an unset `max` allows any
+/// number of trailing fields.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FieldRange {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+/// `FieldRange` per sentence type, keyed like `SentenceKind::as_str`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FieldCounts {
+    pub config: FieldRange,
+    pub sensor: FieldRange,
+    pub current: FieldRange,
+    pub echo: FieldRange,
+    pub bottom_track: FieldRange,
+    pub event: FieldRange,
+}
+
+impl FieldCounts {
+    /// Rejects a `kind` sentence carrying `count` data fields outside its range.
+    fn check(&self, kind: SentenceKind, count: usize) -> std::result::Result<(), FieldCount> {
+        let range = match kind {
+            SentenceKind::Config => self.config,
+            SentenceKind::Sensor => self.sensor,
+            SentenceKind::Current => self.current,
+            SentenceKind::Echo => self.echo,
+            SentenceKind::BottomTrack => self.bottom_track,
+            SentenceKind::Event => self.event,
+        };
+        let min = range.min.unwrap_or(0).max(kind.required_fields());
+        if count < min || range.max.is_some_and(|max| count > max) {
+            return Err(FieldCount { sentence: kind, count, min, max: range.max });
+        }
+        Ok(())
+    }
+}
+
+/// `$PNORI` coordinate-system codes. Defaults to Nortek's 0 = ENU, 1 = XYZ, 2 = BEAM;
+/// reformatted feeds override them. Codes matching none become `CoordinateSystem::Unknown`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CoordinateCodes {
+    pub enu: u8,
+    pub xyz: u8,
+    pub beam: u8,
+}
+
+impl Default for CoordinateCodes {
+    fn default() -> Self {
+        Self { enu: 0, xyz: 1, beam: 2 }
+    }
+}
+
+impl Default for DialectConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            checksum_radix: ChecksumRadix::Hex,
+            keep_raw_checksum: false,
+            coordinate_codes: CoordinateCodes::default(),
+            field_counts: FieldCounts::default(),
+            invalid_sentinels: DEFAULT_INVALID_SENTINELS.iter().map(|s| s.to_string()).collect(),
+            max_field_bytes: DEFAULT_MAX_FIELD_BYTES,
+        }
+    }
+}
+
 impl Frame {
     pub fn from_line(line: &str) -> Result<Self> {
-        let raw = line.trim_end_matches(|c| c == '\r' || c == '\n').trim();
-        let (provided, computed, body, discarded) = validate_checksum(raw)?;
-        let fields: Vec<&str> = body.split(',').collect();
-        let ident = fields
-            .get(0)
-            .copied()
-            .ok_or_else(|| anyhow!("missing sentence identifier"))?;
-        let payload = match ident {
-            "PNORI" => Payload::Config(parse_config(&fields[1..])?),
-            "PNORS" => Payload::Sensor(parse_sensor(&fields[1..])?),
-            "PNORC" => Payload::Current(parse_current(&fields[1..])?),
-            other => bail!("unsupported sentence '{other}'"),
+        Self::from_bytes(line.as_bytes())
+    }
+
+    /// Parses a sentence from the exact bytes received on the wire.
+    ///
+    /// The checksum is computed over the raw bytes, so high or non-UTF-8 bytes in junk
+    /// surrounding the sentence cannot skew it; only the sentence fields must be UTF-8.
+    pub fn from_bytes(line: &[u8]) -> Result<Self> {
+        Self::from_bytes_with(line, &DialectConfig::default())
+    }
+
+    /// Like `from_line`, for a feed in a non-standard `dialect`.
+    pub fn from_line_with(line: &str, dialect: &DialectConfig) -> Result<Self> {
+        Self::from_bytes_with(line.as_bytes(), dialect)
+    }
+
+    /// Like `from_bytes`, for a feed in a non-standard `dialect`. The checksum covers the
+    /// bytes as received, delimiters included.
+    pub fn from_bytes_with(line: &[u8], dialect: &DialectConfig) -> Result<Self> {
+        parse_frame(line, dialect).map(FrameRef::into_owned)
+    }
+
+    /// Like `from_line`, but borrows the raw body and discarded junk from `line` instead of
+    /// copying them.
+    pub fn parse_borrowed(line: &str) -> Result<FrameRef<'_>> {
+        Self::parse_borrowed_with(line, &DialectConfig::default())
+    }
+
+    /// Like `parse_borrowed`, for a feed in a non-standard `dialect`.
+    pub fn parse_borrowed_with<'a>(line: &'a str, dialect: &DialectConfig) -> Result<FrameRef<'a>> {
+        parse_frame(line.as_bytes(), dialect)
+    }
+
+    /// Rescales a current frame measured assuming `reference_m_s` to the actual
+    /// `sound_speed_m_s`, recording the ratio applied. Returns false (leaving the frame
+    /// untouched) for other sentences or non-positive sound speeds.
+    pub fn apply_sound_speed_correction(&mut self, sound_speed_m_s: f32, reference_m_s: f32) -> bool {
+        let Payload::Current(current) = &mut self.payload else {
+            return false;
         };
-        let recorded_at = payload.sent_at().unwrap_or_else(Utc::now);
-        Ok(Self {
-            recorded_at,
-            raw: raw.to_string(),
-            checksum: Checksum {
-                provided,
-                computed,
-                valid: provided == computed,
-            },
-            payload,
-            discarded,
-        })
+        if sound_speed_m_s <= 0.0 || reference_m_s <= 0.0 {
+            return false;
+        }
+        let ratio = sound_speed_m_s / reference_m_s;
+        for value in [
+            &mut current.velocity_1_m_s,
+            &mut current.velocity_2_m_s,
+            &mut current.velocity_3_m_s,
+            &mut current.velocity_4_m_s,
+            &mut current.speed_m_s,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *value *= ratio;
+        }
+        self.sound_speed_ratio = Some(ratio);
+        true
     }
 
     pub fn to_persistence_line(&self) -> String {
         serde_json::to_string(self).expect("frame serialization cannot fail")
     }
+
+    /// Rebuilds a canonical `$PNOR...*XX` line from the parsed payload with a freshly computed
+    /// checksum. Missing values are written as `-9`, so `from_line` yields an equal payload.
+    pub fn to_nmea_line(&self) -> String {
+        self.to_nmea_line_with(&DialectConfig::default())
+    }
+
+    /// Like `to_nmea_line`, but in `dialect`: its delimiter, checksum radix and coordinate
+    /// codes, with missing values written as its first invalid sentinel (blank if it has
+    /// none), so `from_line_with` in the same dialect yields an equal payload.
+    pub fn to_nmea_line_with(&self, dialect: &DialectConfig) -> String {
+        let missing = dialect.invalid_sentinels.first().map_or("", |sentinel| sentinel.trim());
+        let mut fields: Vec<String> = Vec::with_capacity(INLINE_FIELDS);
+        match &self.payload {
+            Payload::Config(c) => {
+                fields.push("PNORI".into());
+                fields.push(c.instrument_type.code().to_string());
+                fields.push(c.head_id.clone());
+                fields.push(c.beams.to_string());
+                fields.push(c.cells.to_string());
+                fields.push(c.blanking_m.to_string());
+                fields.push(c.cell_size_m.to_string());
+                fields.push(coordinate_system_code(&c.coordinate_system, &dialect.coordinate_codes).to_string());
+            }
+            Payload::Sensor(s) => {
+                fields.push("PNORS".into());
+                push_datetime(&mut fields, s.sent_at);
+                fields.push(format!("{:08X}", s.error_code_hex));
+                fields.push(format!("{:08X}", s.status_code_hex));
+                for value in [
+                    s.battery_voltage_v,
+                    s.sound_speed_m_s,
+                    s.heading_deg,
+                    s.pitch_deg,
+                    s.roll_deg,
+                    s.pressure_dbar,
+                    s.temperature_c,
+                    s.analog_input_1,
+                    s.analog_input_2,
+                ] {
+                    fields.push(opt_field(value, missing));
+                }
+            }
+            Payload::Current(c) => {
+                fields.push("PNORC".into());
+                push_datetime(&mut fields, c.sent_at);
+                fields.push(c.cell_number.to_string());
+                for value in [
+                    c.velocity_1_m_s,
+                    c.velocity_2_m_s,
+                    c.velocity_3_m_s,
+                    c.velocity_4_m_s,
+                    c.speed_m_s,
+                    c.direction_deg,
+                ] {
+                    fields.push(opt_field(value, missing));
+                }
+                fields.push(match &c.amplitude_unit {
+                    AmplitudeUnit::Counts => "C".to_string(),
+                    AmplitudeUnit::Unknown(other) => other.clone(),
+                });
+                for value in [
+                    c.amplitude_beam_1,
+                    c.amplitude_beam_2,
+                    c.amplitude_beam_3,
+                    c.amplitude_beam_4,
+                    c.correlation_beam_1_pct,
+                    c.correlation_beam_2_pct,
+                    c.correlation_beam_3_pct,
+                    c.correlation_beam_4_pct,
+                ] {
+                    fields.push(opt_field(value, missing));
+                }
+            }
+            Payload::Echo(e) => {
+                fields.push("PNORE".into());
+                push_datetime(&mut fields, e.sent_at);
+                fields.push(opt_field(e.distance_m, missing));
+                fields.push(opt_field(e.quality, missing));
+            }
+            Payload::Event(e) => {
+                fields.push("PNORE".into());
+                push_datetime(&mut fields, e.sent_at);
+                fields.push(e.code.clone());
+                if !e.message.is_empty() {
+                    fields.push(e.message.clone());
+                }
+            }
+            Payload::BottomTrack(b) => {
+                fields.push("PNORBT".into());
+                push_datetime(&mut fields, b.sent_at);
+                for value in [
+                    b.range_beam_1_m,
+                    b.range_beam_2_m,
+                    b.range_beam_3_m,
+                    b.range_beam_4_m,
+                    b.velocity_1_m_s,
+                    b.velocity_2_m_s,
+                    b.velocity_3_m_s,
+                    b.velocity_4_m_s,
+                ] {
+                    fields.push(opt_field(value, missing));
+                }
+            }
+        }
+        let body = fields.join(&dialect.delimiter.to_string());
+        let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+        match dialect.checksum_radix {
+            ChecksumRadix::Hex => format!("${body}*{checksum:02X}"),
+            ChecksumRadix::Decimal => format!("${body}*{checksum}"),
+        }
+    }
+}
+
+fn push_datetime(fields: &mut Vec<String>, at: DateTime<Utc>) {
+    fields.push(at.format("%m%d%y").to_string());
+    fields.push(at.format("%H%M%S").to_string());
+}
+
+fn opt_field<T: ToString>(value: Option<T>, missing: &str) -> String {
+    value.map_or_else(|| missing.to_string(), |v| v.to_string())
+}
+
+fn coordinate_system_code(system: &CoordinateSystem, codes: &CoordinateCodes) -> u8 {
+    match system {
+        CoordinateSystem::Enu => codes.enu,
+        CoordinateSystem::Xyz => codes.xyz,
+        CoordinateSystem::Beam => codes.beam,
+        CoordinateSystem::Unknown(code) => *code,
+    }
+}
+
+/// Sentence type of a payload without its data, e.g. to filter what a replay persists.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SentenceKind {
+    Config,
+    Sensor,
+    Current,
+    Echo,
+    BottomTrack,
+    Event,
+}
+
+impl SentenceKind {
+    /// The snake_case name used in configuration and file names, e.g. `bottom_track`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SentenceKind::Config => "config",
+            SentenceKind::Sensor => "sensor",
+            SentenceKind::Current => "current",
+            SentenceKind::Echo => "echo",
+            SentenceKind::BottomTrack => "bottom_track",
+            SentenceKind::Event => "event",
+        }
+    }
+
+    /// Sentence identifier on the wire, e.g. `PNORBT`.
+    pub fn ident(&self) -> &'static str {
+        match self {
+            SentenceKind::Config => "PNORI",
+            SentenceKind::Sensor => "PNORS",
+            SentenceKind::Current => "PNORC",
+            SentenceKind::Echo => "PNORE",
+            SentenceKind::BottomTrack => "PNORBT",
+            SentenceKind::Event => "PNORE",
+        }
+    }
+
+    /// Data fields the parser needs to decode this sentence type.
+    fn required_fields(&self) -> usize {
+        match self {
+            SentenceKind::Config => 7,
+            SentenceKind::Sensor => 13,
+            SentenceKind::Current => 18,
+            SentenceKind::Echo => 4,
+            SentenceKind::BottomTrack => 10,
+            SentenceKind::Event => 3,
+        }
+    }
+}
+
+impl std::str::FromStr for SentenceKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "config" => Ok(SentenceKind::Config),
+            "sensor" => Ok(SentenceKind::Sensor),
+            "current" => Ok(SentenceKind::Current),
+            "echo" => Ok(SentenceKind::Echo),
+            "bottom_track" => Ok(SentenceKind::BottomTrack),
+            "event" => Ok(SentenceKind::Event),
+            other => bail!("unknown sentence type '{other}' (expected config, sensor, current, echo, bottom_track or event)"),
+        }
+    }
 }
 
 impl Payload {
+    pub fn kind(&self) -> SentenceKind {
+        match self {
+            Payload::Config(_) => SentenceKind::Config,
+            Payload::Sensor(_) => SentenceKind::Sensor,
+            Payload::Current(_) => SentenceKind::Current,
+            Payload::Echo(_) => SentenceKind::Echo,
+            Payload::BottomTrack(_) => SentenceKind::BottomTrack,
+            Payload::Event(_) => SentenceKind::Event,
+        }
+    }
+
     pub fn sent_at(&self) -> Option<DateTime<Utc>> {
         match self {
             Payload::Config(_) => None,
             Payload::Sensor(s) => Some(s.sent_at),
             Payload::Current(c) => Some(c.sent_at),
+            Payload::Echo(e) => Some(e.sent_at),
+            Payload::BottomTrack(b) => Some(b.sent_at),
+            Payload::Event(e) => Some(e.sent_at),
+        }
+    }
+}
+
+const SENTENCE_MARKERS: [&[u8]; 5] = [b"$PNORC", b"$PNORS", b"$PNORI", b"$PNORE", b"$PNORBT"];
+
+/// Field slots kept on the stack; `$PNORC` is the widest sentence with 20.
+const INLINE_FIELDS: usize = 24;
+
+/// Parses a sentence from the bytes received on the wire, borrowing its text from `line`.
+fn parse_frame<'a>(line: &'a [u8], dialect: &DialectConfig) -> Result<FrameRef<'a>> {
+    let mut end = line.len();
+    while end > 0 && matches!(line[end - 1], b'\r' | b'\n') {
+        end -= 1;
+    }
+    let raw = line[..end].trim_ascii();
+    if !dialect.delimiter.is_ascii() {
+        bail!("field delimiter '{}' is not ASCII", dialect.delimiter);
+    }
+    let delimiter = dialect.delimiter as u8;
+    let (provided, computed, body, discarded) = validate_checksum(raw, dialect)?;
+    let provided_raw = dialect.keep_raw_checksum.then(|| raw_checksum_digits(raw));
+    // Split into a stack buffer; only unusually wide sentences spill onto the heap.
+    let mut inline = [""; INLINE_FIELDS];
+    let mut spilled: Vec<&str> = Vec::new();
+    let mut count = 0;
+    let max_field = dialect.max_field_bytes;
+    for (idx, field) in body.split(|b| *b == delimiter).enumerate() {
+        if field.len() > max_field {
+            return Err(OversizedField { index: idx, len: field.len(), max: max_field }.into());
         }
+        let field = std::str::from_utf8(field)
+            .with_context(|| format!("field {idx} is not valid UTF-8"))?;
+        if idx < INLINE_FIELDS {
+            inline[idx] = field;
+        } else {
+            if spilled.is_empty() {
+                spilled.extend_from_slice(&inline);
+            }
+            spilled.push(field);
+        }
+        count += 1;
     }
+    let fields: &[&str] = if spilled.is_empty() { &inline[..count] } else { &spilled };
+    let ident = fields
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("missing sentence identifier"))?;
+    let kind = match ident {
+        "PNORI" => SentenceKind::Config,
+        "PNORS" => SentenceKind::Sensor,
+        "PNORC" => SentenceKind::Current,
+        // Only four data fields can be a reading; any other count is an event
+        "PNORE" if fields.len() == 5 && is_echo_reading(fields[3], fields[4], dialect) => SentenceKind::Echo,
+        "PNORE" => SentenceKind::Event,
+        "PNORBT" => SentenceKind::BottomTrack,
+        other => bail!("unsupported sentence '{other}'"),
+    };
+    // Never below `required_fields`, so the parsers below can index their fields directly
+    dialect.field_counts.check(kind, fields.len() - 1)?;
+    let payload = match kind {
+        SentenceKind::Config => Payload::Config(parse_config(&fields[1..], &dialect.coordinate_codes)?),
+        SentenceKind::Sensor => Payload::Sensor(parse_sensor(&fields[1..], dialect)?),
+        SentenceKind::Current => Payload::Current(parse_current(&fields[1..], dialect)?),
+        SentenceKind::Echo => Payload::Echo(parse_echo(&fields[1..], dialect)?),
+        SentenceKind::BottomTrack => Payload::BottomTrack(parse_bottom_track(&fields[1..], dialect)?),
+        SentenceKind::Event => Payload::Event(parse_event(&fields[1..], dialect.delimiter)?),
+    };
+    let recorded_at = payload.sent_at().unwrap_or_else(Utc::now);
+    Ok(FrameRef {
+        recorded_at,
+        raw: String::from_utf8_lossy(raw),
+        checksum: ChecksumRef {
+            provided,
+            computed,
+            valid: provided == computed,
+            provided_raw,
+        },
+        payload,
+        discarded,
+    })
 }
 
-fn validate_checksum(raw: &str) -> Result<(u8, u8, &str, Vec<String>)> {
+/// The checksum digits after the last `*`, trimmed of surrounding whitespace and junk.
+fn raw_checksum_digits(raw: &[u8]) -> &str {
+    let after = raw.iter().rposition(|b| *b == b'*').map_or(&raw[..0], |star| &raw[star + 1..]);
+    let start = after.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(after.len());
+    let digits = &after[start..];
+    let len = digits.iter().position(|b| !b.is_ascii_alphanumeric()).unwrap_or(digits.len());
+    // Only ASCII alphanumerics are kept, so this is always valid UTF-8
+    std::str::from_utf8(&digits[..len]).unwrap_or_default()
+}
+
+/// Provided and computed checksum, the sentence body they cover and the junk cut from around it.
+type CheckedSentence<'a> = (u8, u8, &'a [u8], Vec<Cow<'a, str>>);
+
+fn validate_checksum<'a>(raw: &'a [u8], dialect: &DialectConfig) -> Result<CheckedSentence<'a>> {
+    let radix = dialect.checksum_radix;
     let mut discarded = Vec::new();
-    let (body_raw, checksum_hex) = raw
-        .rsplit_once('*')
+    let star = raw
+        .iter()
+        .rposition(|b| *b == b'*')
         .ok_or_else(|| anyhow!("NMEA sentence missing '*' checksum delimiter"))?;
-    let original_checksum = checksum_hex;
-    
-    let mut hex_chars = String::with_capacity(2);
-    let mut last_hex_pos = 0;
-    for (i, c) in checksum_hex.chars().enumerate() {
-        if c.is_ascii_hexdigit() {
-            hex_chars.push(c);
-            if hex_chars.len() == 2 {
-                last_hex_pos = i + 1;
-                break;
-            }
-        } else if !c.is_whitespace() {
-            if !hex_chars.is_empty() {
+    let (body_raw, checksum_hex) = (&raw[..star], &raw[star + 1..]);
+
+    // Collect the digits without allocating; the owned strings are only built on error.
+    let (base, max_digits) = match radix {
+        ChecksumRadix::Hex => (16, 2),
+        ChecksumRadix::Decimal => (10, 3),
+    };
+    let mut digits = [0u8; 3];
+    let mut digit_len = 0;
+    let mut last_digit_pos = 0;
+    for (i, b) in checksum_hex.iter().enumerate() {
+        if char::from(*b).is_digit(base) {
+            digits[digit_len] = *b;
+            digit_len += 1;
+            last_digit_pos = i + 1;
+            if digit_len == max_digits {
                 break;
             }
+        } else if !b.is_ascii_whitespace() && digit_len > 0 {
+            break;
         }
     }
-    if hex_chars.len() != 2 {
-        bail!("checksum '{}' is not two hex digits, original '{}'", hex_chars, original_checksum);
-    }
-    if last_hex_pos < original_checksum.len() {
-        let junk = &original_checksum[last_hex_pos..];
+    let provided = digits[..digit_len]
+        .iter()
+        .try_fold(0u8, |acc, b| {
+            acc.checked_mul(base as u8)?
+                .checked_add(char::from(*b).to_digit(base)? as u8)
+        });
+    let provided = match (radix, digit_len, provided) {
+        (ChecksumRadix::Hex, 2, Some(value)) => value,
+        (ChecksumRadix::Decimal, 1..=3, Some(value)) => value,
+        (ChecksumRadix::Hex, ..) => bail!(
+            "checksum '{}' is not two hex digits, original '{}'",
+            String::from_utf8_lossy(&digits[..digit_len]),
+            String::from_utf8_lossy(checksum_hex)
+        ),
+        (ChecksumRadix::Decimal, ..) => bail!(
+            "checksum '{}' is not a decimal byte, original '{}'",
+            String::from_utf8_lossy(&digits[..digit_len]),
+            String::from_utf8_lossy(checksum_hex)
+        ),
+    };
+    if last_digit_pos < checksum_hex.len() {
+        let junk = bounded_lossy(&checksum_hex[last_digit_pos..], dialect.max_field_bytes);
         if !junk.trim().is_empty() {
-            discarded.push(junk.to_string());
+            discarded.push(junk);
         }
     }
 
-    let provided = u8::from_str_radix(&hex_chars, 16)
-        .with_context(|| format!("checksum '{}' is not hex, original '{}'", hex_chars, original_checksum))?;
-
-    // If the body contains junk before a known sentence ($PNORC/$PNORS/$PNORI), trim it.
+    // If the body contains junk before a known sentence ($PNORC/$PNORS/$PNORI/$PNORE/$PNORBT), trim it.
+    // Clean sentences start with a marker, which is then the earliest one, so skip the search.
     let mut body = body_raw;
     let mut found_pos = None;
-    for marker in ["$PNORC", "$PNORS", "$PNORI"] {
-        if let Some(pos) = body.find(marker) {
-            if found_pos.map_or(true, |p| pos < p) {
-                found_pos = Some(pos);
+    if !SENTENCE_MARKERS.iter().any(|marker| body.starts_with(marker)) {
+        for marker in SENTENCE_MARKERS {
+            if let Some(pos) = body.windows(marker.len()).position(|w| w == marker) {
+                if found_pos.is_none_or(|p| pos < p) {
+                    found_pos = Some(pos);
+                }
             }
         }
     }
-    
+
     if let Some(pos) = found_pos {
         if pos > 0 {
-            let junk = &body[..pos];
+            let junk = bounded_lossy(&body[..pos], dialect.max_field_bytes);
             if !junk.trim().is_empty() {
-                discarded.push(junk.to_string());
+                discarded.push(junk);
             }
             body = &body[pos..];
         }
     }
 
-    let body_valid = body.strip_prefix('$').unwrap_or(body);
-    let computed = body_valid.bytes().fold(0u8, |acc, b| acc ^ b);
+    let body_valid = body.strip_prefix(b"$").unwrap_or(body);
+    let computed = body_valid.iter().fold(0u8, |acc, b| acc ^ b);
     if provided != computed {
         bail!(
             "checksum mismatch: provided {provided:02X} != computed {computed:02X}"
@@ -211,10 +780,8 @@ fn validate_checksum(raw: &str) -> Result<(u8, u8, &str, Vec<String>)> {
     Ok((provided, computed, body_valid, discarded))
 }
 
-fn parse_config(fields: &[&str]) -> Result<ConfigSentence> {
-    if fields.len() < 7 {
-        bail!("PNORI expects 7 fields, got {}", fields.len());
-    }
+fn parse_config(fields: &[&str], coordinate_codes: &CoordinateCodes) -> Result<ConfigSentence> {
+    debug_assert!(fields.len() >= SentenceKind::Config.required_fields());
     let instrument_type_raw: u8 = fields[0]
         .parse()
         .with_context(|| format!("invalid instrument type '{}'", fields[0]))?;
@@ -235,7 +802,7 @@ fn parse_config(fields: &[&str]) -> Result<ConfigSentence> {
     let cell_size_m: f32 = fields[5]
         .parse()
         .with_context(|| format!("invalid cell size '{}'", fields[5]))?;
-    let coordinate_system = parse_coordinate_system(fields[6])?;
+    let coordinate_system = parse_coordinate_system(fields[6], coordinate_codes)?;
     Ok(ConfigSentence {
         instrument_type,
         head_id,
@@ -247,10 +814,8 @@ fn parse_config(fields: &[&str]) -> Result<ConfigSentence> {
     })
 }
 
-fn parse_sensor(fields: &[&str]) -> Result<SensorSentence> {
-    if fields.len() < 13 {
-        bail!("PNORS expects 13 fields, got {}", fields.len());
-    }
+fn parse_sensor(fields: &[&str], dialect: &DialectConfig) -> Result<SensorSentence> {
+    debug_assert!(fields.len() >= SentenceKind::Sensor.required_fields());
     let sent_at = parse_datetime(fields[0], fields[1])?;
     let error_code_hex = parse_hex_u32(fields[2], "error code")?;
     let status_code_hex = parse_hex_u32(fields[3], "status code")?;
@@ -258,22 +823,20 @@ fn parse_sensor(fields: &[&str]) -> Result<SensorSentence> {
         sent_at,
         error_code_hex,
         status_code_hex,
-        battery_voltage_v: parse_opt_f32(fields[4]),
-        sound_speed_m_s: parse_opt_f32(fields[5]),
-        heading_deg: parse_opt_f32(fields[6]),
-        pitch_deg: parse_opt_f32(fields[7]),
-        roll_deg: parse_opt_f32(fields[8]),
-        pressure_dbar: parse_opt_f32(fields[9]),
-        temperature_c: parse_opt_f32(fields[10]),
-        analog_input_1: parse_opt_f32(fields[11]),
-        analog_input_2: parse_opt_f32(fields[12]),
+        battery_voltage_v: parse_opt_f32(fields[4], dialect),
+        sound_speed_m_s: parse_opt_f32(fields[5], dialect),
+        heading_deg: parse_opt_f32(fields[6], dialect),
+        pitch_deg: parse_opt_f32(fields[7], dialect),
+        roll_deg: parse_opt_f32(fields[8], dialect),
+        pressure_dbar: parse_opt_f32(fields[9], dialect),
+        temperature_c: parse_opt_f32(fields[10], dialect),
+        analog_input_1: parse_opt_f32(fields[11], dialect),
+        analog_input_2: parse_opt_f32(fields[12], dialect),
     })
 }
 
-fn parse_current(fields: &[&str]) -> Result<CurrentSentence> {
-    if fields.len() < 18 {
-        bail!("PNORC expects 18 fields, got {}", fields.len());
-    }
+fn parse_current(fields: &[&str], dialect: &DialectConfig) -> Result<CurrentSentence> {
+    debug_assert!(fields.len() >= SentenceKind::Current.required_fields());
     let sent_at = parse_datetime(fields[0], fields[1])?;
     let cell_number: u16 = fields[2]
         .parse()
@@ -282,21 +845,66 @@ fn parse_current(fields: &[&str]) -> Result<CurrentSentence> {
     Ok(CurrentSentence {
         sent_at,
         cell_number,
-        velocity_1_m_s: parse_opt_f32(fields[3]),
-        velocity_2_m_s: parse_opt_f32(fields[4]),
-        velocity_3_m_s: parse_opt_f32(fields[5]),
-        velocity_4_m_s: parse_opt_f32(fields[6]),
-        speed_m_s: parse_opt_f32(fields[7]),
-        direction_deg: parse_opt_f32(fields[8]),
+        velocity_1_m_s: parse_opt_f32(fields[3], dialect),
+        velocity_2_m_s: parse_opt_f32(fields[4], dialect),
+        velocity_3_m_s: parse_opt_f32(fields[5], dialect),
+        velocity_4_m_s: parse_opt_f32(fields[6], dialect),
+        speed_m_s: parse_opt_f32(fields[7], dialect),
+        direction_deg: parse_opt_f32(fields[8], dialect),
         amplitude_unit,
-        amplitude_beam_1: parse_opt_u8(fields[10]),
-        amplitude_beam_2: parse_opt_u8(fields[11]),
-        amplitude_beam_3: parse_opt_u8(fields[12]),
-        amplitude_beam_4: parse_opt_u8(fields[13]),
-        correlation_beam_1_pct: parse_opt_u8(fields[14]),
-        correlation_beam_2_pct: parse_opt_u8(fields[15]),
-        correlation_beam_3_pct: parse_opt_u8(fields[16]),
-        correlation_beam_4_pct: parse_opt_u8(fields[17]),
+        amplitude_beam_1: parse_opt_u8(fields[10], dialect),
+        amplitude_beam_2: parse_opt_u8(fields[11], dialect),
+        amplitude_beam_3: parse_opt_u8(fields[12], dialect),
+        amplitude_beam_4: parse_opt_u8(fields[13], dialect),
+        correlation_beam_1_pct: parse_opt_u8(fields[14], dialect),
+        correlation_beam_2_pct: parse_opt_u8(fields[15], dialect),
+        correlation_beam_3_pct: parse_opt_u8(fields[16], dialect),
+        correlation_beam_4_pct: parse_opt_u8(fields[17], dialect),
+    })
+}
+
+fn parse_echo(fields: &[&str], dialect: &DialectConfig) -> Result<EchoSentence> {
+    debug_assert!(fields.len() >= SentenceKind::Echo.required_fields());
+    let sent_at = parse_datetime(fields[0], fields[1])?;
+    Ok(EchoSentence {
+        sent_at,
+        distance_m: parse_opt_f32(fields[2], dialect),
+        quality: parse_opt_u16(fields[3], dialect),
+    })
+}
+
+/// Whether the last two of a four-field `$PNORE` hold an altimeter distance and quality
+/// (numbers, blanks or sentinels) rather than an event's code and message. An event with a
+/// numeric code and a numeric or blank message is indistinguishable from a reading and
+/// parses as one.
+fn is_echo_reading(distance: &str, quality: &str, dialect: &DialectConfig) -> bool {
+    (is_invalid_field(distance, dialect) || distance.parse::<f32>().is_ok())
+        && (is_invalid_field(quality, dialect) || quality.parse::<u16>().is_ok())
+}
+
+fn parse_event(fields: &[&str], delimiter: char) -> Result<EventSentence> {
+    debug_assert!(fields.len() >= SentenceKind::Event.required_fields());
+    let sent_at = parse_datetime(fields[0], fields[1])?;
+    Ok(EventSentence {
+        sent_at,
+        code: fields[2].trim().to_string(),
+        message: fields[3..].join(&delimiter.to_string()),
+    })
+}
+
+fn parse_bottom_track(fields: &[&str], dialect: &DialectConfig) -> Result<BottomTrackSentence> {
+    debug_assert!(fields.len() >= SentenceKind::BottomTrack.required_fields());
+    let sent_at = parse_datetime(fields[0], fields[1])?;
+    Ok(BottomTrackSentence {
+        sent_at,
+        range_beam_1_m: parse_opt_f32(fields[2], dialect),
+        range_beam_2_m: parse_opt_f32(fields[3], dialect),
+        range_beam_3_m: parse_opt_f32(fields[4], dialect),
+        range_beam_4_m: parse_opt_f32(fields[5], dialect),
+        velocity_1_m_s: parse_opt_f32(fields[6], dialect),
+        velocity_2_m_s: parse_opt_f32(fields[7], dialect),
+        velocity_3_m_s: parse_opt_f32(fields[8], dialect),
+        velocity_4_m_s: parse_opt_f32(fields[9], dialect),
     })
 }
 
@@ -342,14 +950,14 @@ fn parse_time(time: &str) -> Result<NaiveTime> {
         .ok_or_else(|| anyhow!("invalid clock time '{time}'"))
 }
 
-fn parse_coordinate_system(raw: &str) -> Result<CoordinateSystem> {
+fn parse_coordinate_system(raw: &str, codes: &CoordinateCodes) -> Result<CoordinateSystem> {
     let code: u8 = raw
         .parse()
         .with_context(|| format!("invalid coordinate system '{}'", raw))?;
     let system = match code {
-        0 => CoordinateSystem::Enu,
-        1 => CoordinateSystem::Xyz,
-        2 => CoordinateSystem::Beam,
+        code if code == codes.enu => CoordinateSystem::Enu,
+        code if code == codes.xyz => CoordinateSystem::Xyz,
+        code if code == codes.beam => CoordinateSystem::Beam,
         other => CoordinateSystem::Unknown(other),
     };
     Ok(system)
@@ -367,25 +975,85 @@ fn parse_amplitude_unit(raw: &str) -> AmplitudeUnit {
     }
 }
 
-fn parse_opt_f32(raw: &str) -> Option<f32> {
-    if is_invalid_field(raw) {
+fn parse_opt_f32(raw: &str, dialect: &DialectConfig) -> Option<f32> {
+    if is_invalid_field(raw, dialect) {
         None
     } else {
         raw.parse().ok()
     }
 }
 
-fn parse_opt_u8(raw: &str) -> Option<u8> {
-    if is_invalid_field(raw) {
+fn parse_opt_u8(raw: &str, dialect: &DialectConfig) -> Option<u8> {
+    if is_invalid_field(raw, dialect) {
         None
     } else {
         raw.parse().ok()
     }
 }
 
-fn is_invalid_field(raw: &str) -> bool {
+fn parse_opt_u16(raw: &str, dialect: &DialectConfig) -> Option<u16> {
+    if is_invalid_field(raw, dialect) {
+        None
+    } else {
+        raw.parse().ok()
+    }
+}
+
+/// Values Nortek writes in place of a missing measurement. Matched exactly, so legitimate
+/// negatives such as a `-9.50` m/s velocity are kept.
+pub const DEFAULT_INVALID_SENTINELS: &[&str] =
+    &["-9", "-9.0", "-9.00", "-9.000", "-9.9", "-9.99", "-9.999", "-99", "-99.9", "-999"];
+
+/// Default for `max_field_bytes`; real fields are at most a few dozen bytes.
+pub const DEFAULT_MAX_FIELD_BYTES: usize = 1024;
+
+/// A sentence field longer than `max_field_bytes`; callers can `downcast_ref` the parse error
+/// to count these separately from ordinary malformed lines.
+#[derive(Debug)]
+pub struct OversizedField {
+    pub index: usize,
+    pub len: usize,
+    pub max: usize,
+}
+
+impl std::fmt::Display for OversizedField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "field {} is {} bytes, over the {} byte limit", self.index, self.len, self.max)
+    }
+}
+
+impl std::error::Error for OversizedField {}
+
+/// A sentence whose data field count falls outside its `DialectConfig::field_counts` range.
+#[derive(Debug)]
+pub struct FieldCount {
+    pub sentence: SentenceKind,
+    pub count: usize,
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl std::fmt::Display for FieldCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ident = self.sentence.ident();
+        match self.max {
+            Some(max) => write!(f, "{ident} expects {} to {max} fields, got {}", self.min, self.count),
+            None => write!(f, "{ident} expects {} fields, got {}", self.min, self.count),
+        }
+    }
+}
+
+impl std::error::Error for FieldCount {}
+
+/// Junk kept in `Frame::discarded`, cut to `max_field_bytes` so a flood of garbage in front of
+/// a sentence cannot bloat every frame.
+fn bounded_lossy(junk: &[u8], max_field_bytes: usize) -> Cow<'_, str> {
+    String::from_utf8_lossy(&junk[..junk.len().min(max_field_bytes)])
+}
+
+fn is_invalid_field(raw: &str, dialect: &DialectConfig) -> bool {
     let trimmed = raw.trim();
-    trimmed.is_empty() || trimmed.starts_with("-9")
+    trimmed.is_empty() || dialect.invalid_sentinels.iter().any(|s| s.trim() == trimmed)
 }
 
 #[cfg(test)]
@@ -427,6 +1095,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keeps_raw_checksum_digits_when_asked() {
+        let dialect = DialectConfig {
+            keep_raw_checksum: true,
+            ..DialectConfig::default()
+        };
+        let lower = Frame::from_line_with("$PNORI,4,Signature1000_100297,4,18,0.20,1.00,0*4b", &dialect)
+            .expect("parse lowercase checksum");
+        let upper = Frame::from_line_with("$PNORI,4,Signature1000_100297,4,18,0.20,1.00,0*4B", &dialect)
+            .expect("parse uppercase checksum");
+        assert_eq!(lower.checksum.provided, upper.checksum.provided);
+        assert_eq!(lower.checksum.provided_raw.as_deref(), Some("4b"));
+        assert_eq!(upper.checksum.provided_raw.as_deref(), Some("4B"));
+
+        let json = serde_json::to_string(&lower).expect("serialize");
+        assert!(json.contains(r#""provided_raw":"4b""#));
+        let default = Frame::from_line("$PNORI,4,Signature1000_100297,4,18,0.20,1.00,0*4b").expect("parse");
+        assert_eq!(default.checksum.provided_raw, None);
+        assert!(!serde_json::to_string(&default).expect("serialize").contains("provided_raw"));
+    }
+
+    #[test]
+    fn tightened_field_counts_reject_extra_fields() {
+        let body = "PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0,17";
+        let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+        let line = format!("${body}*{checksum:02X}");
+        Frame::from_line(&line).expect("trailing fields are tolerated by default");
+
+        let dialect = DialectConfig {
+            field_counts: FieldCounts {
+                sensor: FieldRange { min: None, max: Some(13) },
+                ..FieldCounts::default()
+            },
+            ..DialectConfig::default()
+        };
+        let err = Frame::from_line_with(&line, &dialect).expect_err("extra field rejected");
+        let field_count = err.downcast_ref::<FieldCount>().expect("typed error");
+        assert_eq!(field_count.sentence, SentenceKind::Sensor);
+        assert_eq!((field_count.count, field_count.min, field_count.max), (14, 13, Some(13)));
+
+        let short = Frame::from_line("$PNORE,010526,220800*4E").expect_err("too few fields");
+        let field_count = short.downcast_ref::<FieldCount>().expect("default minimum is typed too");
+        assert_eq!((field_count.count, field_count.min), (2, 3));
+    }
+
+    #[test]
+    fn maps_coordinate_codes_from_the_dialect() {
+        let dialect = DialectConfig {
+            coordinate_codes: CoordinateCodes { enu: 5, xyz: 6, beam: 7 },
+            ..DialectConfig::default()
+        };
+        let frame = Frame::from_line_with("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,5*44", &dialect)
+            .expect("parse remapped config");
+        match frame.payload {
+            Payload::Config(cfg) => assert_eq!(cfg.coordinate_system, CoordinateSystem::Enu),
+            other => panic!("expected config, got {other:?}"),
+        }
+        let standard = Frame::from_line_with("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", &dialect)
+            .expect("parse standard code");
+        match standard.payload {
+            Payload::Config(cfg) => assert_eq!(cfg.coordinate_system, CoordinateSystem::Unknown(0)),
+            other => panic!("expected config, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_custom_dialect_like_standard_nmea() {
+        let dialect = DialectConfig {
+            delimiter: ';',
+            checksum_radix: ChecksumRadix::Decimal,
+            ..DialectConfig::default()
+        };
+        let custom = Frame::from_line_with(
+            "$PNORS;010526;220800;00000000;3ED40002;23.7;1532.0;275.4;-49.1;83.0;0.000;24.02;0;0*96",
+            &dialect,
+        )
+        .expect("parse custom dialect");
+        let standard = Frame::from_line(
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+        )
+        .expect("parse standard");
+        assert!(custom.checksum.valid);
+        assert_eq!(custom.payload, standard.payload);
+
+        let corrupted = "$PNORS;010526;220800;00000000;3ED40002;23.7;1532.0;275.4;-49.1;83.0;0.000;24.02;0;0*97";
+        assert!(Frame::from_line_with(corrupted, &dialect).is_err());
+    }
+
     #[test]
     fn parses_pnorc_current_with_invalid_flags() {
         let raw = "$PNORC,010526,220800,4,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*26";
@@ -443,6 +1199,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_pnore_echo() {
+        let raw = "$PNORE,010526,220800,12.34,87*6B";
+        let frame = Frame::from_line(raw).expect("parse echo");
+        match frame.payload {
+            Payload::Echo(echo) => {
+                let expected_ts = Utc.with_ymd_and_hms(2026, 1, 5, 22, 8, 0).unwrap();
+                assert_eq!(echo.sent_at, expected_ts);
+                assert_eq!(echo.distance_m, Some(12.34));
+                assert_eq!(echo.quality, Some(87));
+            }
+            _ => panic!("expected echo"),
+        }
+    }
+
+    #[test]
+    fn parses_pnore_event_with_commas_in_the_message() {
+        let body = "PNORE,010526,220800,E17,Beam 3 fault, amplitude low, check cable";
+        let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+        let frame = Frame::from_line(&format!("${body}*{checksum:02X}")).expect("parse event");
+        match &frame.payload {
+            Payload::Event(event) => {
+                assert_eq!(event.sent_at, Utc.with_ymd_and_hms(2026, 1, 5, 22, 8, 0).unwrap());
+                assert_eq!(event.code, "E17");
+                assert_eq!(event.message, "Beam 3 fault, amplitude low, check cable");
+            }
+            other => panic!("expected event, got {other:?}"),
+        }
+        assert_eq!(frame.payload.kind(), SentenceKind::Event);
+        let rebuilt = Frame::from_line(&frame.to_nmea_line()).expect("parse rebuilt line");
+        assert_eq!(rebuilt.payload, frame.payload);
+
+        // Numeric distance and quality still make an echo reading
+        let echo = Frame::from_line("$PNORE,010526,220800,12.34,87*6B").expect("parse echo");
+        assert_eq!(echo.payload.kind(), SentenceKind::Echo);
+    }
+
+    #[test]
+    fn pnore_event_with_a_numeric_code_is_told_apart_by_its_field_count() {
+        let checksummed = |body: &str| {
+            let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            format!("${body}*{checksum:02X}")
+        };
+        let event = Frame::from_line(&checksummed("PNORE,010526,220800,17,3,2")).expect("parse event");
+        match &event.payload {
+            Payload::Event(event) => {
+                assert_eq!(event.code, "17");
+                assert_eq!(event.message, "3,2");
+            }
+            other => panic!("expected event, got {other:?}"),
+        }
+
+        let worded = Frame::from_line(&checksummed("PNORE,010526,220800,17,Low battery")).expect("parse event");
+        assert_eq!(worded.payload.kind(), SentenceKind::Event);
+
+        let bare = Frame::from_line(&checksummed("PNORE,010526,220800,17")).expect("parse event without message");
+        match &bare.payload {
+            Payload::Event(event) => {
+                assert_eq!(event.code, "17");
+                assert_eq!(event.message, "");
+            }
+            other => panic!("expected event, got {other:?}"),
+        }
+
+        // Four numeric fields carry no marker of an event, so they stay a reading
+        let ambiguous = Frame::from_line(&checksummed("PNORE,010526,220800,17,3")).expect("parse echo");
+        assert_eq!(ambiguous.payload.kind(), SentenceKind::Echo);
+    }
+
+    #[test]
+    fn parses_pnorbt_bottom_track() {
+        let raw = "$PNORBT,010526,220800,12.41,12.38,12.52,12.47,0.012,-0.034,0.005,-9*01";
+        let frame = Frame::from_line(raw).expect("parse bottom track");
+        match &frame.payload {
+            Payload::BottomTrack(bottom) => {
+                let expected_ts = Utc.with_ymd_and_hms(2026, 1, 5, 22, 8, 0).unwrap();
+                assert_eq!(bottom.sent_at, expected_ts);
+                assert_eq!(bottom.range_beam_1_m, Some(12.41));
+                assert_eq!(bottom.range_beam_2_m, Some(12.38));
+                assert_eq!(bottom.range_beam_3_m, Some(12.52));
+                assert_eq!(bottom.range_beam_4_m, Some(12.47));
+                assert_eq!(bottom.velocity_1_m_s, Some(0.012));
+                assert_eq!(bottom.velocity_2_m_s, Some(-0.034));
+                assert_eq!(bottom.velocity_3_m_s, Some(0.005));
+                assert_eq!(bottom.velocity_4_m_s, None, "-9 marks an invalid velocity");
+            }
+            other => panic!("expected bottom track, got {other:?}"),
+        }
+        assert_eq!(frame.payload.kind(), SentenceKind::BottomTrack);
+        let rebuilt = Frame::from_line(&frame.to_nmea_line()).expect("parse rebuilt line");
+        assert_eq!(rebuilt.payload, frame.payload);
+
+        let short = Frame::from_line("$PNORBT,010526,220800,12.41*19").unwrap_err();
+        assert!(short.to_string().contains("PNORBT expects 10 fields"), "{short}");
+    }
+
     #[test]
     fn rejects_bad_checksum() {
         let raw = "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*40"; // wrong checksum
@@ -463,6 +1315,104 @@ mod tests {
         }
     }
 
+    #[test]
+    fn nmea_line_round_trips_every_sentence_type() {
+        for raw in [
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41",
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+            "$PNORC,010526,220800,4,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*26",
+            "$PNORC,010526,220800,1,-9.50,-9.99,-9.0,-9.99,-9,305.2,C,-9,-9,-9,-9,-9,-9,-9,-9*31",
+            "$PNORE,010526,220800,12.34,87*6B",
+        ] {
+            let original = Frame::from_line(raw).expect("parse original");
+            let line = original.to_nmea_line();
+            let reparsed = Frame::from_line(&line).expect("parse regenerated line");
+            assert!(reparsed.checksum.valid, "checksum of {line}");
+            assert_eq!(reparsed.payload, original.payload, "{raw} -> {line}");
+        }
+    }
+
+    #[test]
+    fn nmea_line_round_trips_in_a_custom_dialect() {
+        let site = DialectConfig {
+            delimiter: ';',
+            checksum_radix: ChecksumRadix::Decimal,
+            coordinate_codes: CoordinateCodes { enu: 3, xyz: 4, beam: 5 },
+            invalid_sentinels: vec!["NaN".to_string()],
+            ..DialectConfig::default()
+        };
+        let config = Frame::from_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41").expect("parse config");
+        let line = config.to_nmea_line_with(&site);
+        assert!(line.starts_with("$PNORI;4;") && line.contains(";3*"), "{line}");
+        let reparsed = Frame::from_line_with(&line, &site).expect("parse regenerated config");
+        assert!(reparsed.checksum.valid, "checksum of {line}");
+        assert_eq!(reparsed.payload, config.payload);
+
+        let current = Frame::from_line(
+            "$PNORC,010526,220800,1,-9.50,-9.99,-9.0,-9.99,-9,305.2,C,-9,-9,-9,-9,-9,-9,-9,-9*31",
+        )
+        .expect("parse current");
+        let line = current.to_nmea_line_with(&site);
+        assert!(line.contains(";-9.5;NaN;"), "{line}");
+        let reparsed = Frame::from_line_with(&line, &site).expect("parse regenerated current");
+        assert!(reparsed.checksum.valid, "checksum of {line}");
+        assert_eq!(reparsed.payload, current.payload);
+    }
+
+    #[test]
+    fn keeps_legitimate_values_near_sentinels() {
+        let raw = "$PNORC,010526,220800,1,-9.50,-9.99,-9.0,-9.99,-9,305.2,C,-9,-9,-9,-9,-9,-9,-9,-9*31";
+        let frame = Frame::from_line(raw).expect("parse near-sentinel values");
+        match frame.payload {
+            Payload::Current(cur) => {
+                assert_eq!(cur.velocity_1_m_s, Some(-9.5));
+                assert_eq!(cur.velocity_2_m_s, None);
+            }
+            _ => panic!("expected current"),
+        }
+    }
+
+    #[test]
+    fn sentinels_and_field_limit_follow_the_dialect() {
+        let raw = "$PNORC,010526,220800,1,-9.50,-9.99,-9.0,-9.99,-9,305.2,C,-9,-9,-9,-9,-9,-9,-9,-9*31";
+        let site = DialectConfig {
+            invalid_sentinels: vec!["-9.50".to_string()],
+            max_field_bytes: 6,
+            ..DialectConfig::default()
+        };
+        match Frame::from_line_with(raw, &site).expect("parse with site sentinels").payload {
+            Payload::Current(cur) => {
+                assert_eq!(cur.velocity_1_m_s, None);
+                assert_eq!(cur.velocity_2_m_s, Some(-9.99));
+            }
+            _ => panic!("expected current"),
+        }
+        match Frame::from_line(raw).expect("default dialect is unaffected").payload {
+            Payload::Current(cur) => assert_eq!(cur.velocity_1_m_s, Some(-9.5)),
+            _ => panic!("expected current"),
+        }
+
+        let tight = DialectConfig { max_field_bytes: 5, ..site };
+        let err = Frame::from_line_with(raw, &tight).expect_err("the date is six bytes");
+        assert_eq!(err.downcast_ref::<OversizedField>().expect("typed error").index, 1);
+    }
+
+    #[test]
+    fn rejects_enormous_fields_and_bounds_discarded_junk() {
+        let huge = "X".repeat(4 * 1024 * 1024);
+        let body = format!("PNORC{huge},010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18");
+        let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+        let err = Frame::from_line(&format!("${body}*{checksum:02X}")).expect_err("oversized field");
+        let oversized = err.downcast_ref::<OversizedField>().expect("typed error");
+        assert_eq!(oversized.index, 0);
+        assert_eq!(oversized.len, huge.len() + "PNORC".len());
+
+        let raw = format!("{huge}$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41");
+        let frame = Frame::from_line(&raw).expect("junk before a sentence is still skipped");
+        assert_eq!(frame.discarded.len(), 1);
+        assert_eq!(frame.discarded[0].len(), DEFAULT_MAX_FIELD_BYTES);
+    }
+
     #[test]
     fn parses_with_junk_and_records_it() {
         let raw = "prefix_junk$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41suffix_junk";
@@ -471,4 +1421,15 @@ mod tests {
         assert!(frame.discarded.contains(&"prefix_junk".to_string()));
         assert!(frame.discarded.contains(&"suffix_junk".to_string()));
     }
+
+    #[test]
+    fn from_bytes_validates_checksum_with_non_utf8_junk() {
+        let mut raw = vec![0xFF, 0xC3, b'x'];
+        raw.extend_from_slice(b"$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\r\n");
+        let frame = Frame::from_bytes(&raw).expect("parse config with binary junk");
+        assert!(frame.checksum.valid);
+        assert_eq!(frame.checksum.computed, 0x41);
+        assert_eq!(frame.discarded.len(), 1);
+        assert!(matches!(frame.payload, Payload::Config(_)));
+    }
 }
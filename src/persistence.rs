@@ -1,137 +1,657 @@
-use crate::parser::Frame;
+use crate::config::{OutputFormat, RetentionAction, SplitMode};
+use crate::manifest::{OutputKind, RunManifest};
+use crate::parser::{Frame, SentenceKind};
+use crate::retention;
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc, Weekday};
+use futures::Stream;
+use std::collections::{hash_map::Entry, HashMap};
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Duration, Instant};
 use tokio::{
     fs::{create_dir_all, File, OpenOptions},
-    io::AsyncWriteExt,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     sync::Mutex,
 };
 
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Date pattern of daily file names unless `with_date_format` says otherwise.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Write locks keyed by log path, shared by every `Persistence` in the process so separate
+/// instances appending to the same dated file (e.g. several recording tasks) take turns.
+static PATH_LOCKS: OnceLock<std::sync::Mutex<HashMap<PathBuf, Weak<Mutex<()>>>>> = OnceLock::new();
+
+fn path_lock(path: &Path) -> Arc<Mutex<()>> {
+    let mut locks = PATH_LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(lock) = locks.get(path).and_then(Weak::upgrade) {
+        return lock;
+    }
+    locks.retain(|_, lock| lock.strong_count() > 0);
+    let lock = Arc::new(Mutex::new(()));
+    locks.insert(path.to_path_buf(), Arc::downgrade(&lock));
+    lock
+}
+
+/// Makes new entries in `dir` durable by fsyncing the directory itself; without it a crash
+/// right after creating a file can lose the file on some filesystems even though its data
+/// was written. Directories cannot be synced this way on Windows, where this does nothing.
+pub(crate) async fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = dir.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            std::fs::File::open(&dir)
+                .and_then(|handle| handle.sync_all())
+                .with_context(|| format!("fsync directory {}", dir.display()))
+        })
+        .await
+        .context("directory fsync task failed")??;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// An open daily log plus the process-wide lock for its path.
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+    manifest: Option<Arc<RunManifest>>,
+}
+
+impl LogFile {
+    /// Writes each line with its newline in a single append while holding the path lock, so
+    /// concurrent writers never tear or interleave lines.
+    async fn write_lines(&mut self, lines: &[impl AsRef<str>]) -> Result<()> {
+        let mut buf = String::with_capacity(lines.iter().map(|l| l.as_ref().len() + 1).sum());
+        for line in lines {
+            buf.push_str(line.as_ref());
+            buf.push('\n');
+        }
+        let _guard = self.lock.lock().await;
+        self.file.write_all(buf.as_bytes()).await?;
+        self.file.flush().await?;
+        if let Some(manifest) = &self.manifest {
+            manifest.record(OutputKind::Persisted, &self.path, lines.len() as u64);
+        }
+        Ok(())
+    }
+}
+
 struct PersistenceInner {
-    date: Option<NaiveDate>,
-    file: Option<File>,
-    pending: Vec<String>,
+    /// Start of the period (see `SplitMode::bucket_start`) the open files cover.
+    bucket: Option<NaiveDateTime>,
+    /// The `.log` file: every frame in JSON mode, only raw lines in CSV mode.
+    file: Option<LogFile>,
+    /// CSV mode's per-sentence-type files for `bucket`, opened on first use.
+    csv: HashMap<SentenceKind, LogFile>,
+    /// Rendered lines of undated frames waiting for a dated one.
+    pending: Vec<(SentenceKind, String)>,
+    /// When the oldest line in `pending` was buffered.
+    pending_since: Option<Instant>,
+    /// Last write to the open files not yet fsynced.
+    unsynced_write: Option<Instant>,
+}
+
+/// Caps on how many dated files of one kind a `Persistence` (per prefix) or `Backup` keeps
+/// in its directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRetention {
+    /// Keep at most this many dated files, the open one included.
+    pub max_files: Option<usize>,
+    /// Expire files older than this many days: logs by their period's start relative to the
+    /// one being opened, backups by their last write.
+    pub max_age_days: Option<u64>,
+    pub action: RetentionAction,
 }
 
-/// Handles daily rotating files while serializing frames into structured log lines.
+impl DataRetention {
+    pub(crate) fn is_active(&self) -> bool {
+        self.max_files.is_some() || self.max_age_days.is_some()
+    }
+}
+
+/// Handles rotating (daily unless `with_split_mode` says otherwise) files while serializing frames into structured log lines.
 pub struct Persistence {
     base: PathBuf,
+    prefix: String,
+    /// Undated frames older than this are written to a host-dated file instead of waiting
+    /// forever for a dated frame; `None` keeps them buffered (replays rely on this).
+    pending_max_age: Option<Duration>,
+    manifest: Option<Arc<RunManifest>>,
+    retention: Option<DataRetention>,
+    /// strftime pattern for the date in daily file names.
+    date_format: String,
+    split: SplitMode,
+    /// Fsync the directory after opening each new file (`fsync_dir_on_rotate`).
+    sync_dir: bool,
+    format: OutputFormat,
     inner: Mutex<PersistenceInner>,
 }
 
 impl Persistence {
     pub async fn new(base_dir: impl AsRef<Path>) -> Result<Self> {
-        let base = base_dir.as_ref().to_path_buf();
-        create_dir_all(&base)
+        Self::with_prefix(base_dir, "adcp").await
+    }
+
+    /// Like `new`, but names the daily files `<prefix>-YYYY-MM-DD.log` (e.g. `quarantine`).
+    pub async fn with_prefix(base_dir: impl AsRef<Path>, prefix: &str) -> Result<Self> {
+        let base = base_dir.as_ref();
+        create_dir_all(base)
             .await
             .with_context(|| format!("failed to create data directory {}", base.display()))?;
-        Ok(Self {
-            base,
+        Ok(Self::unopened(base, prefix))
+    }
+
+    /// The backend for `base` without creating it, e.g. only to find the files it names.
+    fn unopened(base: &Path, prefix: &str) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            prefix: prefix.to_string(),
+            pending_max_age: None,
+            manifest: None,
+            retention: None,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            split: SplitMode::Daily,
+            sync_dir: false,
+            format: OutputFormat::Json,
             inner: Mutex::new(PersistenceInner {
-                date: None,
+                bucket: None,
                 file: None,
+                csv: HashMap::new(),
                 pending: Vec::new(),
+                pending_since: None,
+                unsynced_write: None,
             }),
-        })
+        }
+    }
+
+    pub fn with_pending_max_age(mut self, max_age: Duration) -> Self {
+        self.pending_max_age = Some(max_age);
+        self
+    }
+
+    /// Counts every line written towards the run's manifest.
+    pub fn with_manifest(mut self, manifest: Option<Arc<RunManifest>>) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Names daily files `<prefix>-<date>.log` with `date` rendered by this strftime pattern,
+    /// which must already be validated (see `filename_date_format`).
+    pub fn with_date_format(mut self, pattern: &str) -> Self {
+        self.date_format = pattern.to_string();
+        self
+    }
+
+    /// Rotates to a new file per `split` period of the frame timestamps, named by the mode's
+    /// pattern (e.g. `<prefix>-2026-W02.log` weekly).
+    pub fn with_split_mode(mut self, split: SplitMode) -> Self {
+        self.split = split;
+        self
+    }
+
+    /// Fsyncs the directory after each rotation so the new file's entry survives a crash.
+    pub fn with_dir_sync(mut self, sync_dir: bool) -> Self {
+        self.sync_dir = sync_dir;
+        self
+    }
+
+    /// Writes frames as `format`; in CSV mode each sentence type gets its own
+    /// `<prefix>-<type>-<date>.csv` starting with a header row.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sweeps older dated logs of this prefix each time a new dated file is opened.
+    pub fn with_retention(mut self, retention: DataRetention) -> Self {
+        self.retention = retention.is_active().then_some(retention);
+        self
+    }
+
+    /// Writes buffered undated frames to a file dated by the host clock once they have waited
+    /// longer than the configured max age. Returns true when a flush happened.
+    pub async fn flush_stale_pending(&self) -> Result<bool> {
+        let Some(max_age) = self.pending_max_age else {
+            return Ok(false);
+        };
+        let mut inner = self.inner.lock().await;
+        if inner.pending_since.is_none_or(|since| since.elapsed() < max_age) {
+            return Ok(false);
+        }
+        let bucket = self.split.bucket_start(Utc::now());
+        self.rotate(&mut inner, bucket).await?;
+        let count = inner.pending.len();
+        self.flush_pending(&mut inner).await?;
+        tracing::warn!(frames = count, period = %bucket, "no dated frame arrived; flushed undated frames using host clock");
+        Ok(true)
+    }
+
+    /// Fsyncs the open file once nothing has been written to it for `idle`, so the tail of a
+    /// burst is durable without waiting for OS writeback. Returns true when a sync happened.
+    pub async fn sync_if_idle(&self, idle: Duration) -> Result<bool> {
+        let mut inner = self.inner.lock().await;
+        if inner.unsynced_write.is_none_or(|written| written.elapsed() < idle) {
+            return Ok(false);
+        }
+        inner.unsynced_write = None;
+        let inner = &mut *inner;
+        for log in inner.file.iter_mut().chain(inner.csv.values_mut()) {
+            let _guard = log.lock.lock().await;
+            log.file
+                .sync_data()
+                .await
+                .with_context(|| format!("fsync {}", log.path.display()))?;
+        }
+        Ok(true)
     }
 
     pub async fn append(&self, frame: &Frame) -> Result<()> {
         let mut inner = self.inner.lock().await;
-        let frame_line = frame.to_persistence_line();
-        let frame_date = frame.payload.sent_at().map(|dt| dt.date_naive());
-
-        let target_date = match (frame_date, inner.date) {
-            (Some(date), current) if current != Some(date) => {
-                inner.file = Some(self.open_file(date).await?);
-                inner.date = Some(date);
-                // Flush any pending undated lines into the new file.
-                if !inner.pending.is_empty() {
-                    let pending = std::mem::take(&mut inner.pending);
-                    if let Some(file) = inner.file.as_mut() {
-                        for line in pending {
-                            file.write_all(line.as_bytes())
-                                .await
-                                .context("failed to write pending frame")?;
-                            file.write_all(b"\n")
-                                .await
-                                .context("failed to terminate pending frame")?;
-                        }
-                        file.flush().await.context("failed to flush pending frames")?;
-                    }
-                }
-                Some(date)
-            }
-            (Some(date), current) => {
-                if current.is_none() {
-                    inner.file = Some(self.open_file(date).await?);
-                    inner.date = Some(date);
-                    // Flush pending as above, though none expected when first file opens.
-                    if !inner.pending.is_empty() {
-                        let pending = std::mem::take(&mut inner.pending);
-                        if let Some(file) = inner.file.as_mut() {
-                            for line in pending {
-                                file.write_all(line.as_bytes())
-                                    .await
-                                    .context("failed to write pending frame")?;
-                                file.write_all(b"\n")
-                                    .await
-                                    .context("failed to terminate pending frame")?;
-                            }
-                            file.flush().await.context("failed to flush pending frames")?;
-                        }
-                    }
-                }
-                Some(date)
+        let kind = frame.payload.kind();
+        let line = match self.format {
+            OutputFormat::Json => frame.to_persistence_line(),
+            OutputFormat::Csv => crate::csv::row(frame),
+        };
+        // Rotation compares periods, so e.g. hourly files roll on the hour
+        let frame_bucket = frame.payload.sent_at().map(|dt| self.split.bucket_start(dt));
+
+        match (frame_bucket, inner.bucket) {
+            (Some(bucket), current) if current != Some(bucket) => {
+                self.rotate(&mut inner, bucket).await?;
+                // Flush any pending undated lines into the new period.
+                self.flush_pending(&mut inner).await?;
             }
-            (None, Some(date)) => Some(date),
+            (Some(_), _) | (None, Some(_)) => {}
             (None, None) => {
-                inner.pending.push(frame_line);
+                inner.pending.push((kind, line));
+                inner.pending_since.get_or_insert_with(Instant::now);
                 return Ok(());
             }
+        }
+        self.write_rows(&mut inner, &[(kind, line)])
+            .await
+            .context("failed to write frame")
+    }
+
+    /// Appends an unparsed line verbatim to the `.log` file for the period containing `at`
+    /// (e.g. the host time for rejected lines, which carry no trustworthy timestamp of their
+    /// own), whatever the output format.
+    pub async fn append_raw(&self, line: &str, at: DateTime<Utc>) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+        let bucket = self.split.bucket_start(at);
+        if inner.bucket != Some(bucket) {
+            self.rotate(&mut inner, bucket).await?;
+        }
+        if inner.file.is_none() {
+            inner.file = Some(self.open_file(bucket, None).await?);
+        }
+        let file = inner.file.as_mut().expect("file opened above");
+        file.write_lines(&[line])
+            .await
+            .context("failed to write raw line")?;
+        inner.unsynced_write = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Moves to the files for `bucket`: the `.log` opens right away in JSON mode, while CSV
+    /// files open as their sentence types arrive.
+    async fn rotate(&self, inner: &mut PersistenceInner, bucket: NaiveDateTime) -> Result<()> {
+        inner.csv.clear();
+        inner.file = match self.format {
+            OutputFormat::Json => Some(self.open_file(bucket, None).await?),
+            OutputFormat::Csv => None,
         };
+        inner.bucket = Some(bucket);
+        Ok(())
+    }
 
-        if let Some(file) = inner.file.as_mut() {
-            file.write_all(frame_line.as_bytes())
-                .await
-                .context("failed to write frame")?;
-            file.write_all(b"\n")
-                .await
-                .context("failed to terminate frame")?;
-            file.flush().await.context("failed to flush frame")?;
-        } else {
+    async fn flush_pending(&self, inner: &mut PersistenceInner) -> Result<()> {
+        inner.pending_since = None;
+        if inner.pending.is_empty() || inner.bucket.is_none() {
+            return Ok(());
+        }
+        let pending = std::mem::take(&mut inner.pending);
+        self.write_rows(inner, &pending)
+            .await
+            .context("failed to write pending frames")
+    }
+
+    /// Writes rendered frames to the open files of the current period.
+    async fn write_rows(&self, inner: &mut PersistenceInner, rows: &[(SentenceKind, String)]) -> Result<()> {
+        let Some(bucket) = inner.bucket else {
             // This should be unreachable, but keep a guard.
-            anyhow::bail!("persistence file not initialized for date {:?}", target_date);
+            anyhow::bail!("persistence file not initialized");
+        };
+        match self.format {
+            OutputFormat::Json => {
+                let Some(file) = inner.file.as_mut() else {
+                    anyhow::bail!("persistence file not initialized for period {bucket}");
+                };
+                let lines: Vec<&str> = rows.iter().map(|(_, line)| line.as_str()).collect();
+                file.write_lines(&lines).await?;
+            }
+            OutputFormat::Csv => {
+                let mut kinds: Vec<SentenceKind> = Vec::new();
+                for (kind, _) in rows {
+                    if !kinds.contains(kind) {
+                        kinds.push(*kind);
+                    }
+                }
+                for kind in kinds {
+                    let file = match inner.csv.entry(kind) {
+                        Entry::Occupied(open) => open.into_mut(),
+                        Entry::Vacant(slot) => slot.insert(self.open_file(bucket, Some(kind)).await?),
+                    };
+                    let lines: Vec<&str> = rows
+                        .iter()
+                        .filter(|(row_kind, _)| *row_kind == kind)
+                        .map(|(_, line)| line.as_str())
+                        .collect();
+                    file.write_lines(&lines).await?;
+                }
+            }
         }
+        inner.unsynced_write = Some(Instant::now());
         Ok(())
     }
 
-    async fn open_file(&self, date: NaiveDate) -> Result<File> {
-        let filename = format!("adcp-{}.log", date.format("%Y-%m-%d"));
-        let path = self.base.join(filename);
-        OpenOptions::new()
+    /// Opens the `.log` file for `bucket`, or with `kind` the CSV file of that sentence type,
+    /// writing its header row when the file is new.
+    async fn open_file(&self, bucket: NaiveDateTime, kind: Option<SentenceKind>) -> Result<LogFile> {
+        let path = self.dated_path_for(bucket, kind);
+        let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&path)
             .await
-            .with_context(|| format!("failed to open {}", path.display()))
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let lock = path_lock(&path);
+        if let Some(kind) = kind {
+            let _guard = lock.lock().await;
+            if file.metadata().await?.len() == 0 {
+                file.write_all(format!("{}\n", crate::csv::header(kind)).as_bytes())
+                    .await
+                    .with_context(|| format!("failed to write CSV header to {}", path.display()))?;
+            }
+        }
+        if self.sync_dir {
+            sync_dir(&self.base).await?;
+        }
+        if let Some(retention) = &self.retention {
+            // A failed sweep must never cost the frame being written
+            if let Err(err) = self.sweep(retention, bucket, kind).await {
+                tracing::warn!(dir = %self.base.display(), error = %err, "data retention sweep failed");
+            }
+        }
+        Ok(LogFile {
+            file,
+            lock,
+            path,
+            manifest: self.manifest.clone(),
+        })
+    }
+
+    /// Retires dated logs of this prefix beyond the configured count or age. Ages are measured
+    /// from `open_bucket`, the frame-time period just rotated to, so replays of old captures do
+    /// not expire everything they write; the file for `open_bucket` itself is never touched.
+    async fn sweep(&self, retention: &DataRetention, open_bucket: NaiveDateTime, kind: Option<SentenceKind>) -> Result<()> {
+        let mut dated = self.dated_files(kind).await?;
+        dated.retain(|(bucket, _)| *bucket != open_bucket);
+        // Newest first, so everything past the kept count is the oldest
+        dated.sort_by_key(|(bucket, _)| std::cmp::Reverse(*bucket));
+
+        let keep = retention.max_files.map(|max| max.saturating_sub(1));
+        for (index, (bucket, path)) in dated.into_iter().enumerate() {
+            let over_count = keep.is_some_and(|keep| index >= keep);
+            let too_old = retention
+                .max_age_days
+                .is_some_and(|days| (open_bucket - bucket).num_days() > days as i64);
+            if over_count || too_old {
+                retention::retire(&path, &retention.action).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every dated file of this prefix in the data directory with the period parsed from its
+    /// name: the `.log` files for `None`, or the CSV files of one sentence type.
+    async fn dated_files(&self, kind: Option<SentenceKind>) -> Result<Vec<(NaiveDateTime, PathBuf)>> {
+        let (stem, extension) = match kind {
+            Some(kind) => (format!("{}-{}-", self.prefix, kind.as_str()), ".csv"),
+            None => (format!("{}-", self.prefix), ".log"),
+        };
+        let mut dated = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.base)
+            .await
+            .with_context(|| format!("list {}", self.base.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            if let Some(bucket) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(stem.as_str()))
+                .and_then(|rest| rest.strip_suffix(extension))
+                .and_then(|stamp| self.parse_bucket(stamp))
+            {
+                dated.push((bucket, entry.path()));
+            }
+        }
+        Ok(dated)
+    }
+
+    /// The dated file covering the latest period, as named by this backend.
+    async fn latest_dated_file(&self, kind: Option<SentenceKind>) -> Option<(NaiveDateTime, PathBuf)> {
+        match self.dated_files(kind).await {
+            Ok(dated) => dated.into_iter().max_by_key(|(bucket, _)| *bucket),
+            Err(err) => {
+                tracing::warn!(dir = %self.base.display(), error = %err, "failed to list dated logs");
+                None
+            }
+        }
     }
 
     pub async fn current_path(&self) -> PathBuf {
         let inner = self.inner.lock().await;
-        let date = inner.date.unwrap_or_else(|| Utc::now().date_naive());
-        self.base
-            .join(format!("adcp-{}.log", date.format("%Y-%m-%d")))
+        let bucket = inner.bucket.unwrap_or_else(|| self.split.bucket_start(Utc::now()));
+        self.dated_path(bucket)
+    }
+
+    fn dated_path(&self, bucket: NaiveDateTime) -> PathBuf {
+        self.dated_path_for(bucket, None)
+    }
+
+    /// `<prefix>-<date>.log`, or `<prefix>-<type>-<date>.csv` for a CSV sentence type.
+    fn dated_path_for(&self, bucket: NaiveDateTime, kind: Option<SentenceKind>) -> PathBuf {
+        let stamp = bucket.format(self.split.file_pattern(&self.date_format));
+        match kind {
+            Some(kind) => self.base.join(format!("{}-{}-{stamp}.csv", self.prefix, kind.as_str())),
+            None => self.base.join(format!("{}-{stamp}.log", self.prefix)),
+        }
+    }
+
+    /// Reads the period start back from the stamp in a file name, filling in what the mode's
+    /// pattern leaves out (the Monday of a week, the first of a month, the top of an hour).
+    fn parse_bucket(&self, stamp: &str) -> Option<NaiveDateTime> {
+        use chrono::format::{parse, Parsed, StrftimeItems};
+        let mut parsed = Parsed::new();
+        parse(&mut parsed, stamp, StrftimeItems::new(self.split.file_pattern(&self.date_format))).ok()?;
+        match self.split {
+            SplitMode::Hourly => parsed.set_minute(0).ok()?,
+            SplitMode::Daily => {}
+            SplitMode::Weekly => parsed.set_weekday(Weekday::Mon).ok()?,
+            SplitMode::Monthly => parsed.set_day(1).ok()?,
+        }
+        let date = parsed.to_naive_date().ok()?;
+        let time = match self.split {
+            SplitMode::Hourly => parsed.to_naive_time().ok()?,
+            _ => chrono::NaiveTime::MIN,
+        };
+        Some(date.and_time(time))
     }
 }
 
+/// Reads a persisted NDJSON log back as `Frame`s, in file order. Blank lines are skipped;
+/// a line that does not deserialize (e.g. a partially written tail) yields an error naming
+/// the line and reading continues with the next one. A read failure ends the stream.
+pub async fn read_frames(path: &Path) -> Result<impl Stream<Item = Result<Frame>>> {
+    let file = File::open(path)
+        .await
+        .with_context(|| format!("open log {}", path.display()))?;
+    let state = (path.to_path_buf(), Some(BufReader::new(file).lines()), 0usize);
+    Ok(futures::stream::unfold(state, |(path, mut lines, mut line_number)| async move {
+        loop {
+            let line = match lines.as_mut()?.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(err) => {
+                    let err = anyhow::Error::new(err).context(format!("read log {}", path.display()));
+                    return Some((Err(err), (path, None, line_number)));
+                }
+            };
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let frame = serde_json::from_str::<Frame>(&line)
+                .with_context(|| format!("line {line_number} of {} is not a frame", path.display()));
+            return Some((frame, (path, lines, line_number)));
+        }
+    }))
+}
+
+struct TailState<L> {
+    logs: L,
+    kind: Option<SentenceKind>,
+    current: Option<(NaiveDateTime, PathBuf, BufReader<File>)>,
+    partial: String,
+    /// A later period has appeared; the current file is read to its end once more first.
+    draining: bool,
+}
+
+/// Follows the newest `adcp-<date>.log` in `data_dir` for log shippers, yielding each complete
+/// line as it is appended. The newest file is read from its start; once a later dated file
+/// appears, the remainder of the current file is drained, including an unterminated last
+/// line, and the stream switches over. See `tail_latest_with` for other naming.
+pub fn tail_latest(data_dir: &Path) -> impl Stream<Item = String> {
+    tail(Box::new(Persistence::unopened(data_dir, "adcp")), None)
+}
+
+/// Like `tail_latest`, but for the files `logs` writes, found through its prefix,
+/// `filename_date_format` and split mode: the `.log` for `None`, or the CSV of one sentence
+/// type. Files are ordered by the period in their names.
+pub fn tail_latest_with(logs: &Persistence, kind: Option<SentenceKind>) -> impl Stream<Item = String> + '_ {
+    tail(logs, kind)
+}
+
+fn tail<L: Deref<Target = Persistence>>(logs: L, kind: Option<SentenceKind>) -> impl Stream<Item = String> {
+    let state = TailState {
+        logs,
+        kind,
+        current: None,
+        partial: String::new(),
+        draining: false,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            let Some((bucket, path, reader)) = state.current.as_mut() else {
+                match state.logs.latest_dated_file(state.kind).await {
+                    Some((bucket, path)) => match File::open(&path).await {
+                        Ok(file) => state.current = Some((bucket, path, BufReader::new(file))),
+                        Err(err) => {
+                            tracing::warn!(file = %path.display(), error = %err, "failed to open log for tailing");
+                            tokio::time::sleep(TAIL_POLL_INTERVAL).await;
+                        }
+                    },
+                    None => tokio::time::sleep(TAIL_POLL_INTERVAL).await,
+                }
+                continue;
+            };
+
+            match reader.read_line(&mut state.partial).await {
+                Ok(_) if state.partial.ends_with('\n') => {
+                    let line = state.partial.trim_end_matches(['\r', '\n']).to_string();
+                    state.partial.clear();
+                    return Some((line, state));
+                }
+                // Ran into the end of the file mid-line; the next read sees whether more follows
+                Ok(n) if n > 0 => {}
+                Ok(_) if state.draining => {
+                    tracing::info!(from = %path.display(), "tail switching to next dated log");
+                    state.current = None;
+                    state.draining = false;
+                    if !state.partial.is_empty() {
+                        let line = std::mem::take(&mut state.partial);
+                        return Some((line.trim_end_matches('\r').to_string(), state));
+                    }
+                }
+                Ok(_) => {
+                    // At the end of the current file: switch only when a later period exists,
+                    // after one more read for whatever was written before it appeared.
+                    match state.logs.latest_dated_file(state.kind).await {
+                        Some((latest_bucket, _)) if latest_bucket > *bucket => state.draining = true,
+                        _ => tokio::time::sleep(TAIL_POLL_INTERVAL).await,
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(file = %path.display(), error = %err, "failed to read tailed log");
+                    tokio::time::sleep(TAIL_POLL_INTERVAL).await;
+                }
+            }
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::Frame;
+    use chrono::TimeZone;
     use std::fs;
     use tempfile::tempdir;
 
+    #[tokio::test]
+    async fn read_frames_round_trips_persisted_frames() {
+        use futures::StreamExt;
+
+        let tmp = tempdir().expect("temp dir");
+        let persistence = Persistence::new(tmp.path()).await.expect("persistence backend");
+        let originals: Vec<Frame> = [
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+            "$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23",
+            "$PNORC,010526,220800,2,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*20",
+        ]
+        .iter()
+        .map(|line| Frame::from_line(line).expect("parse frame"))
+        .collect();
+        for frame in &originals {
+            persistence.append(frame).await.expect("persist frame");
+        }
+        let log = persistence.current_path().await;
+        // A torn tail must be reported without hiding the frames before it
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .and_then(|mut f| std::io::Write::write_all(&mut f, b"{\"recorded_at\":"))
+            .expect("append torn line");
+
+        let read: Vec<Result<Frame>> = read_frames(&log).await.expect("open log").collect().await;
+        assert_eq!(read.len(), originals.len() + 1);
+        for (read, original) in read.iter().zip(&originals) {
+            assert_eq!(read.as_ref().expect("frame"), original);
+        }
+        let torn = read.last().expect("torn line").as_ref().expect_err("torn line is an error");
+        assert!(torn.to_string().contains("line 4"), "{torn}");
+    }
+
     #[tokio::test]
     async fn rotates_using_frame_timestamp_date() {
         let tmp = tempdir().expect("temp dir");
@@ -160,6 +680,93 @@ mod tests {
         assert!(content.contains("PNORS"));
     }
 
+    #[tokio::test]
+    async fn retention_keeps_only_the_newest_dated_logs() {
+        let tmp = tempdir().expect("temp dir");
+        // Another prefix sharing the directory is not this sweep's business
+        fs::write(tmp.path().join("quarantine-2026-01-01.log"), "{}\n").expect("write quarantine log");
+        let persistence = Persistence::new(tmp.path())
+            .await
+            .expect("persistence backend")
+            .with_retention(DataRetention {
+                max_files: Some(2),
+                max_age_days: None,
+                action: RetentionAction::Delete,
+            });
+
+        let first = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        for day in 0..5 {
+            let at = first + chrono::Duration::days(day);
+            persistence.append_raw("{}", at).await.expect("append line");
+        }
+
+        let mut remaining: Vec<String> = fs::read_dir(tmp.path())
+            .expect("list data dir")
+            .map(|entry| entry.expect("entry").file_name().to_string_lossy().to_string())
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec!["adcp-2026-01-04.log", "adcp-2026-01-05.log", "quarantine-2026-01-01.log"]
+        );
+    }
+
+    fn sensor_at(date: &str, time: &str) -> Frame {
+        let body = format!("PNORS,{date},{time},00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0");
+        let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+        Frame::from_line(&format!("${body}*{checksum:02X}")).expect("parse sensor")
+    }
+
+    #[tokio::test]
+    async fn rotates_on_each_split_mode_boundary() {
+        let cases = [
+            (SplitMode::Hourly, ("010526", "225959"), ("010526", "230000"), ["adcp-2026-01-05T22.log", "adcp-2026-01-05T23.log"]),
+            (SplitMode::Daily, ("010526", "235959"), ("010626", "000000"), ["adcp-2026-01-05.log", "adcp-2026-01-06.log"]),
+            // Sunday 2026-01-04 closes ISO week 1; Monday opens week 2
+            (SplitMode::Weekly, ("010426", "235959"), ("010526", "000000"), ["adcp-2026-W01.log", "adcp-2026-W02.log"]),
+            (SplitMode::Monthly, ("013126", "235959"), ("020126", "000000"), ["adcp-2026-01.log", "adcp-2026-02.log"]),
+        ];
+        for (split, before, after, expected) in cases {
+            let tmp = tempdir().expect("temp dir");
+            let persistence = Persistence::new(tmp.path())
+                .await
+                .expect("persistence backend")
+                .with_split_mode(split);
+            let first = sensor_at(before.0, before.1);
+            persistence.append(&first).await.expect("persist first");
+            persistence.append(&sensor_at(after.0, after.1)).await.expect("persist second");
+
+            let mut files: Vec<String> = fs::read_dir(tmp.path())
+                .expect("list data dir")
+                .map(|entry| entry.expect("entry").file_name().to_string_lossy().to_string())
+                .collect();
+            files.sort();
+            assert_eq!(files, expected, "{split:?}");
+
+            // Retention reads the period back out of the name
+            let stamp = expected[0].trim_start_matches("adcp-").trim_end_matches(".log");
+            let sent_at = first.payload.sent_at().expect("timestamped");
+            assert_eq!(persistence.parse_bucket(stamp), Some(split.bucket_start(sent_at)), "{split:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rotation_with_dir_sync_keeps_both_files() {
+        let tmp = tempdir().expect("temp dir");
+        let persistence = Persistence::new(tmp.path())
+            .await
+            .expect("persistence backend")
+            .with_dir_sync(true);
+        persistence.append(&sensor_at("010526", "235959")).await.expect("persist first day");
+        persistence.append(&sensor_at("010626", "000000")).await.expect("persist after rotation");
+
+        // The fsync itself is not observable here; rotation must still work with it on
+        for name in ["adcp-2026-01-05.log", "adcp-2026-01-06.log"] {
+            let content = fs::read_to_string(tmp.path().join(name)).expect("read rotated log");
+            assert_eq!(content.lines().count(), 1, "{name}");
+        }
+    }
+
     #[tokio::test]
     async fn buffers_undated_until_df100_timestamp_present() {
         let tmp = tempdir().expect("temp dir");
@@ -186,4 +793,200 @@ mod tests {
         assert!(content.contains("PNORI"));
         assert!(content.contains("PNORS"));
     }
+
+    #[tokio::test]
+    async fn tail_follows_across_day_boundary() {
+        use futures::StreamExt;
+        use std::io::Write;
+        use std::time::Duration;
+
+        let tmp = tempdir().expect("temp dir");
+        let day1 = tmp.path().join("adcp-2026-01-05.log");
+        fs::write(&day1, "first\n").expect("write day1");
+
+        let tail = tail_latest(tmp.path());
+        futures::pin_mut!(tail);
+        let wait = Duration::from_secs(5);
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("first line").as_deref(), Some("first"));
+
+        let mut file = fs::OpenOptions::new().append(true).open(&day1).expect("open day1");
+        writeln!(file, "second").expect("append day1");
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("appended line").as_deref(), Some("second"));
+
+        // Midnight: the old file goes idle after a final write and the next day appears.
+        writeln!(file, "last of day").expect("append day1");
+        fs::write(tmp.path().join("adcp-2026-01-06.log"), "new day\n").expect("write day2");
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("drained line").as_deref(), Some("last of day"));
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("next day line").as_deref(), Some("new day"));
+    }
+
+    #[tokio::test]
+    async fn tail_keeps_the_unterminated_last_line_when_the_day_rotates() {
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let tmp = tempdir().expect("temp dir");
+        fs::write(tmp.path().join("adcp-2026-01-05.log"), "first\npartial").expect("write day1");
+
+        let tail = tail_latest(tmp.path());
+        futures::pin_mut!(tail);
+        let wait = Duration::from_secs(5);
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("first line").as_deref(), Some("first"));
+
+        // The writer rotated mid-line: the old file never gets its newline.
+        fs::write(tmp.path().join("adcp-2026-01-06.log"), "new day\n").expect("write day2");
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("partial line").as_deref(), Some("partial"));
+        assert_eq!(tokio::time::timeout(wait, tail.next()).await.expect("next day line").as_deref(), Some("new day"));
+    }
+
+    #[tokio::test]
+    async fn tail_orders_by_configured_date_format_and_follows_csv() {
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let tmp = tempdir().expect("temp dir");
+        // Day-first names sort wrongly as text: 31-12-2025 would beat 01-01-2026.
+        fs::write(tmp.path().join("adcp-31-12-2025.log"), "old year\n").expect("write old");
+        fs::write(tmp.path().join("adcp-01-01-2026.log"), "new year\n").expect("write new");
+        fs::write(tmp.path().join("adcp-notes.log"), "not dated\n").expect("write decoy");
+        fs::write(tmp.path().join("adcp-sensor-01-01-2026.csv"), "timestamp\n").expect("write csv");
+        fs::write(tmp.path().join("adcp-sensor-02-01-2026.csv"), "timestamp,temperature\n").expect("write csv");
+        let logs = Persistence::new(tmp.path())
+            .await
+            .expect("persistence backend")
+            .with_date_format("%d-%m-%Y")
+            .with_output_format(OutputFormat::Csv);
+        let wait = Duration::from_secs(5);
+
+        let raw = tail_latest_with(&logs, None);
+        futures::pin_mut!(raw);
+        assert_eq!(tokio::time::timeout(wait, raw.next()).await.expect("log line").as_deref(), Some("new year"));
+
+        let sensor = tail_latest_with(&logs, Some(SentenceKind::Sensor));
+        futures::pin_mut!(sensor);
+        assert_eq!(
+            tokio::time::timeout(wait, sensor.next()).await.expect("csv line").as_deref(),
+            Some("timestamp,temperature")
+        );
+    }
+
+    #[tokio::test]
+    async fn flushes_stale_undated_frames_to_host_dated_file() {
+        let tmp = tempdir().expect("temp dir");
+        let persistence = Persistence::new(tmp.path())
+            .await
+            .expect("persistence backend")
+            .with_pending_max_age(Duration::from_millis(50));
+
+        let config = Frame::from_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41")
+            .expect("parse config");
+        persistence.append(&config).await.expect("buffer config");
+        assert!(!persistence.flush_stale_pending().await.expect("early check"));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(persistence.flush_stale_pending().await.expect("stale flush"));
+
+        let host_dated = tmp
+            .path()
+            .join(format!("adcp-{}.log", Utc::now().date_naive().format("%Y-%m-%d")));
+        let content = fs::read_to_string(host_dated).expect("read host-dated log");
+        assert!(content.contains("Signature1000_100297"));
+        assert!(!persistence.flush_stale_pending().await.expect("nothing left"));
+    }
+
+    #[tokio::test]
+    async fn csv_mode_writes_one_file_per_sentence_type_with_a_single_header() {
+        let tmp = tempdir().expect("temp dir");
+        let lines = [
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41",
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+            "$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23",
+        ];
+        // A restart appending to the same day's files must not repeat the header
+        for _ in 0..2 {
+            let persistence = Persistence::new(tmp.path())
+                .await
+                .expect("persistence backend")
+                .with_output_format(OutputFormat::Csv);
+            for line in lines {
+                persistence.append(&Frame::from_line(line).expect("parse")).await.expect("append");
+            }
+        }
+
+        let currents = fs::read_to_string(tmp.path().join("adcp-current-2026-01-05.csv")).expect("read current csv");
+        let rows: Vec<&str> = currents.lines().collect();
+        assert_eq!(rows.len(), 3, "{currents}");
+        assert_eq!(rows[0], crate::csv::header(SentenceKind::Current));
+        assert_eq!(rows[1], rows[2]);
+        let columns: HashMap<&str, &str> = rows[0].split(',').zip(rows[1].split(',')).collect();
+        assert_eq!(columns["recorded_at"], "2026-01-05T22:08:00Z");
+        assert_eq!(columns["cell_number"], "1");
+        assert_eq!(columns["velocity_1_m_s"], "0.56");
+        assert_eq!(columns["velocity_4_m_s"], "-1.33");
+        assert_eq!(columns["direction_deg"], "305.2");
+        assert_eq!(columns["amplitude_unit"], "counts");
+        assert_eq!(columns["amplitude_beam_2"], "88");
+        assert_eq!(columns["correlation_beam_4_pct"], "18");
+
+        for kind in ["config", "sensor"] {
+            let path = tmp.path().join(format!("adcp-{kind}-2026-01-05.csv"));
+            let content = fs::read_to_string(&path).unwrap_or_else(|err| panic!("read {}: {err}", path.display()));
+            assert_eq!(content.lines().count(), 3, "{content}");
+        }
+        assert!(!tmp.path().join("adcp-2026-01-05.log").exists(), "no JSON log in CSV mode");
+    }
+
+    #[tokio::test]
+    async fn syncs_the_tail_of_a_burst_once_idle() {
+        let tmp = tempdir().expect("temp dir");
+        let persistence = Persistence::new(tmp.path()).await.expect("persistence backend");
+        let idle = Duration::from_millis(100);
+        assert!(!persistence.sync_if_idle(idle).await.expect("nothing written"));
+
+        for cell in 1..=3 {
+            let body = format!("PNORC,010526,220800,{cell},0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18");
+            let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            let frame = Frame::from_line(&format!("${body}*{checksum:02X}")).expect("parse current");
+            persistence.append(&frame).await.expect("append");
+        }
+        assert!(!persistence.sync_if_idle(idle).await.expect("burst still running"));
+
+        tokio::time::sleep(idle + Duration::from_millis(50)).await;
+        assert!(persistence.sync_if_idle(idle).await.expect("idle sync"));
+        assert!(!persistence.sync_if_idle(idle).await.expect("already synced"));
+        let log = fs::read_to_string(tmp.path().join("adcp-2026-01-05.log")).expect("read log");
+        assert_eq!(log.lines().count(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_writers_never_tear_lines() {
+        let tmp = tempdir().expect("temp dir");
+        let at = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        let mut tasks = Vec::new();
+        for writer in 0..8 {
+            let dir = tmp.path().to_path_buf();
+            tasks.push(tokio::spawn(async move {
+                // Each task has its own instance, as separate recording tasks would
+                let persistence = Persistence::with_prefix(&dir, "shared").await.expect("persistence");
+                for seq in 0..100 {
+                    let line = format!("{writer}:{seq}:{}", "x".repeat(16 * 1024));
+                    persistence.append_raw(&line, at).await.expect("append");
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.expect("writer task");
+        }
+
+        let content = fs::read_to_string(tmp.path().join("shared-2026-01-05.log")).expect("read log");
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 800);
+        for line in lines {
+            let mut parts = line.splitn(3, ':');
+            let (writer, seq, body) = (parts.next(), parts.next(), parts.next().unwrap_or_default());
+            assert!(writer.is_some() && seq.is_some(), "torn line prefix");
+            assert_eq!(body.len(), 16 * 1024, "torn line body");
+            assert!(body.bytes().all(|b| b == b'x'), "interleaved line");
+        }
+    }
 }
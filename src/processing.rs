@@ -1,17 +1,153 @@
-use anyhow::{Context, Result};
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::{fs, sync::watch, time::sleep};
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::{
+    fs,
+    sync::{watch, Semaphore},
+    time::sleep,
+};
 
-use crate::{simulator, AppConfig};
+use crate::parser::{CoordinateSystem, CurrentSentence, Frame, Payload};
+use crate::manifest::{OutputKind, RunManifest};
+use crate::metrics::Metrics;
+use crate::{persistence, qc, simulator, AppConfig, ProcessedNaming};
 
 const SCAN_INTERVAL_SECS: u64 = 2;
+/// Name of the watermark kept in `processed_folder`.
+const WATERMARK_FILE: &str = ".processing-watermark.json";
+
+/// Files processed successfully that are still in the processing folder, by name with the
+/// mtime they were processed at, persisted so a restart never replays them even when the move
+/// out of the processing folder failed or both folders are the same. Keyed by name rather
+/// than a position in the sort order, so a backfilled file that sorts before earlier work
+/// (e.g. copied in with `cp -p`, keeping an old mtime) is still processed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+struct Watermark {
+    processed: BTreeMap<String, u64>,
+}
+
+impl Watermark {
+    /// True when `name` was processed and has not been modified since.
+    fn covers(&self, name: &str, modified_unix: u64) -> bool {
+        self.processed.get(name) == Some(&modified_unix)
+    }
+
+    /// Records `name` as processed, forgetting files no longer in the folder so the set stays
+    /// as small as the folder itself.
+    fn record(&mut self, name: String, modified_unix: u64, present: &HashSet<String>) {
+        self.processed.retain(|name, _| present.contains(name));
+        self.processed.insert(name, modified_unix);
+    }
+}
+
+/// A file whose replay failed and waits for a retry on a later scan.
+#[derive(Debug, Default)]
+struct FailedReplay {
+    attempts: u32,
+    /// Capture records the failed attempts got through, skipped by the retry.
+    replayed: usize,
+}
+
+async fn load_watermark(dir: &Path) -> Option<Watermark> {
+    let path = dir.join(WATERMARK_FILE);
+    let raw = fs::read_to_string(&path).await.ok()?;
+    match serde_json::from_str(&raw) {
+        Ok(watermark) => Some(watermark),
+        Err(err) => {
+            tracing::warn!(file = %path.display(), error = %err, "ignoring unreadable processing watermark");
+            None
+        }
+    }
+}
+
+async fn store_watermark(dir: &Path, watermark: &Watermark) -> Result<()> {
+    let path = dir.join(WATERMARK_FILE);
+    let tmp = dir.join(format!("{WATERMARK_FILE}.partial"));
+    fs::write(&tmp, serde_json::to_vec(watermark)?)
+        .await
+        .with_context(|| format!("write watermark {}", tmp.display()))?;
+    fs::rename(&tmp, &path)
+        .await
+        .with_context(|| format!("replace watermark {}", path.display()))
+}
+
+async fn modified_unix(path: &Path) -> Result<u64> {
+    let modified = fs::metadata(path).await?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
+}
+
+/// Suffix a worker appends to a file it has claimed for processing (see `adcp-proc-manager`).
+pub const PROCESSING_SUFFIX: &str = ".processing";
+
+/// Renames `.processing` files in `dir` untouched for longer than `stuck_secs` back to their
+/// base name, so a file claimed by a worker that crashed is retried on the next scan instead
+/// of being skipped forever. Returns the restored paths; an unreadable `dir` restores nothing.
+pub async fn recover_stuck_claims(dir: &Path, stuck_secs: u64) -> Vec<PathBuf> {
+    let mut recovered = Vec::new();
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return recovered;
+    };
+    let threshold = Duration::from_secs(stuck_secs);
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let claimed = entry.path();
+        let Some(base) = claimed
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(PROCESSING_SUFFIX))
+        else {
+            continue;
+        };
+        let base = dir.join(base);
+        let age = match entry.metadata().await.and_then(|m| m.modified()) {
+            Ok(modified) => SystemTime::now().duration_since(modified).unwrap_or_default(),
+            Err(_) => continue,
+        };
+        if age <= threshold {
+            continue;
+        }
+        if base.exists() {
+            tracing::warn!(file = %claimed.display(), "stuck claim not reverted: base name already exists");
+            continue;
+        }
+        match fs::rename(&claimed, &base).await {
+            Ok(()) => {
+                tracing::warn!(
+                    file = %base.display(),
+                    stuck_secs = age.as_secs(),
+                    "recovered file stuck in processing; it will be retried"
+                );
+                recovered.push(base);
+            }
+            Err(err) => tracing::error!(file = %claimed.display(), error = %err, "failed to revert stuck claim"),
+        }
+    }
+    recovered
+}
 
 /// Scans the data process folder and processes stable files in chronological order.
+/// With `run_once` set it makes a single pass and returns, e.g. for cron-driven backfills.
 pub async fn run_processing_loop(
     config: Arc<AppConfig>,
     shutdown: watch::Receiver<()>,
+) -> Result<()> {
+    run_processing_session(config, shutdown, Arc::new(Metrics::new()), Arc::new(RunManifest::new())).await
+}
+
+/// `run_processing_loop`, adding replayed frame counts to `metrics` and every output file to
+/// `manifest` so the caller can summarize the session.
+pub async fn run_processing_session(
+    config: Arc<AppConfig>,
+    shutdown: watch::Receiver<()>,
+    metrics: Arc<Metrics>,
+    manifest: Arc<RunManifest>,
 ) -> Result<()> {
     let data_dir = PathBuf::from(&config.data_process_folder);
     let processed_dir = PathBuf::from(&config.processed_folder);
@@ -23,6 +159,18 @@ pub async fn run_processing_loop(
 
     // File stability timeout configurable from AppConfig
     let stable_secs = config.file_stability_seconds;
+    let mut read_dir_failures: u32 = 0;
+    let replay_options = simulator::ReplayOptions {
+        manifest: Some(manifest.clone()),
+        ..simulator::ReplayOptions::default()
+    };
+    // Failed replays per file name, so transient failures are retried on later scans
+    let mut failed_attempts: HashMap<String, FailedReplay> = HashMap::new();
+    let mut watermark = if config.force_reprocess {
+        Watermark::default()
+    } else {
+        load_watermark(&processed_dir).await.unwrap_or_default()
+    };
 
     loop {
         // Check for shutdown
@@ -31,10 +179,39 @@ pub async fn run_processing_loop(
             break;
         }
 
+        recover_stuck_claims(&data_dir, config.processing_stuck_seconds).await;
         let mut entries = match fs::read_dir(&data_dir).await {
-            Ok(rd) => rd,
+            Ok(rd) => {
+                if read_dir_failures > 0 {
+                    tracing::info!(folder = %data_dir.display(), failures = read_dir_failures, "processing folder readable again");
+                }
+                read_dir_failures = 0;
+                rd
+            }
             Err(err) => {
-                tracing::error!(error = %err, folder = %data_dir.display(), "failed to read processing folder");
+                read_dir_failures = read_dir_failures.saturating_add(1);
+                if config.run_once {
+                    return Err(err).with_context(|| format!("read processing folder {}", data_dir.display()));
+                }
+                tracing::error!(error = %err, folder = %data_dir.display(), failures = read_dir_failures, "failed to read processing folder");
+                // Transient failures just retry; escalate once the threshold is reached.
+                if read_dir_failures == config.read_dir_failure_threshold {
+                    tracing::error!(
+                        folder = %data_dir.display(),
+                        failures = read_dir_failures,
+                        webhook = config.alert_webhook.as_deref().unwrap_or_default(),
+                        "health alert triggered: processing folder unreadable"
+                    );
+                    if config.exit_on_read_dir_failure {
+                        return Err(err).with_context(|| {
+                            format!(
+                                "processing folder {} unreadable after {} consecutive attempts",
+                                data_dir.display(),
+                                read_dir_failures
+                            )
+                        });
+                    }
+                }
                 sleep(Duration::from_secs(SCAN_INTERVAL_SECS)).await;
                 continue;
             }
@@ -46,9 +223,8 @@ pub async fn run_processing_loop(
             if !path.is_file() {
                 continue;
             }
-            // Skip writer marker files ("*.writing"); only process raw data files
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.ends_with(".writing") {
+                if !is_replay_candidate(name) {
                     continue;
                 }
             }
@@ -62,6 +238,13 @@ pub async fn run_processing_loop(
             .unwrap_or_default()
         );
 
+        let present: HashSet<String> = files
+            .iter()
+            .filter_map(|file| file.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .collect();
+        // Forget files that vanished since their failure, e.g. moved away by an operator
+        failed_attempts.retain(|name, _| present.contains(name));
+
         let mut any_work = false;
         for file in files {
             if shutdown.has_changed().unwrap_or(false) {
@@ -69,27 +252,90 @@ pub async fn run_processing_loop(
                 break;
             }
 
+            let name = file
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let modified = modified_unix(&file).await.unwrap_or(u64::MAX);
+            if watermark.covers(&name, modified) {
+                tracing::debug!(file = %file.display(), "skipping file already processed");
+                continue;
+            }
+
             // Check stability
             match is_stable(&file, stable_secs).await {
                 Ok(true) => {
                     tracing::info!(file = %file.display(), "processing stable file (no recent writer marker detected)");
-                    any_work = true;
-                    match simulator::replay_sample(&file, &config).await {
+                    // A retry resumes after the records earlier attempts already persisted
+                    let resume_after = failed_attempts.get(&name).map_or(0, |failed| failed.replayed);
+                    let progress = Arc::new(AtomicUsize::new(resume_after));
+                    let options = simulator::ReplayOptions {
+                        resume_after,
+                        progress: Some(progress.clone()),
+                        ..replay_options.clone()
+                    };
+                    match simulator::replay_sample_with_options(&file, &config, &options).await {
                         Ok(res) => {
+                            any_work = true;
+                            failed_attempts.remove(&name);
+                            metrics.record_replayed(
+                                res.frames_processed as u64,
+                                res.parse_errors as u64,
+                                res.persistence_errors as u64,
+                            );
+                            watermark.record(name, modified, &present);
+                            if let Err(err) = store_watermark(&processed_dir, &watermark).await {
+                                tracing::warn!(file = %file.display(), error = %err, "failed to persist processing watermark");
+                            }
                             if !res.failures.is_empty() {
                                 // Task: .failed files should only include the contents which failed.
                                 if let Err(err) = write_failures(&file, &processed_dir, &res.failures).await {
                                     tracing::error!(file = %file.display(), error = %err, "failed to write partial failures");
                                 }
                             }
-                            if let Err(err) = move_to_processed(&file, &processed_dir).await {
-                                tracing::error!(file = %file.display(), error = %err, "failed to move processed file");
+                            let summary = if config.write_qc_summary {
+                                qc::summarize(&file, &config)
+                                    .await
+                                    .inspect_err(|err| tracing::warn!(file = %file.display(), error = %err, "failed to compute QC summary"))
+                                    .ok()
+                            } else {
+                                None
+                            };
+                            match move_to_processed(&file, &processed_dir, &config.processed_naming).await {
+                                Ok(dest) => {
+                                    manifest.record(OutputKind::Processed, &dest, res.frames_processed as u64);
+                                    if let Some(summary) = &summary {
+                                        if let Err(err) = qc::write_sidecar(&dest, summary).await {
+                                            tracing::warn!(file = %dest.display(), error = %err, "failed to write QC summary");
+                                        }
+                                    }
+                                }
+                                Err(err) => {
+                                    tracing::error!(file = %file.display(), error = %err, "failed to move processed file");
+                                }
                             }
                         }
                         Err(err) => {
+                            let failed = failed_attempts.entry(name.clone()).or_default();
+                            failed.attempts += 1;
+                            failed.replayed = progress.load(Ordering::Relaxed);
+                            // A run_once pass has no later scan to retry on
+                            if !config.run_once && failed.attempts <= config.max_processing_retries {
+                                tracing::warn!(
+                                    file = %file.display(),
+                                    error = %err,
+                                    attempt = failed.attempts,
+                                    resume_after = failed.replayed,
+                                    max_retries = config.max_processing_retries,
+                                    "processing failed; retrying on the next scan"
+                                );
+                                continue;
+                            }
+                            failed_attempts.remove(&name);
+                            any_work = true;
                             tracing::error!(file = %file.display(), error = %err, "processing failed (critical error)");
                             // Move entire file to processed folder with .failed suffix if we couldn't even read/replay it.
-                            if let Err(move_err) = move_failed(&file, &processed_dir).await {
+                            if let Err(move_err) = move_failed(&file, &processed_dir, &config.processed_naming).await {
                                 tracing::error!(file = %file.display(), error = %move_err, "failed to move failed file");
                             }
                         }
@@ -109,14 +355,104 @@ pub async fn run_processing_loop(
             tracing::warn!(error = %err, "failed to cleanup stale markers");
         }
 
+        if config.run_once {
+            tracing::info!(folder = %data_dir.display(), "single processing pass complete");
+            break;
+        }
+
         if !any_work {
             sleep(Duration::from_secs(SCAN_INTERVAL_SECS)).await;
         }
     }
 
+    if config.write_manifest {
+        if let Err(err) = manifest.write(&config.data_directory) {
+            tracing::warn!(error = %err, "failed to write run manifest");
+        }
+    }
+
     Ok(())
 }
 
+/// Builds the semaphore that bounds concurrent replays, sized from `processing_concurrency`.
+/// A configured value of zero is treated as one so processing can still make progress.
+pub fn replay_permits(config: &AppConfig) -> Arc<Semaphore> {
+    Arc::new(Semaphore::new(config.processing_concurrency.max(1)))
+}
+
+/// A file in `processed_folder`, as reported by the `cmd.data.list` RPC.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProcessedFile {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Modification time in Unix seconds (when the file was moved into the folder).
+    pub modified_unix: u64,
+}
+
+/// Parses the query part of `cmd.data.list?since=<unix_ts>`. An empty query lists everything;
+/// a `since` in the future is clamped to now so a skewed client clock cannot hide new files.
+pub fn parse_list_query(query: &str) -> Result<Option<SystemTime>> {
+    let mut since = None;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        match pair.split_once('=') {
+            Some(("since", value)) => {
+                let secs: u64 = value
+                    .parse()
+                    .with_context(|| format!("since '{value}' is not a unix timestamp"))?;
+                let requested = UNIX_EPOCH + Duration::from_secs(secs);
+                since = Some(requested.min(SystemTime::now()));
+            }
+            _ => bail!("unsupported list parameter '{pair}'"),
+        }
+    }
+    Ok(since)
+}
+
+/// Lists processed files modified after `since` (all files when `None`), oldest first,
+/// so incremental consumers can poll for new output without re-listing the archive.
+pub async fn list_processed(dir: &Path, since: Option<SystemTime>) -> Result<Vec<ProcessedFile>> {
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("read processed folder {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let meta = entry.metadata().await?;
+        if !meta.is_file() {
+            continue;
+        }
+        let modified = meta.modified()?;
+        if since.is_some_and(|since| modified <= since) {
+            continue;
+        }
+        files.push(ProcessedFile {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size_bytes: meta.len(),
+            modified_unix: modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        });
+    }
+    files.sort_by(|a, b| (a.modified_unix, &a.name).cmp(&(b.modified_unix, &b.name)));
+    Ok(files)
+}
+
+/// Reads a persisted NDJSON log and returns the `$PNORC` readings for one depth `cell`,
+/// ordered by `sent_at`, e.g. to plot a single bin over time. Lines that are not valid frames
+/// (such as a partially written tail) are skipped with a warning.
+pub async fn extract_cell_series(log_path: &Path, cell: u16) -> Result<Vec<CurrentSentence>> {
+    let mut frames = std::pin::pin!(persistence::read_frames(log_path).await?);
+    let mut series = Vec::new();
+    while let Some(frame) = frames.next().await {
+        match frame {
+            Ok(Frame { payload: Payload::Current(current), .. }) if current.cell_number == cell => {
+                series.push(current)
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!(error = %err, "skipping unreadable log line"),
+        }
+    }
+    series.sort_by_key(|current| current.sent_at);
+    Ok(series)
+}
+
 async fn is_stable(path: &PathBuf, stable_secs: u64) -> Result<bool> {
 
     let meta = fs::metadata(path).await?;
@@ -156,7 +492,7 @@ async fn is_stable(path: &PathBuf, stable_secs: u64) -> Result<bool> {
     }
 }
 
-async fn write_failures(path: &PathBuf, processed_dir: &PathBuf, failures: &[String]) -> Result<()> {
+async fn write_failures(path: &Path, processed_dir: &Path, failures: &[String]) -> Result<()> {
     let name = path
         .file_name()
         .and_then(|n| n.to_str())
@@ -166,11 +502,51 @@ async fn write_failures(path: &PathBuf, processed_dir: &PathBuf, failures: &[Str
     Ok(())
 }
 
-async fn move_to_processed(path: &PathBuf, processed_dir: &PathBuf) -> Result<()> {
+/// Whether a file named `name` in the intake folder is raw data to replay, rather than a
+/// writer marker (`*.writing`), an in-flight move temp (`*.partial`), a claim, a QC report
+/// or the processing watermark.
+pub fn is_replay_candidate(name: &str) -> bool {
+    !(name.ends_with(".writing")
+        || name.ends_with(".partial")
+        || name.ends_with(PROCESSING_SUFFIX)
+        || name.ends_with(qc::QC_SUFFIX)
+        || name == WATERMARK_FILE)
+}
+
+/// Where a processed file named `name` lands in `processed_dir` under the given scheme.
+pub fn processed_destination(processed_dir: &Path, name: &str, naming: &ProcessedNaming) -> PathBuf {
+    match naming {
+        ProcessedNaming::Original => processed_dir.join(name),
+        ProcessedNaming::Timestamped => {
+            let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.6fZ");
+            next_free_name(processed_dir, &format!("{stamp}_{name}"))
+        }
+        ProcessedNaming::Sequential => next_free_name(processed_dir, name),
+    }
+}
+
+/// `name` if unused in `dir`, otherwise the first free `{stem}_{n}{.ext}`.
+fn next_free_name(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+        _ => (name, String::new()),
+    };
+    (1u32..)
+        .map(|n| dir.join(format!("{stem}_{n}{ext}")))
+        .find(|path| !path.exists())
+        .expect("unbounded suffix search")
+}
+
+/// Moves `path` into `processed_dir` and returns where it ended up.
+async fn move_to_processed(path: &Path, processed_dir: &Path, naming: &ProcessedNaming) -> Result<PathBuf> {
     let name = path
         .file_name()
         .ok_or_else(|| anyhow::anyhow!("file has no file name"))?;
-    let dest = processed_dir.join(name);
+    let dest = processed_destination(processed_dir, &name.to_string_lossy(), naming);
     // Attempt atomic rename; fallback to copy + remove
     match fs::rename(path, &dest).await {
         Ok(_) => {
@@ -180,11 +556,10 @@ async fn move_to_processed(path: &PathBuf, processed_dir: &PathBuf) -> Result<()
                 let marker_path = path.parent().unwrap_or_else(|| std::path::Path::new(".")).join(marker_name);
                 let _ = fs::remove_file(marker_path).await;
             }
-            Ok(())
+            Ok(dest)
         }
         Err(_) => {
-            fs::copy(path, &dest).await?;
-            fs::remove_file(path).await?;
+            copy_then_rename(path, &dest).await?;
             // Attempt to remove writer marker in original folder
             if let Some(fname) = name.to_str() {
                 let marker_name = format!("{}.writing", fname);
@@ -195,27 +570,49 @@ async fn move_to_processed(path: &PathBuf, processed_dir: &PathBuf) -> Result<()
                 let proc_marker = processed_dir.join(format!("{}.writing", fname));
                 let _ = fs::remove_file(proc_marker).await;
             }
-            Ok(())
+            Ok(dest)
         }
     }
 }
 
-async fn move_failed(path: &PathBuf, processed_dir: &PathBuf) -> Result<()> {
+async fn move_failed(path: &Path, processed_dir: &Path, naming: &ProcessedNaming) -> Result<()> {
     let name = path
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| anyhow::anyhow!("file has no file name"))?;
-    let dest = processed_dir.join(format!("{}.failed", name));
+    let dest = processed_destination(processed_dir, &format!("{}.failed", name), naming);
     match fs::rename(path, &dest).await {
         Ok(_) => Ok(()),
-        Err(_) => {
-            fs::copy(path, &dest).await?;
-            fs::remove_file(path).await?;
-            Ok(())
-        }
+        Err(_) => copy_then_rename(path, &dest).await,
     }
 }
 
+/// Cross-device fallback for `rename`: copies into a `.partial` temp next to `dest` and renames
+/// it into place, so an interrupted copy never leaves a truncated file under the final name.
+/// The temp is removed on failure and the source is only deleted once `dest` is complete.
+pub(crate) async fn copy_then_rename(path: &Path, dest: &Path) -> Result<()> {
+    let name = dest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow::anyhow!("destination has no file name"))?;
+    let tmp = dest.with_file_name(format!(".{}.partial", name));
+    let copied = async {
+        fs::copy(path, &tmp)
+            .await
+            .with_context(|| format!("copy {} to {}", path.display(), tmp.display()))?;
+        fs::rename(&tmp, dest)
+            .await
+            .with_context(|| format!("rename {} to {}", tmp.display(), dest.display()))
+    }
+    .await;
+    if let Err(err) = copied {
+        let _ = fs::remove_file(&tmp).await;
+        return Err(err);
+    }
+    fs::remove_file(path).await?;
+    Ok(())
+}
+
 async fn cleanup_stale_markers(dir: &PathBuf, stable_secs: u64) -> Result<()> {
     let mut read = tokio::fs::read_dir(dir).await?;
     let threshold = std::time::Duration::from_secs(stable_secs.saturating_mul(1));
@@ -255,15 +652,124 @@ async fn cleanup_stale_markers(dir: &PathBuf, stable_secs: u64) -> Result<()> {
     Ok(())
 }
 
+/// Slant angle of Signature beams from the instrument's Z axis.
+const SIGNATURE_BEAM_ANGLE_DEG: f64 = 25.0;
+
+/// Rotates `$PNORC` velocities reported in XYZ or BEAM coordinates into ENU for
+/// `convert_to_enu`. The coordinate system comes from the latest `$PNORI` and the attitude from
+/// the latest `$PNORS` with heading, pitch and roll all present; current frames seen before
+/// both are known pass through unconverted.
+#[derive(Debug, Default)]
+pub struct EnuConversion {
+    system: Option<CoordinateSystem>,
+    /// Heading, pitch and roll in degrees.
+    attitude: Option<[f64; 3]>,
+}
+
+impl EnuConversion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Learns from config and sensor frames and converts current frames in place, recording
+    /// the system they were reported in. Returns true when `frame` was converted.
+    pub fn apply(&mut self, frame: &mut Frame) -> bool {
+        match &mut frame.payload {
+            Payload::Config(config) => {
+                self.system = Some(config.coordinate_system.clone());
+                false
+            }
+            Payload::Sensor(sensor) => {
+                if let (Some(heading), Some(pitch), Some(roll)) = (sensor.heading_deg, sensor.pitch_deg, sensor.roll_deg) {
+                    self.attitude = Some([heading.into(), pitch.into(), roll.into()]);
+                }
+                false
+            }
+            Payload::Current(current) => {
+                let (Some(system), Some(attitude)) = (&self.system, self.attitude) else {
+                    return false;
+                };
+                let (Some(v1), Some(v2), Some(v3), Some(v4)) = (
+                    current.velocity_1_m_s,
+                    current.velocity_2_m_s,
+                    current.velocity_3_m_s,
+                    current.velocity_4_m_s,
+                ) else {
+                    return false;
+                };
+                let measured = [v1, v2, v3, v4].map(f64::from);
+                let xyz = match system {
+                    CoordinateSystem::Xyz => measured,
+                    CoordinateSystem::Beam => beam_to_xyz(measured),
+                    CoordinateSystem::Enu | CoordinateSystem::Unknown(_) => return false,
+                };
+                let [east, north, up_1, up_2] = xyz_to_enu(xyz, attitude).map(|v| v as f32);
+                current.velocity_1_m_s = Some(east);
+                current.velocity_2_m_s = Some(north);
+                current.velocity_3_m_s = Some(up_1);
+                current.velocity_4_m_s = Some(up_2);
+                frame.original_coordinate_system = Some(system.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Resolves four slanted beam velocities into X, Y, Z1 and Z2, where Z1 comes from beams 1/3
+/// and Z2 from beams 2/4.
+fn beam_to_xyz([b1, b2, b3, b4]: [f64; 4]) -> [f64; 4] {
+    let (sin, cos) = SIGNATURE_BEAM_ANGLE_DEG.to_radians().sin_cos();
+    [
+        (b1 - b3) / (2.0 * sin),
+        (b4 - b2) / (2.0 * sin),
+        (b1 + b3) / (2.0 * cos),
+        (b2 + b4) / (2.0 * cos),
+    ]
+}
+
+/// Rotates X, Y, Z1, Z2 into East, North, Up1, Up2 following Nortek's heading, pitch and roll
+/// convention (heading 0 points the X axis north).
+fn xyz_to_enu([x, y, z1, z2]: [f64; 4], [heading, pitch, roll]: [f64; 3]) -> [f64; 4] {
+    let (sh, ch) = (heading - 90.0).to_radians().sin_cos();
+    let (sp, cp) = pitch.to_radians().sin_cos();
+    let (sr, cr) = roll.to_radians().sin_cos();
+    let heading_m = [[ch, sh, 0.0], [-sh, ch, 0.0], [0.0, 0.0, 1.0]];
+    let tilt_m = [[cp, -sp * sr, -cr * sp], [0.0, cr, -sr], [sp, sr * cp, cp * cr]];
+    let r = |i: usize, j: usize| (0..3).map(|k| heading_m[i][k] * tilt_m[k][j]).sum::<f64>();
+    let z = (z1 + z2) / 2.0;
+    [
+        r(0, 0) * x + r(0, 1) * y + r(0, 2) * z,
+        r(1, 0) * x + r(1, 1) * y + r(1, 2) * z,
+        r(2, 0) * x + r(2, 1) * y + r(2, 2) * z1,
+        r(2, 0) * x + r(2, 1) * y + r(2, 2) * z2,
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{AppConfig, ServiceMode, SplitMode};
+    use crate::config::{AppConfig, ServiceMode};
     use std::sync::Arc;
     use tempfile::tempdir;
     use tokio::fs;
 
+    #[test]
+    fn only_raw_data_is_a_replay_candidate() {
+        assert!(is_replay_candidate("2026-01-01.raw"));
+        for name in [
+            "2026-01-01.raw.writing",
+            ".2026-01-01.raw.partial",
+            &format!("2026-01-01.raw{PROCESSING_SUFFIX}"),
+            &format!("2026-01-01.raw{}", qc::QC_SUFFIX),
+            WATERMARK_FILE,
+        ] {
+            assert!(!is_replay_candidate(name), "{name}");
+        }
+    }
+
     #[tokio::test]
+    #[allow(clippy::nonminimal_bool)]
     async fn processes_and_moves_file() {
         let tmp = tempdir().expect("temp dir");
         let to_process = tmp.path().join("to_process");
@@ -282,21 +788,15 @@ mod tests {
 
         let config = AppConfig {
             service_name: "test".to_string(),
-            log_level: "info".to_string(),
             data_directory: data_out.to_string_lossy().to_string(),
             serial_port: Some("/dev/null".to_string()),
-            baud_rate: 115200,
-            idle_threshold_seconds: 30,
-            alert_webhook: None,
             mode: ServiceMode::Processing,
             backup_folder: "./backup".to_string(),
             data_process_folder: to_process.to_string_lossy().to_string(),
             processed_folder: processed.to_string_lossy().to_string(),
-            split_mode: SplitMode::Daily,
-            max_backup_files: None,
-            max_backup_age_days: None,
             file_stability_seconds: stable,
-            sample_file: None,
+            processing_concurrency: 1,
+            ..Default::default()
         };
 
         let (shutdown_tx, shutdown_rx) = watch::channel(());
@@ -324,4 +824,563 @@ mod tests {
         shutdown_tx.send(()).ok();
         handle.await.expect("join");
     }
+
+    #[tokio::test]
+    async fn failed_replay_is_retried_on_the_next_scan() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let sample = to_process.join("2026-01-01.raw");
+        fs::write(&sample, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n")
+            .await
+            .expect("write sample");
+        // A plain file where the data directory should be makes the first replay fail
+        let data_out = tmp.path().join("out");
+        fs::write(&data_out, "").await.expect("block data directory");
+
+        let mut config: AppConfig = toml::from_str("service_name = \"retry\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = data_out.to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.max_processing_retries = 1;
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let handle = tokio::spawn(run_processing_loop(Arc::new(config), shutdown_rx));
+
+        // The first attempt fails straight away; the retry waits for the next scan
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        assert!(fs::metadata(&sample).await.is_ok(), "failed file stays for a retry");
+        assert!(fs::metadata(processed.join("2026-01-01.raw.failed")).await.is_err());
+        fs::remove_file(&data_out).await.expect("unblock data directory");
+
+        let moved = processed.join("2026-01-01.raw");
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while fs::metadata(&moved).await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "retry never processed the file");
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(fs::metadata(processed.join("2026-01-01.raw.failed")).await.is_err());
+
+        shutdown_tx.send(()).ok();
+        handle.await.expect("join").expect("processing loop");
+    }
+
+    #[tokio::test]
+    async fn retry_resumes_after_the_frames_already_persisted() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        let out = tmp.path().join("out");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let sensor = |second: u32| {
+            let body = format!("PNORS,010526,2208{second:02},00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0");
+            let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            format!("${body}*{checksum:02X}\n")
+        };
+        // The first attempt persists one frame, then fails reading the invalid UTF-8
+        let capture = to_process.join("2026-01-05.raw");
+        let mut broken = format!("{}{}", sensor(0), sensor(1)).into_bytes();
+        broken.extend_from_slice(b"\xff\xfe\n");
+        broken.extend_from_slice(sensor(2).as_bytes());
+        fs::write(&capture, broken).await.expect("write broken capture");
+
+        let mut config: AppConfig = toml::from_str("service_name = \"resume\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = out.to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.max_processing_retries = 3;
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let handle = tokio::spawn(run_processing_loop(Arc::new(config), shutdown_rx));
+        let log = out.join("adcp-2026-01-05.log");
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while fs::read_to_string(&log).await.map_or(true, |content| content.lines().count() < 1) {
+            assert!(tokio::time::Instant::now() < deadline, "first attempt persisted nothing");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        let repaired = to_process.join("2026-01-05.raw.partial");
+        fs::write(&repaired, format!("{}{}{}", sensor(0), sensor(1), sensor(2))).await.expect("write repair");
+        fs::rename(&repaired, &capture).await.expect("repair capture");
+        while fs::metadata(processed.join("2026-01-05.raw")).await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "retry never processed the file");
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        let content = fs::read_to_string(&log).await.expect("read log");
+        for second in ["220800", "220801", "220802"] {
+            let copies = content.lines().filter(|line| line.contains(&format!("010526,{second}"))).count();
+            assert_eq!(copies, 1, "frame at {second} persisted {copies} times:\n{content}");
+        }
+
+        shutdown_tx.send(()).ok();
+        handle.await.expect("join").expect("processing loop");
+    }
+
+    #[tokio::test]
+    async fn later_success_does_not_skip_a_file_waiting_for_retry() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let sample = "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n";
+        // Invalid UTF-8 fails the replay of the first file without touching the second
+        let first = to_process.join("2026-01-01.raw");
+        fs::write(&first, b"\xff\xfe\n").await.expect("write unreadable sample");
+        let first_modified = std::fs::metadata(&first).expect("stat").modified().expect("mtime");
+        fs::write(to_process.join("2026-01-02.raw"), sample).await.expect("write sample");
+
+        let mut config: AppConfig = toml::from_str("service_name = \"retry-order\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = tmp.path().join("out").to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.max_processing_retries = 3;
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let handle = tokio::spawn(run_processing_loop(Arc::new(config), shutdown_rx));
+        let second = processed.join("2026-01-02.raw");
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while fs::metadata(&second).await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "second file never processed");
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        let mark = load_watermark(&processed).await.expect("watermark stored");
+        assert!(!mark.processed.contains_key("2026-01-01.raw"), "pending retry marked as processed");
+
+        // The cause clears without a newer mtime, e.g. a remounted disk
+        fs::write(&first, sample).await.expect("repair sample");
+        std::fs::File::options()
+            .write(true)
+            .open(&first)
+            .and_then(|f| f.set_modified(first_modified))
+            .expect("restore mtime");
+        let moved = processed.join("2026-01-01.raw");
+        while fs::metadata(&moved).await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "retry never processed the first file");
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        shutdown_tx.send(()).ok();
+        handle.await.expect("join").expect("processing loop");
+    }
+
+    #[tokio::test]
+    async fn run_once_processes_stable_files_and_returns() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let names = ["2026-01-01.raw", "2026-01-02.raw", "2026-01-03.raw"];
+        for name in names {
+            fs::write(to_process.join(name), "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n")
+                .await
+                .expect("write sample");
+        }
+        let mut config: AppConfig = toml::from_str("service_name = \"batch\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = tmp.path().join("out").to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.run_once = true;
+
+        // The sender stays alive: the loop must return on its own, not because of a shutdown
+        let (_shutdown_tx, shutdown_rx) = watch::channel(());
+        tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            run_processing_loop(Arc::new(config), shutdown_rx),
+        )
+        .await
+        .expect("single pass returns without a shutdown signal")
+        .expect("processing pass");
+
+        for name in names {
+            assert!(fs::metadata(to_process.join(name)).await.is_err(), "{name} left behind");
+            assert!(fs::metadata(processed.join(name)).await.is_ok(), "{name} not processed");
+        }
+    }
+
+    #[tokio::test]
+    async fn restart_skips_files_covered_by_the_watermark() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let sample = "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n";
+        let mut config: AppConfig = toml::from_str("service_name = \"watermark\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = tmp.path().join("out").to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.run_once = true;
+        let run = |config: AppConfig| async move {
+            let (_shutdown_tx, shutdown_rx) = watch::channel(());
+            run_processing_loop(Arc::new(config), shutdown_rx).await.expect("processing pass");
+        };
+
+        let first = to_process.join("2026-01-01.raw");
+        fs::write(&first, sample).await.expect("write sample");
+        let first_modified = std::fs::metadata(&first).expect("stat").modified().expect("mtime");
+        run(config.clone()).await;
+        assert!(fs::metadata(processed.join(WATERMARK_FILE)).await.is_ok(), "watermark persisted");
+
+        // The same file reappears unchanged (e.g. the move was undone) next to a new one
+        fs::copy(processed.join("2026-01-01.raw"), &first).await.expect("restore file");
+        std::fs::File::options()
+            .write(true)
+            .open(&first)
+            .and_then(|f| f.set_modified(first_modified))
+            .expect("restore mtime");
+        fs::write(to_process.join("2026-01-02.raw"), sample).await.expect("write sample");
+        run(config.clone()).await;
+        assert!(fs::metadata(&first).await.is_ok(), "already processed file is skipped");
+        assert!(fs::metadata(processed.join("2026-01-02.raw")).await.is_ok(), "newer file processed");
+
+        config.force_reprocess = true;
+        run(config).await;
+        assert!(fs::metadata(&first).await.is_err(), "forced run reprocesses it");
+    }
+
+    #[tokio::test]
+    async fn backfilled_file_with_an_old_mtime_is_still_processed() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let sample = "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n";
+        let mut config: AppConfig = toml::from_str("service_name = \"backfill\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = tmp.path().join("out").to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.run_once = true;
+        let run = |config: AppConfig| async move {
+            let (_shutdown_tx, shutdown_rx) = watch::channel(());
+            run_processing_loop(Arc::new(config), shutdown_rx).await.expect("processing pass");
+        };
+
+        fs::write(to_process.join("2026-01-05.raw"), sample).await.expect("write sample");
+        run(config.clone()).await;
+
+        // A late copy that sorts first and keeps its original mtime, as `cp -p` or rsync give it
+        let backfill = to_process.join("2026-01-01.raw");
+        fs::write(&backfill, sample).await.expect("write backfill");
+        std::fs::File::options()
+            .write(true)
+            .open(&backfill)
+            .and_then(|f| f.set_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000)))
+            .expect("backdate mtime");
+        run(config).await;
+        assert!(fs::metadata(&backfill).await.is_err(), "backfill left behind");
+        assert!(fs::metadata(processed.join("2026-01-01.raw")).await.is_ok(), "backfill not processed");
+    }
+
+    #[tokio::test]
+    async fn stuck_processing_claim_is_reverted_and_reprocessed() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let mut config: AppConfig = toml::from_str("service_name = \"stuck\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = tmp.path().join("out").to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.processing_stuck_seconds = 60;
+        config.run_once = true;
+
+        // A worker claimed this file two minutes ago and never came back
+        let claimed = to_process.join(format!("2026-01-01.raw{PROCESSING_SUFFIX}"));
+        fs::write(&claimed, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n")
+            .await
+            .expect("write claim");
+        std::fs::File::options()
+            .write(true)
+            .open(&claimed)
+            .and_then(|f| f.set_modified(SystemTime::now() - Duration::from_secs(120)))
+            .expect("age claim");
+        // A fresh claim belongs to a live worker and is left alone
+        let live = to_process.join(format!("2026-01-02.raw{PROCESSING_SUFFIX}"));
+        fs::write(&live, "").await.expect("write live claim");
+
+        let (_shutdown_tx, shutdown_rx) = watch::channel(());
+        run_processing_loop(Arc::new(config), shutdown_rx).await.expect("processing pass");
+
+        assert!(fs::metadata(&claimed).await.is_err(), "stuck claim reverted");
+        assert!(fs::metadata(processed.join("2026-01-01.raw")).await.is_ok(), "reverted file reprocessed");
+        assert!(fs::metadata(&live).await.is_ok(), "live claim untouched");
+    }
+
+    #[tokio::test]
+    async fn writes_qc_sidecar_for_processed_file() {
+        let tmp = tempdir().expect("temp dir");
+        let to_process = tmp.path().join("to_process");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&to_process).await.expect("mk to_process");
+        let mut config: AppConfig = toml::from_str("service_name = \"qc\"").expect("parse config");
+        config.data_process_folder = to_process.to_string_lossy().to_string();
+        config.processed_folder = processed.to_string_lossy().to_string();
+        config.data_directory = tmp.path().join("out").to_string_lossy().to_string();
+        config.file_stability_seconds = 0;
+        config.run_once = true;
+        config.write_qc_summary = true;
+
+        // Two announced cells; cell 2 has a masked velocity and amplitude, cell 3 is out of range
+        let capture = "$PNORI,4,Signature1000_100297,4,2,0.20,1.00,0*70
+$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23
+$PNORC,010526,220800,2,-9.00,-0.80,-1.99,-1.33,0.98,305.2,C,60,-9,67,78,11,17,10,18*1F
+$PNORC,010526,220800,3,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,70,88,67,78,12,17,10,18*2F
+";
+        fs::write(to_process.join("2026-01-05.raw"), capture).await.expect("write capture");
+        let (_shutdown_tx, shutdown_rx) = watch::channel(());
+        run_processing_loop(Arc::new(config), shutdown_rx).await.expect("processing pass");
+
+        let sidecar = fs::read_to_string(processed.join("2026-01-05.raw.qc.json")).await.expect("qc sidecar");
+        let qc: serde_json::Value = serde_json::from_str(&sidecar).expect("qc json");
+        assert_eq!(qc["frames"], 4);
+        assert_eq!(qc["current_frames"], 3);
+        assert_eq!(qc["beams"][0]["amplitude_mean"], 70.0);
+        assert_eq!(qc["beams"][0]["amplitude_min"], 60);
+        assert_eq!(qc["beams"][0]["correlation_mean_pct"], 12.0);
+        assert_eq!(qc["beams"][0]["correlation_min_pct"], 11);
+        assert_eq!(qc["beams"][1]["amplitude_mean"], 88.0);
+        assert_eq!(qc["masked_fraction"], 2.0 / 36.0);
+        assert_eq!(qc["inconsistent_frames"], 1);
+    }
+
+    #[test]
+    fn replay_permits_match_configured_concurrency() {
+        let mut config: AppConfig = toml::from_str("service_name = \"test\"").expect("parse config");
+        config.processing_concurrency = 3;
+        assert_eq!(replay_permits(&config).available_permits(), 3);
+
+        config.processing_concurrency = 0;
+        assert_eq!(replay_permits(&config).available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn escalates_after_consecutive_read_dir_failures() {
+        let tmp = tempdir().expect("temp dir");
+        let config = AppConfig {
+            service_name: "test".to_string(),
+            data_directory: tmp.path().join("out").to_string_lossy().to_string(),
+            mode: ServiceMode::Processing,
+            backup_folder: "./backup".to_string(),
+            data_process_folder: tmp.path().join("missing").to_string_lossy().to_string(),
+            processed_folder: tmp.path().join("processed").to_string_lossy().to_string(),
+            file_stability_seconds: 1,
+            read_dir_failure_threshold: 2,
+            exit_on_read_dir_failure: true,
+            processing_concurrency: 1,
+            ..Default::default()
+        };
+
+        let (_shutdown_tx, shutdown_rx) = watch::channel(());
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            run_processing_loop(Arc::new(config), shutdown_rx),
+        )
+        .await
+        .expect("loop should exit once the failure threshold is reached");
+        let err = result.expect_err("unreadable folder escalates to an error");
+        assert!(err.to_string().contains("after 2 consecutive attempts"));
+    }
+
+    #[tokio::test]
+    async fn copy_fallback_leaves_no_partial_file() {
+        let tmp = tempdir().expect("temp dir");
+        let src = tmp.path().join("2026-01-01.raw");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&processed).await.expect("create processed");
+        fs::write(&src, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n").await.expect("write src");
+
+        // A non-empty directory squatting on the destination makes the final rename fail,
+        // standing in for a copy interrupted before it could be put in place.
+        let blocked = processed.join("2026-01-01.raw");
+        fs::create_dir_all(blocked.join("occupied")).await.expect("block dest");
+        assert!(copy_then_rename(&src, &blocked).await.is_err());
+        assert!(fs::metadata(&src).await.is_ok(), "source kept when the move fails");
+        let mut entries = fs::read_dir(&processed).await.expect("read processed");
+        while let Some(entry) = entries.next_entry().await.expect("entry") {
+            let name = entry.file_name().to_string_lossy().to_string();
+            assert!(!name.ends_with(".partial"), "leftover temp {name}");
+        }
+
+        fs::remove_dir_all(&blocked).await.expect("unblock dest");
+        copy_then_rename(&src, &blocked).await.expect("fallback move");
+        assert!(fs::metadata(&src).await.is_err(), "source removed after move");
+        let moved = fs::read_to_string(&blocked).await.expect("read moved");
+        assert!(moved.starts_with("$PNORI"));
+    }
+
+    #[tokio::test]
+    async fn timestamped_naming_keeps_same_named_files() {
+        let tmp = tempdir().expect("temp dir");
+        let processed = tmp.path().join("processed");
+        fs::create_dir_all(&processed).await.expect("create processed");
+        for (run, contents) in [("run1", "first\n"), ("run2", "second\n")] {
+            let src_dir = tmp.path().join(run);
+            fs::create_dir_all(&src_dir).await.expect("create source");
+            let src = src_dir.join("2026-01-01.raw");
+            fs::write(&src, contents).await.expect("write source");
+            move_to_processed(&src, &processed, &ProcessedNaming::Timestamped)
+                .await
+                .expect("move to processed");
+        }
+
+        let mut contents = Vec::new();
+        let mut entries = fs::read_dir(&processed).await.expect("read processed");
+        while let Some(entry) = entries.next_entry().await.expect("entry") {
+            assert!(entry.file_name().to_string_lossy().ends_with("_2026-01-01.raw"));
+            contents.push(fs::read_to_string(entry.path()).await.expect("read moved"));
+        }
+        contents.sort();
+        assert_eq!(contents, ["first\n", "second\n"]);
+        assert_eq!(
+            next_free_name(&processed, "2026-01-02.raw"),
+            processed.join("2026-01-02.raw")
+        );
+    }
+
+    #[tokio::test]
+    async fn extracts_one_cell_in_time_order() {
+        let tmp = tempdir().expect("temp dir");
+        let log = tmp.path().join("adcp-2026-01-05.log");
+        let lines = [
+            "$PNORC,010526,220802,2,0.10,0.20,0.30,0.40,0.50,90.0,C,80,88,67,78,13,17,10,18",
+            "$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18",
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0",
+            "$PNORC,010526,220800,2,0.11,0.21,0.31,0.41,0.51,91.0,C,80,88,67,78,13,17,10,18",
+        ];
+        let mut contents = String::new();
+        for body in lines {
+            let checksum = body[1..].bytes().fold(0u8, |acc, b| acc ^ b);
+            let frame = Frame::from_line(&format!("{body}*{checksum:02X}")).expect("parse frame");
+            contents.push_str(&frame.to_persistence_line());
+            contents.push('\n');
+        }
+        contents.push_str("{\"truncated\":");
+        fs::write(&log, contents).await.expect("write log");
+
+        let series = extract_cell_series(&log, 2).await.expect("extract series");
+        assert_eq!(series.len(), 2);
+        assert!(series.iter().all(|c| c.cell_number == 2));
+        assert_eq!(series[0].direction_deg, Some(91.0));
+        assert_eq!(series[1].direction_deg, Some(90.0));
+        assert!(series[0].sent_at < series[1].sent_at);
+    }
+
+    #[tokio::test]
+    async fn lists_only_files_processed_since() {
+        let tmp = tempdir().expect("temp dir");
+        let base = UNIX_EPOCH + Duration::from_secs(1_767_225_600); // 2026-01-01
+        for (name, offset) in [("old.raw", 0), ("edge.raw", 100), ("new.raw", 200)] {
+            let path = tmp.path().join(name);
+            std::fs::write(&path, name).expect("write file");
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|f| f.set_modified(base + Duration::from_secs(offset)))
+                .expect("set mtime");
+        }
+
+        let since = parse_list_query("since=1767225700").expect("parse since");
+        let names: Vec<String> = list_processed(tmp.path(), since)
+            .await
+            .expect("list")
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(names, ["new.raw"]);
+        assert_eq!(list_processed(tmp.path(), None).await.expect("list all").len(), 3);
+
+        assert!(parse_list_query("since=yesterday").is_err());
+        assert!(parse_list_query("until=1").is_err());
+        let future = parse_list_query("since=99999999999").expect("future since").unwrap();
+        assert!(future <= SystemTime::now(), "future since is clamped to now");
+    }
+
+    fn frame(body: &str) -> Frame {
+        let checksum = body[1..].bytes().fold(0u8, |acc, b| acc ^ b);
+        Frame::from_line(&format!("{body}*{checksum:02X}")).expect("parse frame")
+    }
+
+    /// Converts one current frame with velocities `v` under `system` (`$PNORI` code) and `heading`.
+    fn convert(system: u8, heading: f32, v: [f32; 4]) -> Frame {
+        let mut enu = EnuConversion::new();
+        assert!(!enu.apply(&mut frame(&format!("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,{system}"))));
+        assert!(!enu.apply(&mut frame(&format!(
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,{heading:.1},0.0,0.0,0.000,24.02,0,0"
+        ))));
+        let mut current = frame(&format!(
+            "$PNORC,010526,220800,1,{:.3},{:.3},{:.3},{:.3},1.00,0.0,C,80,88,67,78,13,17,10,18",
+            v[0], v[1], v[2], v[3]
+        ));
+        assert!(enu.apply(&mut current));
+        current
+    }
+
+    fn velocities(frame: &Frame) -> [f32; 4] {
+        let Payload::Current(current) = &frame.payload else {
+            panic!("not a current frame");
+        };
+        [
+            current.velocity_1_m_s,
+            current.velocity_2_m_s,
+            current.velocity_3_m_s,
+            current.velocity_4_m_s,
+        ]
+        .map(|v| v.expect("velocity present"))
+    }
+
+    fn assert_close(actual: [f32; 4], expected: [f32; 4]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn heading_rotates_xyz_between_north_and_east() {
+        // Heading 0 points X north; turning to 90 moves the same X flow onto east.
+        let north = convert(1, 0.0, [0.5, 0.0, 0.1, 0.1]);
+        assert_close(velocities(&north), [0.0, 0.5, 0.1, 0.1]);
+        assert_eq!(north.original_coordinate_system, Some(CoordinateSystem::Xyz));
+
+        let east = convert(1, 90.0, [0.5, 0.0, 0.1, 0.1]);
+        assert_close(velocities(&east), [0.5, 0.0, 0.1, 0.1]);
+
+        let swapped = convert(1, 90.0, [0.0, 0.5, 0.0, 0.0]);
+        assert_close(velocities(&swapped), [0.0, 0.5, 0.0, 0.0]);
+        let swapped = convert(1, 0.0, [0.0, 0.5, 0.0, 0.0]);
+        assert_close(velocities(&swapped), [-0.5, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn beam_velocities_resolve_through_xyz() {
+        let (sin, cos) = SIGNATURE_BEAM_ANGLE_DEG.to_radians().sin_cos();
+        let (x, z) = (0.4, 0.1);
+        let beams = [x * sin + z * cos, z * cos, -x * sin + z * cos, z * cos].map(|v| v as f32);
+        let converted = convert(2, 90.0, beams);
+        assert_close(velocities(&converted), [x as f32, 0.0, z as f32, z as f32]);
+        assert_eq!(converted.original_coordinate_system, Some(CoordinateSystem::Beam));
+    }
+
+    #[test]
+    fn enu_frames_and_frames_without_attitude_are_untouched() {
+        let mut enu = EnuConversion::new();
+        enu.apply(&mut frame("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,1"));
+        let mut current = frame("$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18");
+        let before = current.clone();
+        assert!(!enu.apply(&mut current), "no sensor frame yet");
+        assert_eq!(current, before);
+
+        enu.apply(&mut frame("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0"));
+        enu.apply(&mut frame("$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0"));
+        assert!(!enu.apply(&mut current), "already ENU");
+        assert_eq!(current, before);
+        assert!(!current.to_persistence_line().contains("original_coordinate_system"));
+    }
 }
@@ -0,0 +1,164 @@
+use crate::{
+    parser::{Frame, Payload},
+    simulator::CaptureReader,
+    AppConfig,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::BufReader};
+
+/// Suffix of QC sidecar files.
+pub const QC_SUFFIX: &str = ".qc.json";
+
+/// Correlation and amplitude statistics of one beam over a file's `$PNORC` cells. Means
+/// and minimums are `None` when the beam never reported a valid value.
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct BeamQc {
+    pub correlation_mean_pct: Option<f64>,
+    pub correlation_min_pct: Option<u8>,
+    pub amplitude_mean: Option<f64>,
+    pub amplitude_min: Option<u8>,
+    #[serde(skip)]
+    correlation: Accumulator,
+    #[serde(skip)]
+    amplitude: Accumulator,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Accumulator {
+    sum: u64,
+    count: u64,
+    min: Option<u8>,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: u8) {
+        self.sum += u64::from(value);
+        self.count += 1;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+    }
+
+    fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+    }
+}
+
+/// Data-quality verdict for one processed file, written next to it as `{name}.qc.json`.
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct QcSummary {
+    pub frames: u64,
+    pub current_frames: u64,
+    pub beams: [BeamQc; 4],
+    /// Share of `$PNORC` velocity, amplitude and correlation values the instrument marked
+    /// invalid (masked to `null` by the parser's sentinel handling).
+    pub masked_fraction: f64,
+    /// Frames violating a `validation_rules` bound, or current cells numbered beyond the
+    /// cell count announced by the preceding `$PNORI`.
+    pub inconsistent_frames: u64,
+    #[serde(skip)]
+    masked: u64,
+    #[serde(skip)]
+    measured: u64,
+}
+
+impl QcSummary {
+    fn observe(&mut self, frame: &Frame, config: &AppConfig, announced_cells: Option<u16>) {
+        self.frames += 1;
+        let mut consistent = !config.validation_rules.iter().any(|rule| rule.is_violated_by(frame));
+        if let Payload::Current(current) = &frame.payload {
+            self.current_frames += 1;
+            if announced_cells.is_some_and(|cells| current.cell_number > cells) {
+                consistent = false;
+            }
+            let velocities = [
+                current.velocity_1_m_s,
+                current.velocity_2_m_s,
+                current.velocity_3_m_s,
+                current.velocity_4_m_s,
+            ];
+            let amplitudes = [
+                current.amplitude_beam_1,
+                current.amplitude_beam_2,
+                current.amplitude_beam_3,
+                current.amplitude_beam_4,
+            ];
+            let correlations = [
+                current.correlation_beam_1_pct,
+                current.correlation_beam_2_pct,
+                current.correlation_beam_3_pct,
+                current.correlation_beam_4_pct,
+            ];
+            self.measured += 12;
+            self.masked += velocities.iter().filter(|v| v.is_none()).count() as u64;
+            for (beam, (amplitude, correlation)) in self.beams.iter_mut().zip(amplitudes.into_iter().zip(correlations)) {
+                match amplitude {
+                    Some(value) => beam.amplitude.add(value),
+                    None => self.masked += 1,
+                }
+                match correlation {
+                    Some(value) => beam.correlation.add(value),
+                    None => self.masked += 1,
+                }
+            }
+        }
+        if !consistent {
+            self.inconsistent_frames += 1;
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        for beam in &mut self.beams {
+            beam.correlation_mean_pct = beam.correlation.mean();
+            beam.correlation_min_pct = beam.correlation.min;
+            beam.amplitude_mean = beam.amplitude.mean();
+            beam.amplitude_min = beam.amplitude.min;
+        }
+        if self.measured > 0 {
+            self.masked_fraction = self.masked as f64 / self.measured as f64;
+        }
+        self
+    }
+}
+
+/// Streams the capture at `path` and summarizes its data quality. Lines that fail to parse
+/// are skipped; they are already reported through the `.failed` file.
+pub async fn summarize(path: &Path, config: &AppConfig) -> Result<QcSummary> {
+    let file = fs::File::open(path)
+        .await
+        .with_context(|| format!("open capture {}", path.display()))?;
+    let mut capture = CaptureReader::new(BufReader::new(file));
+    let mut summary = QcSummary::default();
+    let mut announced_cells = None;
+    while let Some(line) = capture
+        .next_frame()
+        .await
+        .with_context(|| format!("read capture {}", path.display()))?
+    {
+        let Ok(frame) = Frame::from_line_with(&line, &config.dialect) else {
+            continue;
+        };
+        if let Payload::Config(header) = &frame.payload {
+            announced_cells = Some(header.cells);
+        }
+        summary.observe(&frame, config, announced_cells);
+    }
+    Ok(summary.finish())
+}
+
+/// Writes `summary` as `{data_file}.qc.json` and returns the sidecar's path.
+pub async fn write_sidecar(data_file: &Path, summary: &QcSummary) -> Result<PathBuf> {
+    let path = sidecar_path(data_file);
+    let rendered = serde_json::to_vec_pretty(summary).context("serialize QC summary")?;
+    fs::write(&path, rendered)
+        .await
+        .with_context(|| format!("write QC summary {}", path.display()))?;
+    Ok(path)
+}
+
+/// Where the QC sidecar of `data_file` lives: next to it, named `{name}.qc.json`.
+pub fn sidecar_path(data_file: &Path) -> PathBuf {
+    let mut name = data_file.file_name().unwrap_or_default().to_os_string();
+    name.push(QC_SUFFIX);
+    data_file.with_file_name(name)
+}
@@ -0,0 +1,140 @@
+use crate::config::AppConfig;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Config fields a running recording service can pick up without reopening the serial port.
+/// Anything else that differs (serial_port, baud_rate, folders, ...) needs a restart.
+pub const RUNTIME_FIELDS: &[&str] = &[
+    "idle_threshold_seconds",
+    "alert_webhook",
+    "statsd_address",
+    "quiet_hours",
+    "validation_rules",
+    "quarantine_invalid_frames",
+    "expected_instrument_type",
+    "empty_line_threshold",
+    "empty_line_window_seconds",
+    "overrun_burst_lines",
+];
+
+/// The subset of `RUNTIME_FIELDS` the standalone port recorder reads live: it only parses for
+/// health, validation and the sensor cache, with no quarantine or line-burst accounting.
+pub const PORT_RECORDER_RUNTIME_FIELDS: &[&str] = &[
+    "idle_threshold_seconds",
+    "alert_webhook",
+    "statsd_address",
+    "quiet_hours",
+    "validation_rules",
+    "expected_instrument_type",
+];
+
+/// Which changed fields of a pushed config were applied and which were left for a restart.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReconfigOutcome {
+    pub applied: Vec<String>,
+    pub requires_restart: Vec<String>,
+}
+
+/// Applies the fields of `incoming` listed in `runtime_fields` to the live config, e.g. from
+/// a `conf.update` bus message. Any other changed field is reported but never applied.
+pub fn apply_update(
+    live: &watch::Sender<Arc<AppConfig>>,
+    incoming: &AppConfig,
+    runtime_fields: &[&str],
+) -> Result<ReconfigOutcome> {
+    let current = live.borrow().clone();
+    let mut merged = serde_json::to_value(&*current).context("serialize live config")?;
+    let incoming = serde_json::to_value(incoming).context("serialize pushed config")?;
+    let (Value::Object(merged_fields), Value::Object(incoming_fields)) = (&mut merged, incoming)
+    else {
+        bail!("config did not serialize to an object");
+    };
+
+    let mut outcome = ReconfigOutcome::default();
+    for (field, value) in incoming_fields {
+        if merged_fields.get(&field) == Some(&value) {
+            continue;
+        }
+        if runtime_fields.contains(&field.as_str()) {
+            merged_fields.insert(field.clone(), value);
+            outcome.applied.push(field);
+        } else {
+            outcome.requires_restart.push(field);
+        }
+    }
+
+    if !outcome.applied.is_empty() {
+        let updated: AppConfig = serde_json::from_value(merged).context("rebuild live config")?;
+        live.send_replace(Arc::new(updated));
+        tracing::info!(service = %current.service_name, fields = ?outcome.applied, "runtime config applied");
+    }
+    if !outcome.requires_restart.is_empty() {
+        tracing::warn!(
+            service = %current.service_name,
+            fields = ?outcome.requires_restart,
+            "config changes ignored until restart"
+        );
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{self, Metrics};
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn pushed_idle_threshold_reaches_health_monitor() {
+        let config: AppConfig = toml::from_str(
+            "service_name = \"reconfig\"\nserial_port = \"/dev/ttyUSB0\"\nidle_threshold_seconds = 3600",
+        )
+        .expect("parse config");
+        let (live, _) = watch::channel(Arc::new(config.clone()));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let metrics = Arc::new(Metrics::new());
+        metrics.record_frame();
+
+        let monitor = tokio::spawn(metrics::monitor_health(
+            Arc::new(config.service_name.clone()),
+            metrics.clone(),
+            shutdown_rx,
+            live.subscribe(),
+        ));
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(metrics.snapshot().idle_alerts, 0);
+
+        let pushed = AppConfig {
+            idle_threshold_seconds: 0,
+            serial_port: Some("/dev/ttyUSB1".to_string()),
+            ..config
+        };
+        let outcome = apply_update(&live, &pushed, RUNTIME_FIELDS).expect("apply update");
+        assert_eq!(outcome.applied, ["idle_threshold_seconds"]);
+        assert_eq!(outcome.requires_restart, ["serial_port"]);
+        assert_eq!(live.borrow().serial_port.as_deref(), Some("/dev/ttyUSB0"));
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(metrics.snapshot().idle_alerts, 1);
+
+        shutdown_tx.send(()).ok();
+        monitor.await.expect("join monitor").expect("monitor result");
+    }
+
+    #[test]
+    fn port_recorder_leaves_fields_it_does_not_read_for_a_restart() {
+        let config: AppConfig = toml::from_str("service_name = \"reconfig\"").expect("parse config");
+        let (live, _) = watch::channel(Arc::new(config.clone()));
+        let pushed = AppConfig {
+            quarantine_invalid_frames: !config.quarantine_invalid_frames,
+            expected_instrument_type: Some(2),
+            ..config
+        };
+        let outcome = apply_update(&live, &pushed, PORT_RECORDER_RUNTIME_FIELDS).expect("apply update");
+        assert_eq!(outcome.applied, ["expected_instrument_type"]);
+        assert_eq!(outcome.requires_restart, ["quarantine_invalid_frames"]);
+        assert_eq!(live.borrow().quarantine_invalid_frames, !pushed.quarantine_invalid_frames);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::config::RetentionAction;
+use crate::processing::copy_then_rename;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Applies the crate-wide `retention_action` to a file a retention sweep has expired: it is
+/// either deleted or moved into the archive directory, keeping its name. Every sweep goes
+/// through here so deployments that must never delete data only need the one setting.
+/// Returns where the file ended up, if it was kept.
+pub async fn retire(path: &Path, action: &RetentionAction) -> Result<Option<PathBuf>> {
+    match action {
+        RetentionAction::Delete => {
+            fs::remove_file(path)
+                .await
+                .with_context(|| format!("delete expired {}", path.display()))?;
+            tracing::info!(file = %path.display(), "retention removed expired file");
+            Ok(None)
+        }
+        RetentionAction::Archive(dir) => {
+            let name = path
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("{} has no file name", path.display()))?;
+            fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("create archive directory {dir}"))?;
+            let dest = Path::new(dir).join(name);
+            // The archive tier is often another mount, where a rename cannot work
+            if fs::rename(path, &dest).await.is_err() {
+                copy_then_rename(path, &dest).await?;
+            }
+            tracing::info!(file = %path.display(), archive = %dest.display(), "retention archived expired file");
+            Ok(Some(dest))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn archive_moves_expired_backups_instead_of_deleting() {
+        let tmp = tempdir().expect("temp dir");
+        let backup = tmp.path().join("backup");
+        let archive = tmp.path().join("archive");
+        fs::create_dir_all(&backup).await.expect("create backup");
+        let expired = backup.join("2026-01-01.raw");
+        fs::write(&expired, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n")
+            .await
+            .expect("write backup");
+
+        let action = RetentionAction::Archive(archive.to_string_lossy().to_string());
+        let dest = retire(&expired, &action).await.expect("retire");
+        assert_eq!(dest, Some(archive.join("2026-01-01.raw")));
+        assert!(!expired.exists(), "expired file left in the backup folder");
+        assert_eq!(
+            fs::read_to_string(archive.join("2026-01-01.raw")).await.expect("read archived"),
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n"
+        );
+
+        let doomed = backup.join("2026-01-02.raw");
+        fs::write(&doomed, "").await.expect("write backup");
+        assert_eq!(retire(&doomed, &RetentionAction::Delete).await.expect("retire"), None);
+        assert!(!doomed.exists());
+    }
+}
@@ -1,35 +1,62 @@
 use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::fs::File;
+use tokio::net::TcpStream;
 use tokio_serial::{SerialPortBuilderExt, SerialStream};
 
 #[cfg(unix)]
 use std::os::unix::fs::FileTypeExt;
 
+/// Prefix of a `serial_port` value naming a TCP terminal server, e.g. `tcp://10.0.0.5:4001`.
+pub const TCP_SCHEME: &str = "tcp://";
+
 enum ReaderSource {
     Serial(BufReader<SerialStream>),
     File(BufReader<File>),
+    Tcp(BufReader<TcpStream>),
 }
 
-/// A minimal async wrapper around a serial stream or file that returns newline-delimited
-/// strings. The buffer is reused to avoid repeated allocations.
+/// A minimal async wrapper around a serial stream or file that returns records delimited by
+/// `\n` (or the configured `record_delimiter`). The buffer is reused to avoid repeated allocations.
 pub struct SerialPort {
     reader: ReaderSource,
-    buffer: String,
+    buffer: Vec<u8>,
+    delimiter: u8,
+    reopenable: bool,
 }
 
 impl SerialPort {
-    pub async fn connect(port: &str, baud_rate: u32) -> Result<Self> {
+    /// Opens a serial device, FIFO, plain file or `tcp://host:port` terminal server. A FIFO
+    /// must see data from a writer, or a TCP server accept the connection, within
+    /// `open_timeout`, so a source that never starts is reported instead of hanging; see
+    /// `AppConfig::open_timeout` for the configured value per kind of port.
+    pub async fn connect(port: &str, baud_rate: u32, open_timeout: Duration) -> Result<Self> {
+        if let Some(address) = port.strip_prefix(TCP_SCHEME) {
+            return Self::connect_tcp(address, open_timeout).await;
+        }
         let _metadata = std::fs::metadata(port)?;
-        
-        let is_fifo_or_file = {
-            #[cfg(unix)]
-            { _metadata.file_type().is_fifo() || _metadata.is_file() }
-            #[cfg(not(unix))]
-            { _metadata.is_file() }
-        };
 
-        let reader = if is_fifo_or_file {
+        #[cfg(unix)]
+        if _metadata.file_type().is_fifo() {
+            let owned = port.to_string();
+            let file = tokio::task::spawn_blocking(move || open_fifo(&owned, open_timeout))
+                .await
+                .context("FIFO open task failed")??;
+            return Ok(Self {
+                reader: ReaderSource::File(BufReader::new(File::from_std(file))),
+                buffer: Vec::with_capacity(256),
+                delimiter: b'\n',
+                reopenable: true,
+            });
+        }
+        #[cfg(not(unix))]
+        let _ = open_timeout;
+
+        let reader = if _metadata.is_file() {
             // Treat as FIFO/file
             let file = File::open(port)
                 .await
@@ -43,25 +70,369 @@ impl SerialPort {
                 .with_context(|| format!("failed to open serial port {}", port))?;
             ReaderSource::Serial(BufReader::new(stream))
         };
+        let reopenable = matches!(reader, ReaderSource::Serial(_));
         Ok(Self {
             reader,
-            buffer: String::with_capacity(256),
+            buffer: Vec::with_capacity(256),
+            delimiter: b'\n',
+            reopenable,
         })
     }
 
+    /// Connects to a terminal server relaying the instrument's serial output over TCP.
+    pub async fn connect_tcp(address: &str, timeout: Duration) -> Result<Self> {
+        if address.rsplit_once(':').is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err()) {
+            anyhow::bail!("TCP input {address:?} must be {TCP_SCHEME}host:port");
+        }
+        let stream = match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(err)) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                return Err(err).with_context(|| format!("TCP terminal server {address} refused the connection; is it listening?"));
+            }
+            Ok(Err(err)) => return Err(err).with_context(|| format!("failed to connect to TCP terminal server {address}")),
+            Err(_) => anyhow::bail!("TCP terminal server {address} did not accept the connection within {timeout:?}"),
+        };
+        Ok(Self {
+            reader: ReaderSource::Tcp(BufReader::new(stream)),
+            buffer: Vec::with_capacity(256),
+            delimiter: b'\n',
+            reopenable: true,
+        })
+    }
+
+    /// Splits records on `delimiter` instead of `\n`, for instruments that frame records
+    /// with a control byte.
+    pub fn with_record_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether a closed or failed read means the device went away and is worth reopening
+    /// (serial port, FIFO or TCP connection), rather than the end of a plain file that may still grow.
+    pub fn reopenable(&self) -> bool {
+        self.reopenable
+    }
+
+    /// Reads the next record without its delimiter (and any trailing `\r`/`\n`).
     pub async fn next_line(&mut self) -> Result<Option<String>> {
         self.buffer.clear();
         let bytes = match &mut self.reader {
-            ReaderSource::Serial(r) => r.read_line(&mut self.buffer).await?,
-            ReaderSource::File(r) => r.read_line(&mut self.buffer).await?,
+            ReaderSource::Serial(r) => r.read_until(self.delimiter, &mut self.buffer).await?,
+            ReaderSource::File(r) => r.read_until(self.delimiter, &mut self.buffer).await?,
+            ReaderSource::Tcp(r) => r.read_until(self.delimiter, &mut self.buffer).await?,
         };
         if bytes == 0 {
             return Ok(None);
         }
-        let line = self
-            .buffer
-            .trim_end_matches(|c| c == '\r' || c == '\n')
-            .to_string();
-        Ok(Some(line))
+        if self.buffer.last() == Some(&self.delimiter) {
+            self.buffer.pop();
+        }
+        let record = std::str::from_utf8(&self.buffer).context("serial record is not valid UTF-8")?;
+        Ok(Some(record.trim_end_matches(['\r', '\n']).to_string()))
+    }
+}
+
+/// Splits bytes read in arbitrary chunks into records on `delimiter`, for readers that see the
+/// raw byte stream (e.g. to count throughput) instead of going through `SerialPort::next_line`.
+/// A record running past `max_record_bytes` without a delimiter is dropped, up to and
+/// including its delimiter, rather than buffered without bound.
+pub struct RecordSplitter {
+    pending: Vec<u8>,
+    delimiter: u8,
+    max_record_bytes: usize,
+    /// Set after an oversized record was dropped, until its delimiter arrives.
+    skipping: bool,
+    oversized: u64,
+}
+
+impl RecordSplitter {
+    pub fn new(delimiter: u8, max_record_bytes: usize) -> Self {
+        Self {
+            pending: Vec::with_capacity(256),
+            delimiter,
+            max_record_bytes,
+            skipping: false,
+            oversized: 0,
+        }
+    }
+
+    /// Adds `bytes` and returns the records they complete, without the delimiter (and any
+    /// trailing `\r`/`\n`); invalid UTF-8 is replaced rather than failing the record.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut records = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|b| *b == self.delimiter) {
+            let record: Vec<u8> = self.pending.drain(..=pos).collect();
+            if std::mem::take(&mut self.skipping) {
+                // The tail of a record already dropped as oversized
+                continue;
+            }
+            let record = String::from_utf8_lossy(&record[..record.len() - 1]);
+            records.push(record.trim_end_matches(['\r', '\n']).to_string());
+        }
+        if self.pending.len() > self.max_record_bytes {
+            self.pending.clear();
+            if !self.skipping {
+                self.skipping = true;
+                self.oversized += 1;
+            }
+        }
+        records
+    }
+
+    /// Records dropped for running past `max_record_bytes` since the last call.
+    pub fn take_oversized(&mut self) -> u64 {
+        std::mem::take(&mut self.oversized)
+    }
+}
+
+/// Opens a FIFO without blocking on a missing writer (`O_NONBLOCK`), waits up to `timeout` for
+/// it to become readable, then switches the descriptor back to blocking reads.
+#[cfg(unix)]
+fn open_fifo(port: &str, timeout: Duration) -> Result<std::fs::File> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(port)
+        .with_context(|| format!("failed to open FIFO {}", port))?;
+    let fd = file.as_raw_fd();
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        let millis = remaining.as_millis().min(i32::MAX as u128) as i32;
+        // SAFETY: `pollfd` is a valid, exclusively borrowed array of one entry.
+        let ready = unsafe { libc::poll(&mut pollfd, 1, millis) };
+        if ready > 0 {
+            break;
+        }
+        if ready == 0 {
+            anyhow::bail!("no writer produced data on FIFO {} within {:?}", port, timeout);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err).with_context(|| format!("failed to poll FIFO {}", port));
+        }
+    }
+    // SAFETY: `fd` is owned by `file`, which outlives both calls.
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK);
+    }
+    Ok(file)
+}
+
+/// Exclusive per-port lock so two recorders never read the same serial port. The lock file
+/// holds the owner's PID for diagnostics; the OS drops the lock when its holder exits, so a
+/// file left behind is simply reclaimed by the next recorder. It is never unlinked: a recorder
+/// that opened it just before the release would otherwise lock an orphaned inode while the
+/// next one created and locked a fresh file for the same port.
+#[derive(Debug)]
+pub struct PortLock {
+    file: std::fs::File,
+}
+
+impl PortLock {
+    /// Takes the lock for `port` under `dir`, failing immediately if another recorder holds it.
+    pub fn acquire(dir: &Path, port: &str) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create lock dir {}", dir.display()))?;
+        let sanitized: String = port
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = dir.join(format!("adcp_port_{}.lock", sanitized.trim_start_matches('_')));
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("failed to open port lock {}", path.display()))?;
+        if file.try_lock_exclusive().is_err() {
+            let mut holder = String::new();
+            file.read_to_string(&mut holder).ok();
+            anyhow::bail!(
+                "serial port {} already in use by this service (pid {}, lock {})",
+                port,
+                holder.trim(),
+                path.display()
+            );
+        }
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for PortLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Describes each serial port the OS reports, e.g. `/dev/ttyUSB0 (USB 0403:6001 FTDI FT232R)`.
+/// An empty list is not an error: FIFO/test setups and machines without adapters report none.
+pub fn describe_available_ports() -> Result<Vec<String>> {
+    let ports = tokio_serial::available_ports().context("enumerate serial ports")?;
+    Ok(ports
+        .into_iter()
+        .map(|port| {
+            let kind = match port.port_type {
+                tokio_serial::SerialPortType::UsbPort(usb) => {
+                    let mut kind = format!("USB {:04x}:{:04x}", usb.vid, usb.pid);
+                    for detail in [usb.manufacturer, usb.product].into_iter().flatten() {
+                        kind.push(' ');
+                        kind.push_str(&detail);
+                    }
+                    kind
+                }
+                tokio_serial::SerialPortType::PciPort => "PCI".to_string(),
+                tokio_serial::SerialPortType::BluetoothPort => "Bluetooth".to_string(),
+                tokio_serial::SerialPortType::Unknown => "unknown".to_string(),
+            };
+            format!("{} ({})", port.port_name, kind)
+        })
+        .collect())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[test]
+    fn splitter_joins_chunks_and_drops_oversized_records() {
+        let mut splitter = RecordSplitter::new(b'\n', 8);
+        assert!(splitter.push(b"$PNO").is_empty());
+        assert_eq!(splitter.push(b"RI\r\nok\n"), ["$PNORI", "ok"]);
+
+        // Past the cap the record is dropped through its delimiter, and counted once
+        assert!(splitter.push(b"0123456789").is_empty());
+        assert!(splitter.push(b"0123456789").is_empty());
+        assert_eq!(splitter.push(b"tail\nnext\n"), ["next"]);
+        assert_eq!(splitter.take_oversized(), 1);
+        assert_eq!(splitter.take_oversized(), 0);
+    }
+
+    #[tokio::test]
+    async fn fifo_connect_times_out_without_writer() {
+        let tmp = tempfile::tempdir().expect("temp dir");
+        let fifo = tmp.path().join("adcp.fifo");
+        let c_path = CString::new(fifo.as_os_str().as_bytes()).expect("fifo path");
+        // SAFETY: `c_path` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0, "mkfifo");
+
+        let started = std::time::Instant::now();
+        let result = SerialPort::connect(fifo.to_str().unwrap(), 115200, Duration::from_millis(200)).await;
+        let err = result.err().expect("connect must time out without a writer");
+        assert!(err.to_string().contains("within"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // With a writer present the same FIFO opens and yields its lines.
+        let writer_path = fifo.clone();
+        let writer = std::thread::spawn(move || {
+            std::fs::write(writer_path, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n")
+        });
+        let mut port = SerialPort::connect(fifo.to_str().unwrap(), 115200, Duration::from_secs(5))
+            .await
+            .expect("connect with writer");
+        let line = port.next_line().await.expect("read line");
+        assert_eq!(line.as_deref(), Some("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41"));
+        writer.join().unwrap().expect("writer");
+    }
+
+    #[tokio::test]
+    async fn reads_lines_from_a_tcp_terminal_server() {
+        use tokio::io::AsyncWriteExt;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            socket
+                .write_all(
+                    b"$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\r\n\
+                      $PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\r\n",
+                )
+                .await
+                .expect("send lines");
+        });
+
+        let mut port = SerialPort::connect(&format!("tcp://{address}"), 115200, Duration::from_secs(5))
+            .await
+            .expect("connect over TCP");
+        assert!(port.reopenable());
+        assert_eq!(
+            port.next_line().await.expect("first line").as_deref(),
+            Some("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41")
+        );
+        assert_eq!(
+            port.next_line().await.expect("second line").as_deref(),
+            Some("$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77")
+        );
+        server.await.expect("server");
+        assert_eq!(port.next_line().await.expect("closed").as_deref(), None);
+
+        // Nothing listens on the port once the server is gone
+        let err = SerialPort::connect(&format!("tcp://{address}"), 115200, Duration::from_secs(5))
+            .await
+            .err()
+            .expect("connection refused");
+        assert!(err.to_string().contains("refused the connection"), "{err:#}");
+        let err = SerialPort::connect("tcp://no-port", 115200, Duration::from_secs(5)).await.err().expect("bad address");
+        assert!(err.to_string().contains("host:port"), "{err:#}");
+    }
+
+    #[test]
+    fn second_recorder_cannot_lock_the_same_port() {
+        let tmp = tempfile::tempdir().expect("temp dir");
+        let first = PortLock::acquire(tmp.path(), "/dev/ttyUSB0").expect("first lock");
+        let err = PortLock::acquire(tmp.path(), "/dev/ttyUSB0").expect_err("port is held");
+        assert!(err.to_string().contains("already in use"), "{err:#}");
+        assert!(err.to_string().contains(&std::process::id().to_string()), "{err:#}");
+        PortLock::acquire(tmp.path(), "/dev/ttyUSB1").expect("other ports are independent");
+
+        drop(first);
+        PortLock::acquire(tmp.path(), "/dev/ttyUSB0").expect("released on drop");
+    }
+
+    #[test]
+    fn lock_opened_across_a_release_still_excludes_a_new_recorder() {
+        let tmp = tempfile::tempdir().expect("temp dir");
+        let first = PortLock::acquire(tmp.path(), "/dev/ttyUSB0").expect("first lock");
+        // A second recorder that opened the lock file just before the holder released it
+        let racer = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(tmp.path().join("adcp_port_dev_ttyUSB0.lock"))
+            .expect("open held lock file");
+        drop(first);
+        racer.try_lock_exclusive().expect("racer takes the released lock");
+
+        let err = PortLock::acquire(tmp.path(), "/dev/ttyUSB0").expect_err("racer holds the port");
+        assert!(err.to_string().contains("already in use"), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn splits_records_on_a_custom_delimiter() {
+        let tmp = tempfile::tempdir().expect("temp dir");
+        let path = tmp.path().join("capture.bin");
+        // ETX-framed records, one of them also carrying a CRLF
+        std::fs::write(&path, b"$PNORI,4,a*00\x03$PNORS,b*00\r\n\x03partial").expect("write capture");
+        let mut port = SerialPort::connect(path.to_str().expect("utf-8 path"), 9600, Duration::from_secs(1))
+            .await
+            .expect("open file")
+            .with_record_delimiter(0x03);
+
+        let mut records = Vec::new();
+        while let Some(record) = port.next_line().await.expect("read record") {
+            records.push(record);
+        }
+        assert_eq!(records, ["$PNORI,4,a*00", "$PNORS,b*00", "partial"]);
     }
 }
@@ -1,8 +1,9 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::{
     fs,
-    io::AsyncWriteExt,
     process,
     signal,
     sync::watch,
@@ -10,7 +11,7 @@ use tokio::{
 };
 
 use crate::config::{AppConfig, ServiceMode};
-use crate::{backup, metrics, parser, persistence, serial, processing};
+use crate::{backup, biofouling, control, http, manifest, metrics, parser, persistence, serial, processing, reconfig, simulator, telemetry, validation};
 use chrono::Utc;
 use std::time::Duration as StdDuration;
 use tokio::time::interval;
@@ -18,20 +19,990 @@ use tokio::time::interval;
 
 pub struct Service {
     config: AppConfig,
+    /// External stop request, e.g. `cmd.service.shutdown` received over the bus.
+    shutdown_trigger: Option<watch::Receiver<()>>,
+    /// File `config` was loaded from, re-read on SIGHUP in recording mode.
+    config_path: Option<PathBuf>,
+    /// Session totals for the shutdown summary, shared with the mode's health monitor.
+    metrics: Arc<metrics::Metrics>,
+    /// Output files of the session; written out as a manifest only when `write_manifest` is on.
+    files: Arc<manifest::RunManifest>,
+    /// Where heartbeats, the port lock and the orchestrator FIFO live.
+    tmp_dir: PathBuf,
+}
+
+/// Destinations each recorded serial line fans out to.
+pub struct RecordingSinks {
+    backup: tokio::sync::Mutex<backup::Backup>,
+    data_process: tokio::sync::Mutex<backup::Backup>,
+    persistence: persistence::Persistence,
+    quarantine: persistence::Persistence,
+    rejected: persistence::Persistence,
+    /// Per-instrument persistence used instead of `persistence` when `split_by_head_id` is on.
+    heads: tokio::sync::Mutex<HeadSinks>,
+    /// Takes over from `persistence` once `data_directory` keeps reporting a full disk.
+    fallback: tokio::sync::Mutex<DiskFallback>,
+    empty_run: std::sync::Mutex<EmptyLineRun>,
+    framing: std::sync::Mutex<FramingRun>,
+    timestamp_run: std::sync::Mutex<TimestampRun>,
+    cell_range: std::sync::Mutex<CellRange>,
+    config_frame: std::sync::Mutex<ConfigFrameWait>,
+    amplitude_trend: std::sync::Mutex<biofouling::AmplitudeTrend>,
+    saturation: std::sync::Mutex<biofouling::SaturationWindow>,
+    /// Latest sound speed reported by a `$PNORS` frame, for velocity correction.
+    sound_speed: std::sync::Mutex<Option<f32>>,
+    /// Output files written this run, present when `write_manifest` is enabled or the service
+    /// tracks them for its shutdown summary.
+    pub manifest: Option<Arc<manifest::RunManifest>>,
+    /// Last-value cache kept current with every recorded sensor frame, when one is attached.
+    sensors: Option<Arc<telemetry::SensorCache>>,
+}
+
+/// Subdirectory of `data_directory` for frames seen before any `$PNORI` names their head.
+const UNASSIGNED_HEAD_FOLDER: &str = "unassigned";
+
+/// Persistence per instrument head on a multiplexed port, keyed by subdirectory name.
+#[derive(Default)]
+struct HeadSinks {
+    /// Folder of the head named by the latest `$PNORI`; later frames belong to it.
+    current: Option<String>,
+    sinks: HashMap<String, Arc<persistence::Persistence>>,
+}
+
+/// Turns a head_id into a safe directory name: anything but ASCII letters, digits, `-`, `_`
+/// and `.` becomes `_`, so a corrupt config frame cannot escape `data_directory`.
+fn head_folder(head_id: &str) -> String {
+    let folder: String = head_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if folder.is_empty() || folder.chars().all(|c| c == '.') {
+        UNASSIGNED_HEAD_FOLDER.to_string()
+    } else {
+        folder
+    }
+}
+
+/// Consecutive out-of-space write failures in `data_directory` before the primary frame log
+/// moves to `fallback_data_directory`.
+const ENOSPC_FALLBACK_THRESHOLD: u32 = 3;
+
+/// Out-of-space failures of the primary frame log and, once switched, its fallback.
+#[derive(Default)]
+struct DiskFallback {
+    storage_full_run: u32,
+    sink: Option<Arc<persistence::Persistence>>,
+}
+
+/// Whether `err` was caused by the disk running out of space (ENOSPC).
+fn is_storage_full(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|io| io.kind() == std::io::ErrorKind::StorageFull)
+    })
+}
+
+/// The primary frame log for `dir`, with the pending, manifest and retention settings shared
+/// by the single-instrument and per-head layouts.
+async fn data_persistence(
+    dir: impl AsRef<std::path::Path>,
+    config: &AppConfig,
+    manifest: &Option<Arc<manifest::RunManifest>>,
+) -> Result<persistence::Persistence> {
+    Ok(persistence::Persistence::new(dir)
+        .await?
+        .with_pending_max_age(StdDuration::from_secs(config.pending_flush_seconds))
+        .with_manifest(manifest.clone())
+        .with_retention(data_retention(config))
+        .with_date_format(&config.filename_date_format)
+        .with_split_mode(config.split_mode)
+        .with_dir_sync(config.fsync_dir_on_rotate)
+        .with_output_format(config.output_format))
+}
+
+fn data_retention(config: &AppConfig) -> persistence::DataRetention {
+    persistence::DataRetention {
+        max_files: config.max_data_files,
+        max_age_days: config.max_data_age_days,
+        action: config.retention_action.clone(),
+    }
+}
+
+/// Tracks a run of consecutive empty lines, e.g. a stuck instrument emitting only CRLFs.
+#[derive(Default)]
+struct EmptyLineRun {
+    count: u32,
+    started: Option<std::time::Instant>,
+    warned: bool,
+}
+
+/// Tracks lines that do not start with `$` after cleanly framed data. The serial API hides
+/// the OS overrun flag, but an overrun that drops bytes mid-line shows up exactly like this.
+#[derive(Default)]
+struct FramingRun {
+    /// A `$`-leading line arrived since the last suspected overrun.
+    clean: bool,
+    unframed: u32,
+}
+
+/// Tracks consecutive frames carrying the same instrument timestamp, e.g. a frozen RTC.
+#[derive(Default)]
+struct TimestampRun {
+    last: Option<chrono::DateTime<Utc>>,
+    repeats: u32,
+    warned: bool,
+}
+
+/// Tracks the deepest cell reported per ensemble against the `$PNORI` cell count.
+#[derive(Default)]
+struct CellRange {
+    configured: Option<u16>,
+    ensemble: Option<chrono::DateTime<Utc>>,
+    max_cell: u16,
+    short_ensembles: u32,
+    warned: bool,
+}
+
+/// First wait before reopening a serial port that closed or failed.
+const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Reopens `port` after it closed or failed, waiting `backoff` before every attempt and
+/// doubling it up to `max_backoff`. The caller keeps `backoff` across reconnects and resets
+/// it only once a line arrives, so a port that opens and closes at once cannot spin.
+/// Returns `None` once shutdown is requested.
+async fn reconnect_serial(
+    port: &str,
+    baud_rate: u32,
+    open_timeout: Duration,
+    max_backoff: Duration,
+    backoff: &mut Duration,
+    shutdown_rx: &mut watch::Receiver<()>,
+    service_name: &str,
+) -> Option<serial::SerialPort> {
+    let max_backoff = max_backoff.max(RECONNECT_INITIAL_BACKOFF);
+    let mut attempt = 1u32;
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => return None,
+            _ = sleep(*backoff) => {}
+        }
+        *backoff = (*backoff * 2).min(max_backoff);
+        tracing::info!(service = %service_name, port = %port, attempt, "reconnecting serial port");
+        tokio::select! {
+            _ = shutdown_rx.changed() => return None,
+            opened = serial::SerialPort::connect(port, baud_rate, open_timeout) => match opened {
+                Ok(reader) => {
+                    tracing::info!(service = %service_name, port = %port, attempt, "serial port reconnected");
+                    return Some(reader);
+                }
+                Err(err) => tracing::warn!(
+                    service = %service_name,
+                    port = %port,
+                    attempt,
+                    retry_in_seconds = backoff.as_secs(),
+                    error = %err,
+                    "serial reconnect failed"
+                ),
+            }
+        }
+        attempt += 1;
+    }
+}
+
+/// Tracks how long data has flowed without a `$PNORI`, which the cell-count checks need.
+#[derive(Default)]
+struct ConfigFrameWait {
+    first_sent_at: Option<chrono::DateTime<Utc>>,
+    seen: bool,
+    warned: bool,
+}
+
+/// Hand-off between the serial reader and the task persisting its lines, bounded by the bytes
+/// held rather than the line count since line sizes vary. A line that would exceed the budget
+/// is dropped so a slow disk never stalls the reader; one line is always accepted into an empty
+/// queue so an oversized line cannot wedge capture.
+pub struct InflightLines {
+    lines: std::sync::Mutex<(std::collections::VecDeque<String>, usize)>,
+    max_bytes: usize,
+    ready: tokio::sync::Notify,
+}
+
+impl InflightLines {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            lines: std::sync::Mutex::new((std::collections::VecDeque::new(), 0)),
+            max_bytes,
+            ready: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Queues `line` without waiting; returns false if it was dropped for lack of budget.
+    pub fn push(&self, line: String) -> bool {
+        let Ok(mut guard) = self.lines.lock() else {
+            return false;
+        };
+        let (lines, bytes) = &mut *guard;
+        if !lines.is_empty() && *bytes + line.len() > self.max_bytes {
+            return false;
+        }
+        *bytes += line.len();
+        lines.push_back(line);
+        drop(guard);
+        self.ready.notify_one();
+        true
+    }
+
+    /// Takes the oldest queued line, if any.
+    pub fn try_next(&self) -> Option<String> {
+        let mut guard = self.lines.lock().ok()?;
+        let (lines, bytes) = &mut *guard;
+        let line = lines.pop_front()?;
+        *bytes -= line.len();
+        Some(line)
+    }
+
+    /// Waits for the next line to persist.
+    pub async fn next(&self) -> String {
+        loop {
+            if let Some(line) = self.try_next() {
+                return line;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    /// Bytes currently held.
+    pub fn bytes(&self) -> usize {
+        self.lines.lock().map(|guard| guard.1).unwrap_or(0)
+    }
+}
+
+impl RecordingSinks {
+    pub async fn new(config: &AppConfig) -> Result<Self> {
+        Self::with_manifest(config, config.write_manifest.then(|| Arc::new(manifest::RunManifest::new()))).await
+    }
+
+    /// Like `new`, recording every output file into `manifest` whatever `write_manifest` says.
+    pub async fn with_manifest(config: &AppConfig, manifest: Option<Arc<manifest::RunManifest>>) -> Result<Self> {
+        let pending_max_age = StdDuration::from_secs(config.pending_flush_seconds);
+        let retention = data_retention(config);
+        Ok(Self {
+            backup: tokio::sync::Mutex::new(
+                backup::Backup::new(&config.backup_folder)
+                    .await
+                    .context("prepare backup backend")?
+                    .with_compression(config.compress_backups)
+                    .with_writer_markers(config.backup_writer_markers)
+                    .with_dir_sync(config.fsync_dir_on_rotate)
+                    .with_retention(persistence::DataRetention {
+                        max_files: config.max_backup_files,
+                        max_age_days: config.max_backup_age_days,
+                        action: config.retention_action.clone(),
+                    })
+                    .with_manifest(manifest.clone()),
+            ),
+            data_process: tokio::sync::Mutex::new(
+                if config.data_process_run_id {
+                    backup::Backup::new_per_append_with_run_id(&config.data_process_folder).await
+                } else {
+                    backup::Backup::new_per_append(&config.data_process_folder).await
+                }
+                .context("prepare data process backend")?
+                .with_writer_markers(config.data_process_writer_markers)
+                .with_dir_sync(config.fsync_dir_on_rotate),
+            ),
+            persistence: data_persistence(&config.data_directory, config, &manifest)
+                .await
+                .context("prepare persistence backend")?,
+            quarantine: persistence::Persistence::with_prefix(&config.data_directory, "quarantine")
+                .await
+                .context("prepare quarantine backend")?
+                .with_pending_max_age(pending_max_age)
+                .with_manifest(manifest.clone())
+                .with_retention(retention.clone())
+                .with_date_format(&config.filename_date_format)
+                .with_split_mode(config.split_mode)
+                .with_dir_sync(config.fsync_dir_on_rotate)
+                .with_output_format(config.output_format),
+            rejected: persistence::Persistence::with_prefix(&config.data_directory, "rejected")
+                .await
+                .context("prepare rejected line backend")?
+                .with_manifest(manifest.clone())
+                .with_retention(retention)
+                .with_date_format(&config.filename_date_format)
+                .with_split_mode(config.split_mode)
+                .with_dir_sync(config.fsync_dir_on_rotate),
+            heads: tokio::sync::Mutex::new(HeadSinks::default()),
+            fallback: tokio::sync::Mutex::new(DiskFallback::default()),
+            empty_run: std::sync::Mutex::new(EmptyLineRun::default()),
+            framing: std::sync::Mutex::new(FramingRun::default()),
+            timestamp_run: std::sync::Mutex::new(TimestampRun::default()),
+            cell_range: std::sync::Mutex::new(CellRange::default()),
+            config_frame: std::sync::Mutex::new(ConfigFrameWait::default()),
+            amplitude_trend: std::sync::Mutex::new(biofouling::AmplitudeTrend::new()),
+            saturation: std::sync::Mutex::new(biofouling::SaturationWindow::new()),
+            sound_speed: std::sync::Mutex::new(None),
+            manifest,
+            sensors: None,
+        })
+    }
+
+    /// Keeps `sensors` updated with each sensor frame recorded, e.g. for `cmd.recorder.sensors`.
+    pub fn with_sensor_cache(mut self, sensors: Arc<telemetry::SensorCache>) -> Self {
+        self.sensors = Some(sensors);
+        self
+    }
+
+    /// Counts an empty line and warns once per window when the run exceeds the threshold.
+    fn observe_empty_line(&self, config: &AppConfig, metrics: &metrics::Metrics) {
+        metrics.record_empty_line();
+        let Ok(mut run) = self.empty_run.lock() else {
+            return;
+        };
+        let window = StdDuration::from_secs(config.empty_line_window_seconds);
+        if run.started.is_none_or(|started| started.elapsed() > window) {
+            *run = EmptyLineRun {
+                started: Some(std::time::Instant::now()),
+                ..EmptyLineRun::default()
+            };
+        }
+        run.count += 1;
+        if run.count > config.empty_line_threshold && !run.warned {
+            run.warned = true;
+            metrics.record_empty_line_burst();
+            tracing::warn!(
+                service = %config.service_name,
+                consecutive = run.count,
+                window_seconds = config.empty_line_window_seconds,
+                "instrument sending only empty lines"
+            );
+        }
+    }
+
+    /// Counts a suspected overrun once a burst of unframed lines follows clean framing; the
+    /// next burst only counts after clean lines arrive again.
+    fn observe_framing(&self, raw: &str, config: &AppConfig, metrics: &metrics::Metrics) {
+        let Ok(mut run) = self.framing.lock() else {
+            return;
+        };
+        if raw.trim_start().starts_with('$') {
+            run.clean = true;
+            run.unframed = 0;
+            return;
+        }
+        run.unframed += 1;
+        if config.overrun_burst_lines > 0 && run.clean && run.unframed >= config.overrun_burst_lines {
+            run.clean = false;
+            metrics.record_suspected_overrun();
+            tracing::warn!(
+                service = %config.service_name,
+                unframed_lines = run.unframed,
+                baud_rate = config.baud_rate,
+                "lines stopped starting with '$' after clean data; suspected serial buffer overrun, consider lowering the baud or ping rate"
+            );
+        }
+    }
+
+    /// Finishes the recording: completes a rolled backup still being gzipped and removes the
+    /// `.writing` markers left in the data process (and backup) folder, so the processor does
+    /// not wait on a writer that is gone.
+    pub async fn close(&self, config: &AppConfig) {
+        self.backup.lock().await.finish_compression().await;
+        for dp in [Some(&config.data_process_folder), config.backup_writer_markers.then_some(&config.backup_folder)]
+            .into_iter()
+            .flatten()
+        {
+            if let Err(e) = async {
+                let mut rd = tokio::fs::read_dir(dp).await?;
+                while let Ok(Some(entry)) = rd.next_entry().await {
+                    let path = entry.path();
+                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                        if name.ends_with(".writing") {
+                            let _ = tokio::fs::remove_file(&path).await;
+                            tracing::info!(marker = %name, folder = %dp, "removed leftover writing marker");
+                        }
+                    }
+                }
+                Ok::<(), anyhow::Error>(())
+            }
+            .await
+            {
+                tracing::warn!(error = %e, folder = %dp, "failed to cleanup leftover writing markers");
+            }
+        }
+    }
+
+    /// Writes undated frames that waited too long for a dated frame using the host clock, and
+    /// fsyncs logs idle for `sync_on_idle_seconds`.
+    pub async fn flush_stale_pending(&self, config: &AppConfig) {
+        let mut extra: Vec<_> = self.heads.lock().await.sinks.values().cloned().collect();
+        extra.extend(self.fallback.lock().await.sink.clone());
+        let shared = [&self.persistence, &self.quarantine, &self.rejected];
+        for sink in shared.into_iter().chain(extra.iter().map(|sink| sink.as_ref())) {
+            if let Err(err) = sink.flush_stale_pending().await {
+                tracing::error!(service = %config.service_name, error = %err, "pending frame flush failed");
+            }
+            if let Some(idle) = config.sync_on_idle_seconds {
+                if let Err(err) = sink.sync_if_idle(StdDuration::from_secs(idle)).await {
+                    tracing::error!(service = %config.service_name, error = %err, "idle log fsync failed");
+                }
+            }
+        }
+    }
+
+    /// Flags a frozen instrument clock once too many consecutive frames repeat one timestamp;
+    /// the flag clears as soon as the timestamp advances again.
+    fn observe_timestamp(
+        &self,
+        sent_at: chrono::DateTime<Utc>,
+        config: &AppConfig,
+        metrics: &metrics::Metrics,
+    ) {
+        let Ok(mut run) = self.timestamp_run.lock() else {
+            return;
+        };
+        if run.last != Some(sent_at) {
+            if run.warned {
+                tracing::info!(service = %config.service_name, sent_at = %sent_at, "instrument clock advancing again");
+                metrics.set_clock_frozen(false);
+            }
+            *run = TimestampRun {
+                last: Some(sent_at),
+                ..TimestampRun::default()
+            };
+            return;
+        }
+        run.repeats += 1;
+        if run.repeats >= config.frozen_clock_threshold && !run.warned {
+            run.warned = true;
+            metrics.set_clock_frozen(true);
+            tracing::warn!(
+                service = %config.service_name,
+                sent_at = %sent_at,
+                repeats = run.repeats,
+                "instrument clock appears frozen: timestamp not advancing"
+            );
+        }
+    }
+
+    /// Closes an ensemble when a new `$PNORC` timestamp arrives and flags degraded range once
+    /// enough consecutive ensembles stop short of the configured cell count.
+    fn observe_cells(&self, payload: &parser::Payload, config: &AppConfig, metrics: &metrics::Metrics) {
+        let Ok(mut range) = self.cell_range.lock() else {
+            return;
+        };
+        let current = match payload {
+            parser::Payload::Config(cfg) => {
+                range.configured = Some(cfg.cells);
+                return;
+            }
+            parser::Payload::Current(current) => current,
+            _ => return,
+        };
+        if range.ensemble == Some(current.sent_at) {
+            range.max_cell = range.max_cell.max(current.cell_number);
+            return;
+        }
+        if let (Some(configured), Some(_)) = (range.configured, range.ensemble) {
+            let expected = f64::from(configured) * config.min_cells_fraction;
+            if f64::from(range.max_cell) < expected {
+                range.short_ensembles += 1;
+            } else {
+                if range.warned {
+                    tracing::info!(service = %config.service_name, "profiling range recovered");
+                    metrics.set_range_degraded(false);
+                }
+                range.short_ensembles = 0;
+                range.warned = false;
+            }
+            if range.short_ensembles >= config.range_degraded_ensembles && !range.warned {
+                range.warned = true;
+                metrics.set_range_degraded(true);
+                tracing::warn!(
+                    service = %config.service_name,
+                    max_cell = range.max_cell,
+                    configured_cells = configured,
+                    ensembles = range.short_ensembles,
+                    "profiling range degraded: ensembles report too few cells"
+                );
+            }
+        }
+        range.ensemble = Some(current.sent_at);
+        range.max_cell = current.cell_number;
+    }
+
+    /// Warns once when dated frames span `config_frame_grace_seconds` of instrument time
+    /// without a `$PNORI`, since the cell-count checks silently idle until one arrives, and
+    /// reports them enabled again when it does.
+    fn observe_config_frame(&self, payload: &parser::Payload, config: &AppConfig, metrics: &metrics::Metrics) {
+        let Ok(mut wait) = self.config_frame.lock() else {
+            return;
+        };
+        if let parser::Payload::Config(header) = payload {
+            if wait.warned {
+                tracing::info!(
+                    service = %config.service_name,
+                    head_id = %header.head_id,
+                    "$PNORI received; depth/range checks enabled"
+                );
+                metrics.set_config_frame_missing(false);
+            }
+            *wait = ConfigFrameWait {
+                seen: true,
+                ..ConfigFrameWait::default()
+            };
+            return;
+        }
+        let Some(sent_at) = payload.sent_at() else {
+            return;
+        };
+        if wait.seen || wait.warned || config.config_frame_grace_seconds == 0 {
+            return;
+        }
+        let first = *wait.first_sent_at.get_or_insert(sent_at);
+        let waited = (sent_at - first).num_seconds();
+        if waited >= 0 && waited as u64 >= config.config_frame_grace_seconds {
+            wait.warned = true;
+            metrics.set_config_frame_missing(true);
+            tracing::warn!(
+                service = %config.service_name,
+                waited_seconds = waited,
+                "no $PNORI seen in {waited}s; depth/range checks disabled until the instrument sends its configuration"
+            );
+        }
+    }
+
+    /// Feeds beam amplitudes into the biofouling trend and raises a maintenance warning
+    /// the first time a beam degrades.
+    fn observe_amplitude(
+        &self,
+        current: &parser::CurrentSentence,
+        config: &AppConfig,
+        metrics: &metrics::Metrics,
+    ) {
+        let Ok(mut trend) = self.amplitude_trend.lock() else {
+            return;
+        };
+        for beam in trend.observe(current, config.biofouling_alert_fraction, config.biofouling_baseline_days) {
+            tracing::warn!(
+                service = %config.service_name,
+                beam,
+                fraction = config.biofouling_alert_fraction,
+                "maintenance: beam amplitude below baseline, transducer biofouling suspected"
+            );
+        }
+        metrics.set_biofouling_suspected(trend.suspected());
+    }
+
+    /// Counts pegged beam amplitudes and warns the first time a beam's saturated fraction
+    /// exceeds the configured threshold.
+    fn observe_saturation(
+        &self,
+        current: &parser::CurrentSentence,
+        config: &AppConfig,
+        metrics: &metrics::Metrics,
+    ) {
+        for beam in biofouling::pegged_beams(current) {
+            metrics.record_saturated_sample(beam);
+        }
+        let Ok(mut saturation) = self.saturation.lock() else {
+            return;
+        };
+        for beam in saturation.observe(current, config.saturation_window, config.saturation_alert_fraction) {
+            tracing::warn!(
+                service = %config.service_name,
+                beam,
+                window = config.saturation_window,
+                fraction = config.saturation_alert_fraction,
+                "beam amplitude pegged at maximum, hardware fault or interference suspected"
+            );
+        }
+        metrics.set_amplitude_saturated(saturation.saturated());
+    }
+
+    /// Remembers the latest sensor sound speed and, when enabled, rescales current
+    /// velocities by it. Currents arriving before any sound speed is known are left as-is.
+    fn correct_sound_speed(&self, frame: &mut parser::Frame, config: &AppConfig) {
+        let Ok(mut sound_speed) = self.sound_speed.lock() else {
+            return;
+        };
+        if let parser::Payload::Sensor(sensor) = &frame.payload {
+            if sensor.sound_speed_m_s.is_some() {
+                *sound_speed = sensor.sound_speed_m_s;
+            }
+        }
+        if let (true, Some(actual)) = (config.sound_speed_correction, *sound_speed) {
+            frame.apply_sound_speed_correction(actual, config.reference_sound_speed_m_s);
+        }
+    }
+
+    /// Persists `frame` to the primary log, moving it to `fallback_data_directory` once
+    /// `data_directory` has reported a full disk too many times in a row. The frame that
+    /// triggers the switch is retried on the fallback, so it is not lost.
+    async fn append_primary(&self, frame: &parser::Frame, config: &AppConfig) -> Result<()> {
+        let mut fallback = self.fallback.lock().await;
+        if let Some(sink) = &fallback.sink {
+            return sink.append(frame).await;
+        }
+        let err = match self.persistence.append(frame).await {
+            Ok(()) => {
+                fallback.storage_full_run = 0;
+                return Ok(());
+            }
+            Err(err) if is_storage_full(&err) => err,
+            Err(err) => return Err(err),
+        };
+        fallback.storage_full_run += 1;
+        let Some(dir) = &config.fallback_data_directory else {
+            return Err(err);
+        };
+        if fallback.storage_full_run < ENOSPC_FALLBACK_THRESHOLD {
+            return Err(err);
+        }
+        let sink = Arc::new(
+            data_persistence(dir, config, &self.manifest)
+                .await
+                .context("prepare fallback data directory")?,
+        );
+        tracing::error!(
+            service = %config.service_name,
+            data_dir = %config.data_directory,
+            fallback_dir = %dir,
+            failures = fallback.storage_full_run,
+            error = %err,
+            "DATA DIRECTORY FULL: switching frame persistence to fallback_data_directory"
+        );
+        fallback.sink = Some(sink.clone());
+        sink.append(frame).await
+    }
+
+    /// Persists `frame` under the folder of the head named by the latest `$PNORI` (which
+    /// itself switches the head), or the unassigned folder before any was seen.
+    async fn append_for_head(&self, frame: &parser::Frame, config: &AppConfig) -> Result<()> {
+        let sink = {
+            let mut heads = self.heads.lock().await;
+            if let parser::Payload::Config(header) = &frame.payload {
+                heads.current = Some(head_folder(&header.head_id));
+            }
+            let folder = heads
+                .current
+                .clone()
+                .unwrap_or_else(|| UNASSIGNED_HEAD_FOLDER.to_string());
+            match heads.sinks.get(&folder) {
+                Some(sink) => sink.clone(),
+                None => {
+                    let dir = std::path::Path::new(&config.data_directory).join(&folder);
+                    let sink = Arc::new(
+                        data_persistence(&dir, config, &self.manifest)
+                            .await
+                            .with_context(|| format!("prepare persistence for head {folder}"))?,
+                    );
+                    tracing::info!(service = %config.service_name, head = %folder, dir = %dir.display(), "persisting new instrument head separately");
+                    heads.sinks.insert(folder, sink.clone());
+                    sink
+                }
+            }
+        };
+        sink.append(frame).await
+    }
+
+    /// Writes one raw line to the backup and processing folders, then parses and persists it.
+    /// Blank lines are counted and kept in the raw copies but not parsed.
+    /// Failures of one destination are counted and logged without stopping the others.
+    pub async fn record_line(&self, raw: &str, config: &AppConfig, metrics: &metrics::Metrics) {
+        let service_name = &config.service_name;
+        let blank = raw.trim().is_empty();
+        if blank {
+            self.observe_empty_line(config, metrics);
+        } else {
+            if let Ok(mut run) = self.empty_run.lock() {
+                *run = EmptyLineRun::default();
+            }
+            self.observe_framing(raw, config, metrics);
+        }
+        // Always write raw capture to backup and processing folders. Do not allow
+        // backup failures to stop capture; log and continue. The data_process
+        // append updates a writer marker file to signal active writing so the
+        // processor will avoid files that are still being appended to.
+        let ts = Utc::now();
+        if let Err(err) = self.backup.lock().await.append(raw, ts).await {
+            metrics.record_backup_error();
+            tracing::error!(service = %service_name, error = %err, "backup write failed");
+        }
+        if let Err(err) = self.data_process.lock().await.append(raw, ts).await {
+            metrics.record_data_process_error();
+            tracing::error!(service = %service_name, error = %err, "data process write failed");
+        }
+        if blank {
+            return;
+        }
+
+        let parse_started = config.profile_parsing.then(std::time::Instant::now);
+        match parser::Frame::from_line_with(raw, &config.dialect) {
+            Ok(mut frame) => {
+                if let Some(started) = parse_started {
+                    metrics.record_parse_time(frame.payload.kind(), started.elapsed());
+                }
+                if config.include_ingested_at {
+                    frame.ingested_at = Some(ts);
+                }
+                for junk in &frame.discarded {
+                    metrics.record_discarded(junk);
+                }
+                if !validation::check_horizon(config.min_frame_age_days, config.max_frame_age_days, &frame, ts, metrics) {
+                    return;
+                }
+                metrics.record_frame();
+                if let Some(sensors) = &self.sensors {
+                    sensors.update(&frame);
+                }
+                if let Some(sent_at) = frame.payload.sent_at() {
+                    self.observe_timestamp(sent_at, config, metrics);
+                }
+                self.observe_config_frame(&frame.payload, config, metrics);
+                self.observe_cells(&frame.payload, config, metrics);
+                if let parser::Payload::Current(current) = &frame.payload {
+                    self.observe_amplitude(current, config, metrics);
+                    self.observe_saturation(current, config, metrics);
+                }
+                self.correct_sound_speed(&mut frame, config);
+                validation::check_instrument(config.expected_instrument_type, &frame, metrics);
+                let valid = validation::check(&config.validation_rules, &frame, metrics);
+                let result = if !valid && config.quarantine_invalid_frames {
+                    self.quarantine.append(&frame).await
+                } else if config.split_by_head_id {
+                    self.append_for_head(&frame, config).await
+                } else {
+                    self.append_primary(&frame, config).await
+                };
+                if let Err(err) = result {
+                    metrics.record_persistence_error();
+                    tracing::error!(
+                        service = %service_name,
+                        error = %err,
+                        "persister failed"
+                    );
+                }
+            }
+            Err(err) => {
+                metrics.record_parse_error();
+                if err.downcast_ref::<parser::OversizedField>().is_some() {
+                    metrics.record_oversized_field();
+                }
+                tracing::warn!(
+                    service = %service_name,
+                    error = %err,
+                    frame = %raw,
+                    "frame rejected"
+                );
+                if config.persist_rejected {
+                    if let Err(err) = self.rejected.append_raw(raw, ts).await {
+                        metrics.record_persistence_error();
+                        tracing::error!(service = %service_name, error = %err, "rejected line write failed");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resolves on ctrl-c or, on Unix, SIGTERM (e.g. `systemctl stop`) so the service loops run
+/// their cleanup instead of being killed with markers left behind. Returns the signal's name.
+pub async fn os_shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal as unix_signal, SignalKind};
+        match unix_signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                return tokio::select! {
+                    _ = signal::ctrl_c() => "ctrl-c",
+                    _ = sigterm.recv() => "SIGTERM",
+                };
+            }
+            Err(err) => tracing::warn!(error = %err, "failed to install SIGTERM handler"),
+        }
+    }
+    signal::ctrl_c().await.ok();
+    "ctrl-c"
+}
+
+/// Waits for an OS shutdown signal or a request through `trigger`, returning what fired.
+async fn wait_for_shutdown_signal(trigger: Option<watch::Receiver<()>>) -> &'static str {
+    let requested = async move {
+        match trigger {
+            Some(mut trigger) => control::shutdown_requested(&mut trigger).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        _ = requested => "shutdown request",
+        signal = os_shutdown_signal() => signal,
+    }
+}
+
+/// Records the lines the serial reader queued in `inflight` until `shutdown`, then drains the
+/// rest. Every line is recorded against the current `config`, so reloaded validation,
+/// quarantine and framing settings apply from the next line on.
+async fn persist_lines(
+    sinks: Arc<RecordingSinks>,
+    inflight: Arc<InflightLines>,
+    config: watch::Receiver<Arc<AppConfig>>,
+    metrics: Arc<metrics::Metrics>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            biased;
+            raw = inflight.next() => {
+                let live = config.borrow().clone();
+                sinks.record_line(&raw, &live, &metrics).await;
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+    // Lines the reader already accepted are still written before exiting
+    while let Some(raw) = inflight.try_next() {
+        let live = config.borrow().clone();
+        sinks.record_line(&raw, &live, &metrics).await;
+    }
+}
+
+/// Whether the orchestrator can spawn `path`. A bare name (`adcp`) is looked up on `PATH`,
+/// as `Command` does; on Windows the `.exe` may be left off, as `Command` adds it.
+fn child_binary_exists(path: &Path) -> bool {
+    let is_spawnable = |candidate: &Path| {
+        candidate.is_file()
+            || (cfg!(windows) && candidate.extension().is_none() && candidate.with_extension("exe").is_file())
+    };
+    if path.parent().is_some_and(|parent| parent.as_os_str().is_empty()) {
+        std::env::var_os("PATH")
+            .is_some_and(|dirs| std::env::split_paths(&dirs).any(|dir| is_spawnable(&dir.join(path))))
+    } else {
+        is_spawnable(path)
+    }
+}
+
+/// Re-reads `config_path` on every SIGHUP and applies its runtime fields to `live`, the
+/// config `monitor_health` reads each tick and `persist_lines` reads for each line. A file
+/// that fails to load is logged and the running config kept.
+#[cfg(unix)]
+async fn reload_on_hangup(
+    mut hangup: tokio::signal::unix::Signal,
+    config_path: PathBuf,
+    live: watch::Sender<Arc<AppConfig>>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            received = hangup.recv() => {
+                if received.is_none() {
+                    break;
+                }
+                tracing::info!(path = %config_path.display(), "SIGHUP received; reloading config");
+                match AppConfig::load(&config_path) {
+                    Ok(reloaded) => {
+                        if let Err(err) = reconfig::apply_update(&live, &reloaded, reconfig::RUNTIME_FIELDS) {
+                            tracing::error!(path = %config_path.display(), error = %err, "config reload failed; keeping the running config");
+                        }
+                    }
+                    Err(err) => tracing::error!(
+                        path = %config_path.display(),
+                        error = format!("{err:#}"),
+                        "config reload failed; keeping the running config"
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// Pause before respawning a panicked task.
+const SUPERVISOR_RESPAWN_DELAY_MS: u64 = 200;
+
+/// Default `Service::with_tmp_dir`, shared with the child processes the orchestrator starts.
+const DEFAULT_TMP_DIR: &str = "./deployment/tmp";
+
+/// Spawns the task built by `make` and respawns it whenever it panics, so a panicked
+/// heartbeat or health loop recovers in-process instead of going silent until the
+/// orchestrator restarts everything. A panic after `shutdown` fired is propagated as is.
+fn supervise<T, F, Fut>(task: &'static str, shutdown: watch::Receiver<()>, mut make: F) -> tokio::task::JoinHandle<T>
+where
+    T: Send + 'static,
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            match tokio::spawn(make()).await {
+                Ok(output) => return output,
+                Err(err) if err.is_panic() && !shutdown.has_changed().unwrap_or(true) => {
+                    tracing::error!(task, error = %err, "task panicked; respawning");
+                    // Back off so a task that panics on every start does not spin
+                    sleep(Duration::from_millis(SUPERVISOR_RESPAWN_DELAY_MS)).await;
+                }
+                Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+                Err(err) => panic!("supervised task {task} cancelled: {err}"),
+            }
+        }
+    })
 }
 
 impl Service {
     pub fn new(config: AppConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            shutdown_trigger: None,
+            config_path: None,
+            metrics: Arc::new(metrics::Metrics::new()),
+            files: Arc::new(manifest::RunManifest::new()),
+            tmp_dir: PathBuf::from(DEFAULT_TMP_DIR),
+        }
+    }
+
+    /// Keeps heartbeats, the port lock and the orchestrator FIFO in `dir` instead of
+    /// `./deployment/tmp`.
+    pub fn with_tmp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.tmp_dir = dir.into();
+        self
+    }
+
+    /// Lets a recorder reload `path` on SIGHUP, applying its runtime fields (see
+    /// `reconfig::RUNTIME_FIELDS`) without dropping the serial connection.
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Also stops the service when `trigger` fires, running the same cleanup as ctrl-c.
+    pub fn with_shutdown_trigger(mut self, trigger: watch::Receiver<()>) -> Self {
+        self.shutdown_trigger = Some(trigger);
+        self
     }
 
     pub async fn run(self) -> Result<()> {
-        match self.config.mode {
+        let started = std::time::Instant::now();
+        if matches!(self.config.mode, ServiceMode::Recording | ServiceMode::Processing) {
+            let snapshot = self
+                .config
+                .write_run_snapshot(&self.config.data_directory)
+                .context("record effective run config")?;
+            tracing::info!(service = %self.config.service_name, path = %snapshot.display(), "effective config recorded");
+        }
+        let result = match self.config.mode {
             ServiceMode::Recording => self.run_recording().await,
             ServiceMode::Processing => self.run_processing().await,
             ServiceMode::Orchestrator => self.run_orchestrator().await,
             ServiceMode::Simulator => self.run_simulator().await,
-        }
+        };
+        self.shutdown_report(started.elapsed()).log(&self.config.service_name);
+        result
+    }
+
+    /// Totals of the session so far, as logged when `run` returns.
+    pub fn shutdown_report(&self, uptime: StdDuration) -> metrics::ShutdownReport {
+        metrics::ShutdownReport::new(&self.metrics.snapshot(), uptime, self.files.entries().len())
     }
 
     async fn run_recording(&self) -> Result<()> {
@@ -40,11 +1011,9 @@ impl Service {
             data_directory,
             serial_port: serial_port_opt,
             baud_rate,
-            idle_threshold_seconds,
-            alert_webhook,
-            backup_folder,
-            data_process_folder,
+            reconnect_max_backoff_seconds,
             file_stability_seconds,
+            record_delimiter,
             ..
         } = &self.config;
 
@@ -52,61 +1021,129 @@ impl Service {
         let supervisor_name = Arc::new(service_name.clone());
         let data_directory = Arc::new(data_directory.clone());
         let serial_port = Arc::new(serial_port_opt.clone().ok_or_else(|| anyhow::anyhow!("serial_port required for Recording mode"))?);
-        let backup_folder = Arc::new(backup_folder.clone());
-        let data_process_folder = Arc::new(data_process_folder.clone());
-        let metrics = Arc::new(metrics::Metrics::new());
-        let persistence = Arc::new(
-            persistence::Persistence::new(data_directory.as_ref())
-                .await
-                .context("prepare persistence backend")?,
-        );
-        let backup = Arc::new(tokio::sync::Mutex::new(
-            backup::Backup::new(backup_folder.as_ref())
-                .await
-                .context("prepare backup backend")?,
-        ));
-        let data_process = Arc::new(tokio::sync::Mutex::new(
-            backup::Backup::new_per_append(data_process_folder.as_ref())
-                .await
-                .context("prepare data process backend")?,
-        ));
+        // Held until this function returns; a second recorder on the same port bails here
+        let _port_lock = serial::PortLock::acquire(&self.tmp_dir, &serial_port)?;
+        let open_timeout = self.config.open_timeout(&serial_port);
+        let metrics = self.metrics.clone();
+        let sinks = Arc::new(RecordingSinks::with_manifest(&self.config, Some(self.files.clone())).await?);
 
-        let health_handle = tokio::spawn(metrics::monitor_health(
-            supervisor_name.clone(),
-            metrics.clone(),
-            shutdown_rx.clone(),
-            Duration::from_secs(*idle_threshold_seconds),
-            alert_webhook.clone(),
-        ));
+        let (live_config, config_rx) = watch::channel(Arc::new(self.config.clone()));
+        #[cfg(unix)]
+        let reload_handle = match &self.config_path {
+            Some(path) => match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(hangup) => Some(tokio::spawn(reload_on_hangup(
+                    hangup,
+                    path.clone(),
+                    live_config,
+                    shutdown_rx.clone(),
+                ))),
+                Err(err) => {
+                    tracing::warn!(service = %service_name, error = %err, "failed to install SIGHUP handler");
+                    None
+                }
+            },
+            None => None,
+        };
+        #[cfg(not(unix))]
+        let _live_config = live_config;
 
-        // Prepare tmp folder under deployment for IPC and heartbeats
-        let tmp_dir = "./deployment/tmp".to_string();
+        let http_handle = match &self.config.metrics_listen_addr {
+            Some(address) => Some(tokio::spawn(http::serve_health(
+                http::bind(address).await?,
+                supervisor_name.clone(),
+                metrics.clone(),
+                config_rx.clone(),
+                shutdown_rx.clone(),
+            ))),
+            None => None,
+        };
+        let health_handle = supervise("health", shutdown_rx.clone(), {
+            let supervisor_name = supervisor_name.clone();
+            let metrics = metrics.clone();
+            let shutdown_rx = shutdown_rx.clone();
+            let config_rx = config_rx.clone();
+            move || {
+                metrics::monitor_health(
+                    supervisor_name.clone(),
+                    metrics.clone(),
+                    shutdown_rx.clone(),
+                    config_rx.clone(),
+                )
+            }
+        });
+
+        // Prepare the tmp folder for IPC and heartbeats
+        let tmp_dir = self.tmp_dir.to_string_lossy().to_string();
         fs::create_dir_all(&tmp_dir).await.ok();
         // Heartbeat file for supervisor to monitor liveness
         let hb_path = format!("{}/adcp_{}_hb", tmp_dir, service_name.replace(' ', "_"));
-        let mut hb_shutdown = shutdown_rx.clone();
-        let hb_name = hb_path.clone();
         let hb_interval = StdDuration::from_secs(std::cmp::min(5, *file_stability_seconds).max(1));
-        let hb_handle = tokio::spawn(async move {
-            let mut ticker = interval(hb_interval);
-            loop {
-                tokio::select! {
-                    _ = hb_shutdown.changed() => break,
-                    _ = ticker.tick() => {
-                        let _ = tokio::fs::write(&hb_name, format!("{}", chrono::Utc::now().timestamp())).await;
+        let hb_handle = supervise("heartbeat", shutdown_rx.clone(), {
+            let hb_shutdown = shutdown_rx.clone();
+            let hb_name = hb_path.clone();
+            move || {
+                let mut hb_shutdown = hb_shutdown.clone();
+                let hb_name = hb_name.clone();
+                async move {
+                    let mut ticker = interval(hb_interval);
+                    loop {
+                        tokio::select! {
+                            _ = hb_shutdown.changed() => break,
+                            _ = ticker.tick() => {
+                                let _ = tokio::fs::write(&hb_name, format!("{}", chrono::Utc::now().timestamp())).await;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Undated frames must not wait forever when no dated frame ever arrives
+        let pending_handle = supervise("pending flush", shutdown_rx.clone(), {
+            let pending_shutdown = shutdown_rx.clone();
+            let sinks = sinks.clone();
+            let config = self.config.clone();
+            move || {
+                let mut pending_shutdown = pending_shutdown.clone();
+                let sinks = sinks.clone();
+                let config = config.clone();
+                async move {
+                    let mut ticker = interval(Duration::from_secs(1));
+                    loop {
+                        tokio::select! {
+                            _ = pending_shutdown.changed() => break,
+                            _ = ticker.tick() => sinks.flush_stale_pending(&config).await,
+                        }
                     }
                 }
             }
         });
 
+        // Persist on a separate task so slow disks cost dropped lines, not a stalled reader
+        let inflight = Arc::new(InflightLines::new(self.config.max_inflight_bytes));
+        let persist_handle = supervise("persistence", shutdown_rx.clone(), {
+            let persist_shutdown = shutdown_rx.clone();
+            let sinks = sinks.clone();
+            let metrics = metrics.clone();
+            let inflight = inflight.clone();
+            let config_rx = config_rx.clone();
+            move || {
+                persist_lines(
+                    sinks.clone(),
+                    inflight.clone(),
+                    config_rx.clone(),
+                    metrics.clone(),
+                    persist_shutdown.clone(),
+                )
+            }
+        });
+
         let worker_future = {
             let supervisor_name = supervisor_name.clone();
             let data_directory = data_directory.clone();
             let serial_port = serial_port.clone();
-            let persistence = persistence.clone();
             let metrics = metrics.clone();
-            let backup = backup.clone();
-            let data_process = data_process.clone();
+            let inflight = inflight.clone();
             let mut shutdown_rx = shutdown_rx.clone();
             async move {
                 tracing::info!(
@@ -115,7 +1152,14 @@ impl Service {
                     port = %serial_port,
                     "serial capture starting"
                 );
-                let mut reader = serial::SerialPort::connect(&serial_port, *baud_rate).await?;
+                let mut reader = serial::SerialPort::connect(
+                    &serial_port,
+                    *baud_rate,
+                    open_timeout,
+                )
+                .await?
+                .with_record_delimiter(*record_delimiter);
+                let mut reconnect_backoff = RECONNECT_INITIAL_BACKOFF;
                 loop {
                     tokio::select! {
                         _ = shutdown_rx.changed() => {
@@ -125,44 +1169,19 @@ impl Service {
                         line = reader.next_line() => {
                             match line {
                                 Ok(Some(raw)) => {
-                                                        // Always write raw capture to backup and processing folders. Do not allow
-                                    // backup failures to stop capture; log and continue. The data_process
-                                    // append updates a writer marker file to signal active writing so the
-                                    // processor will avoid files that are still being appended to.
-                                    let ts = Utc::now();
-                                    if let Err(err) = backup.lock().await.append(&raw, ts).await {
-                                        tracing::error!(service = %supervisor_name, error = %err, "backup write failed");
-                                    }
-                                    if let Err(err) = data_process.lock().await.append(&raw, ts).await {
-                                        tracing::error!(service = %supervisor_name, error = %err, "data process write failed");
-                                    }
-
-                                    match parser::Frame::from_line(&raw) {
-                                        Ok(frame) => {
-                                            metrics.record_frame();
-                                            if let Err(err) = persistence.append(&frame).await {
-                                                metrics.record_persistence_error();
-                                                tracing::error!(
-                                                    service = %supervisor_name,
-                                                    error = %err,
-                                                    "persister failed"
-                                                );
-                                            }
-                                        }
-                                        Err(err) => {
-                                            metrics.record_parse_error();
-                                            tracing::warn!(
-                                                service = %supervisor_name,
-                                                error = %err,
-                                                frame = %raw,
-                                                "frame rejected"
-                                            );
-                                        }
+                                    reconnect_backoff = RECONNECT_INITIAL_BACKOFF;
+                                    if !inflight.push(raw) {
+                                        metrics.record_line_dropped();
+                                        tracing::debug!(
+                                            service = %supervisor_name,
+                                            inflight_bytes = inflight.bytes(),
+                                            "persistence behind; serial line dropped"
+                                        );
                                     }
+                                    continue;
                                 }
                                 Ok(None) => {
                                     tracing::warn!(service = %supervisor_name, "serial port closed");
-                                    sleep(Duration::from_secs(1)).await;
                                 }
                                 Err(err) => {
                                     tracing::warn!(
@@ -170,7 +1189,28 @@ impl Service {
                                         error = %err,
                                         "serial read failed"
                                     );
-                                    sleep(Duration::from_secs(1)).await;
+                                }
+                            }
+                            if !reader.reopenable() {
+                                // A plain file may still grow; keep reading it like before
+                                sleep(Duration::from_secs(1)).await;
+                                continue;
+                            }
+                            let reopened = reconnect_serial(
+                                &serial_port,
+                                *baud_rate,
+                                open_timeout,
+                                Duration::from_secs(*reconnect_max_backoff_seconds),
+                                &mut reconnect_backoff,
+                                &mut shutdown_rx,
+                                &supervisor_name,
+                            )
+                            .await;
+                            match reopened {
+                                Some(port) => reader = port.with_record_delimiter(*record_delimiter),
+                                None => {
+                                    tracing::info!(service = %supervisor_name, "shutdown requested");
+                                    break;
                                 }
                             }
                         }
@@ -183,9 +1223,10 @@ impl Service {
         let shutdown_signal = {
             let supervisor_name = supervisor_name.clone();
             let shutdown_tx = shutdown_tx.clone();
+            let trigger = self.shutdown_trigger.clone();
             async move {
-                signal::ctrl_c().await.ok();
-                tracing::info!(service = %supervisor_name, "ctrl-c received, requesting shutdown");
+                let signal = wait_for_shutdown_signal(trigger).await;
+                tracing::info!(service = %supervisor_name, signal, "shutdown signal received, requesting shutdown");
                 shutdown_tx.send(()).ok();
             }
         };
@@ -196,28 +1237,23 @@ impl Service {
         };
 
         shutdown_tx.send(()).ok();
+        persist_handle.await.ok();
         health_handle.await??;
+        if let Some(http_handle) = http_handle {
+            http_handle.await.ok();
+        }
         hb_handle.await.ok();
+        pending_handle.await.ok();
+        #[cfg(unix)]
+        if let Some(reload_handle) = reload_handle {
+            reload_handle.await.ok();
+        }
+        sinks.close(&self.config).await;
 
-        // Cleanup any leftover writer marker files in the data process folder
-        // This ensures `.writing` markers do not persist after the recorder shuts down.
-        if let Err(e) = async {
-            let dp = &*data_process_folder;
-            let mut rd = tokio::fs::read_dir(dp).await?;
-            while let Ok(Some(entry)) = rd.next_entry().await {
-                let path = entry.path();
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.ends_with(".writing") {
-                        let _ = tokio::fs::remove_file(&path).await;
-                        tracing::info!(marker = %name, folder = %dp, "removed leftover writing marker");
-                    }
-                }
+        if self.config.write_manifest {
+            if let Err(e) = self.files.write(&self.config.data_directory) {
+                tracing::warn!(error = %e, "failed to write run manifest");
             }
-            Ok::<(), anyhow::Error>(())
-        }
-        .await
-        {
-            tracing::warn!(error = %e, "failed to cleanup leftover writing markers");
         }
 
         worker_result
@@ -229,16 +1265,33 @@ impl Service {
         let (shutdown_tx, shutdown_rx) = watch::channel(());
         let supervisor_name = Arc::new(service_name.clone());
 
+        // The probe answers to the configured idle threshold; the log heartbeat keeps its fixed
+        // minute and raises no webhook alerts for processing.
+        let (_probe_config_tx, probe_config) = watch::channel(Arc::new(self.config.clone()));
+        let http_handle = match &self.config.metrics_listen_addr {
+            Some(address) => Some(tokio::spawn(http::serve_health(
+                http::bind(address).await?,
+                supervisor_name.clone(),
+                self.metrics.clone(),
+                probe_config,
+                shutdown_rx.clone(),
+            ))),
+            None => None,
+        };
+        let (_health_config_tx, health_config) = watch::channel(Arc::new(AppConfig {
+            idle_threshold_seconds: 60,
+            alert_webhook: None,
+            ..self.config.clone()
+        }));
         let health_handle = tokio::spawn(metrics::monitor_health(
             supervisor_name.clone(),
-            Arc::new(metrics::Metrics::new()),
+            self.metrics.clone(),
             shutdown_rx.clone(),
-            Duration::from_secs(60),
-            None,
+            health_config,
         ));
 
         // Heartbeat file for supervisor to monitor liveness
-        let tmp_dir = "./deployment/tmp".to_string();
+        let tmp_dir = self.tmp_dir.to_string_lossy().to_string();
         fs::create_dir_all(&tmp_dir).await.ok();
         let hb_path = format!("{}/adcp_{}_hb", tmp_dir, service_name.replace(' ', "_"));
         let mut hb_shutdown = shutdown_rx.clone();
@@ -257,25 +1310,47 @@ impl Service {
         });
 
         let cfg = Arc::new(self.config.clone());
-        let processing_handle = tokio::spawn({
-            let cfg = cfg.clone();
-            async move { processing::run_processing_loop(cfg, shutdown_rx).await }
-        });
+        let mut processing_handle = tokio::spawn(processing::run_processing_session(
+            cfg.clone(),
+            shutdown_rx,
+            self.metrics.clone(),
+            self.files.clone(),
+        ));
 
-        // Wait for ctrl-c
-        signal::ctrl_c().await.ok();
-        tracing::info!(service = %supervisor_name, "ctrl-c received, requesting shutdown");
-        shutdown_tx.send(()).ok();
+        // Wait for ctrl-c, SIGTERM or a shutdown request, unless the loop finishes first
+        // (a `run_once` pass, or an escalated folder failure)
+        let res = tokio::select! {
+            signal = wait_for_shutdown_signal(self.shutdown_trigger.clone()) => {
+                tracing::info!(service = %supervisor_name, signal, "shutdown signal received, requesting shutdown");
+                shutdown_tx.send(()).ok();
+                processing_handle.await?
+            }
+            res = &mut processing_handle => {
+                tracing::info!(service = %supervisor_name, "processing loop finished");
+                shutdown_tx.send(()).ok();
+                res?
+            }
+        };
 
         // Wait for tasks
-        let res = processing_handle.await?;
         health_handle.await??;
+        if let Some(http_handle) = http_handle {
+            http_handle.await.ok();
+        }
         hb_handle.await.ok();
         res
     }
 
     async fn run_orchestrator(&self) -> Result<()> {
-        let tmp_dir = "./deployment/tmp".to_string();
+        let child_binary = self.config.child_binary_path.clone();
+        // Fail before creating the FIFO or any child config, with a hint on how to fix it
+        if !child_binary_exists(Path::new(&child_binary)) {
+            anyhow::bail!(
+                "orchestrator child binary {child_binary} does not exist; build it with \
+                 `cargo build --release` or set `child_binary_path` to the installed adcp binary"
+            );
+        }
+        let tmp_dir = self.tmp_dir.to_string_lossy().to_string();
         fs::create_dir_all(&tmp_dir).await.ok();
         let fifo_path = format!("{}/adcp_fifo", tmp_dir);
         // Create FIFO (Unix only)
@@ -298,7 +1373,7 @@ impl Service {
         let simulator_config = format!("service_name = \"adcp-simulator\"\nmode = \"Simulator\"\nserial_port = \"{}\"\nsample_file = \"tests/sample.data\"\n", fifo_path);
         let simulator_cfg_path = format!("{}/simulator.toml", tmp_dir);
         fs::write(&simulator_cfg_path, simulator_config).await?;
-        let simulator_proc = process::Command::new("./target/release/adcp")
+        let simulator_proc = process::Command::new(&child_binary)
             .arg(&simulator_cfg_path)
             .spawn()
             .context("failed to spawn simulator")?;
@@ -318,7 +1393,7 @@ impl Service {
         fs::create_dir_all(&self.config.processed_folder).await.ok();
         fs::create_dir_all(&self.config.data_directory).await.ok();
 
-        let recorder_proc = process::Command::new("./target/release/adcp")
+        let recorder_proc = process::Command::new(&child_binary)
             .arg(&recorder_cfg_path)
             .spawn()
             .context("failed to spawn recorder")?;
@@ -333,7 +1408,7 @@ impl Service {
         );
         let processor_cfg_path = format!("{}/processor.toml", tmp_dir);
         fs::write(&processor_cfg_path, processor_config).await?;
-        let processor_proc = process::Command::new("./target/release/adcp")
+        let processor_proc = process::Command::new(&child_binary)
             .arg(&processor_cfg_path)
             .spawn()
             .context("failed to spawn processor")?;
@@ -342,9 +1417,9 @@ impl Service {
         use std::sync::Arc as StdArc;
 
         // Wrap children in Arc<Mutex<>> so the watchdog can restart them
-        let sim_cmd = ("./target/release/adcp".to_string(), simulator_cfg_path.to_string());
-        let rec_cmd = ("./target/release/adcp".to_string(), recorder_cfg_path.to_string());
-        let proc_cmd = ("./target/release/adcp".to_string(), processor_cfg_path.to_string());
+        let sim_cmd = (child_binary.clone(), simulator_cfg_path.to_string());
+        let rec_cmd = (child_binary.clone(), recorder_cfg_path.to_string());
+        let proc_cmd = (child_binary.clone(), processor_cfg_path.to_string());
 
         let sim_child = StdArc::new(TokioMutex::new(Some(simulator_proc)));
         let rec_child = StdArc::new(TokioMutex::new(Some(recorder_proc)));
@@ -362,6 +1437,8 @@ impl Service {
         // Compute a safer threshold for considering a child heartbeat stale.
         // Use 3x the configured `file_stability_seconds`, but at least 10s.
         let threshold_secs = std::cmp::max(10u64, self.config.file_stability_seconds.saturating_mul(3));
+        let max_restarts = self.config.watchdog_max_restarts;
+        let restarts: [StdArc<std::sync::atomic::AtomicU32>; 3] = Default::default();
         let watchdog = tokio::spawn(async move {
             let mut ticker = interval(StdDuration::from_secs(2));
             loop {
@@ -369,7 +1446,7 @@ impl Service {
                 let threshold = StdDuration::from_secs(threshold_secs);
                 let now = std::time::SystemTime::now();
 
-                let check_and_restart = |hb: &str, cmd: &(String,String), child_arc: StdArc<TokioMutex<Option<process::Child>>>| {
+                let check_and_restart = |hb: &str, cmd: &(String,String), child_arc: StdArc<TokioMutex<Option<process::Child>>>, restarts: StdArc<std::sync::atomic::AtomicU32>| {
                     let hb = hb.to_string();
                     let cmd = cmd.clone();
                     let child_arc = child_arc.clone();
@@ -381,6 +1458,19 @@ impl Service {
                             },
                             Err(_) => true,
                         };
+                        if stale && max_restarts > 0 {
+                            use std::sync::atomic::Ordering;
+                            let done = restarts.load(Ordering::Relaxed);
+                            if done >= max_restarts {
+                                // Log once, then leave the job down: restarting a misconfigured job only loops
+                                if done == max_restarts {
+                                    restarts.fetch_add(1, Ordering::Relaxed);
+                                    tracing::error!(heartbeat = %hb, cmd = %cmd.1, restarts = done, "job keeps failing; giving up on restarts");
+                                }
+                                return;
+                            }
+                            restarts.fetch_add(1, Ordering::Relaxed);
+                        }
                         if stale {
                             tracing::warn!(heartbeat = %hb, "heartbeat stale — restarting job");
                             // kill existing
@@ -404,16 +1494,16 @@ impl Service {
 
                 // Run checks concurrently
                 let _ = tokio::join!(
-                    check_and_restart(&sim_hb, &sim_cmd, sim_child_mon.clone()),
-                    check_and_restart(&rec_hb, &rec_cmd, rec_child_mon.clone()),
-                    check_and_restart(&proc_hb, &proc_cmd, proc_child_mon.clone()),
+                    check_and_restart(&sim_hb, &sim_cmd, sim_child_mon.clone(), restarts[0].clone()),
+                    check_and_restart(&rec_hb, &rec_cmd, rec_child_mon.clone(), restarts[1].clone()),
+                    check_and_restart(&proc_hb, &proc_cmd, proc_child_mon.clone(), restarts[2].clone()),
                 );
             }
         });
 
-        // Wait for ctrl-c
-        signal::ctrl_c().await.ok();
-        tracing::info!("orchestrator shutting down");
+        // Wait for ctrl-c, SIGTERM or a shutdown request
+        let signal = wait_for_shutdown_signal(self.shutdown_trigger.clone()).await;
+        tracing::info!(signal, "orchestrator shutting down");
 
         // Stop the watchdog first so it does not restart children while we shut them down
         watchdog.abort();
@@ -434,7 +1524,7 @@ impl Service {
         }
 
         // Cleanup any leftover child pid files created by children (best-effort)
-        if let Ok(rd) = std::fs::read_dir("./deployment/tmp") {
+        if let Ok(rd) = std::fs::read_dir(DEFAULT_TMP_DIR) {
             for entry in rd.flatten() {
                 if let Some(name) = entry.file_name().to_str() {
                     if name.starts_with("adcp-") && name.ends_with(".pid") {
@@ -498,8 +1588,17 @@ impl Service {
     async fn run_simulator(&self) -> Result<()> {
         let sample_file = self.config.sample_file.as_ref().ok_or_else(|| anyhow::anyhow!("sample_file required for simulator mode"))?;
         let fifo_path = self.config.serial_port.as_ref().ok_or_else(|| anyhow::anyhow!("serial_port required for simulator mode"))?; // Use serial_port as the output FIFO
+        // A missing sample is a configuration error: fail before the first heartbeat so the
+        // watchdog gives up after `watchdog_max_restarts` instead of looping on it
+        let sample_data = match fs::read_to_string(sample_file).await {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("sample_file {sample_file} does not exist")
+            }
+            Err(err) => return Err(err).with_context(|| format!("failed to read sample_file {sample_file}")),
+        };
         // Ensure tmp dir exists and start heartbeat for simulator
-        let tmp_dir = "./deployment/tmp".to_string();
+        let tmp_dir = self.tmp_dir.to_string_lossy().to_string();
         fs::create_dir_all(&tmp_dir).await.ok();
         let hb_name = format!("{}/adcp_{}_hb", tmp_dir, self.config.service_name.replace(' ', "_"));
         use tokio::time::interval as tokio_interval;
@@ -515,26 +1614,813 @@ impl Service {
             }
         });
 
-        let sample_data = fs::read_to_string(sample_file).await?;
-        let lines: Vec<&str> = sample_data.lines().collect();
+        if sample_data.trim().is_empty() {
+            // Nothing to stream, but exiting would read as a crash; stay alive until stopped
+            tracing::warn!(sample_file = %sample_file, "sample_file is empty; simulator idle until shutdown");
+            let signal = wait_for_shutdown_signal(self.shutdown_trigger.clone()).await;
+            tracing::info!(signal, "simulator shutting down");
+            hb_handle.abort();
+            hb_handle.await.ok();
+            return Ok(());
+        }
+        let mut lines: Vec<&str> = sample_data.lines().collect();
+        if self.config.simulator_filter_invalid {
+            let (valid, rejected) = simulator::valid_lines(&lines, &self.config.dialect);
+            tracing::info!(forwarded = valid.len(), rejected, "simulator filtering invalid lines");
+            lines = valid;
+        }
 
         let mut file = fs::OpenOptions::new()
             .write(true)
-            .create(true) // Ensure file is created if not already there (especially for Windows "FIFO" simulation)
+            .create(true)
+            .truncate(false) // Ensure file is created if not already there (especially for Windows "FIFO" simulation)
             .open(fifo_path)
             .await
             .with_context(|| format!("failed to open FIFO {}", fifo_path))?;
 
-        for line in &lines {
-            if line.trim().is_empty() { continue; }
-            file.write_all(line.as_bytes()).await?;
-            file.write_all(b"\n").await?;
-            file.flush().await?;
-            sleep(Duration::from_millis(100)).await; // Simulate real-time data
-        }
+        // Pace lines by their capture timestamps to simulate real-time data
+        simulator::stream_capture(&lines, &mut file, self.config.replay_speed, &self.config.dialect).await?;
         // Stop heartbeat and return
         hb_handle.abort();
         hb_handle.await.ok();
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn supervise_respawns_a_panicked_heartbeat() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let starts = Arc::new(AtomicUsize::new(0));
+        let (beat_tx, mut beat_rx) = tokio::sync::mpsc::unbounded_channel();
+        let handle = supervise("heartbeat", shutdown_rx.clone(), {
+            let starts = starts.clone();
+            move || {
+                let attempt = starts.fetch_add(1, Ordering::SeqCst);
+                let beat_tx = beat_tx.clone();
+                let mut shutdown = shutdown_rx.clone();
+                async move {
+                    if attempt == 0 {
+                        panic!("heartbeat task blew up");
+                    }
+                    let mut ticker = interval(Duration::from_millis(10));
+                    loop {
+                        tokio::select! {
+                            _ = shutdown.changed() => break,
+                            _ = ticker.tick() => { beat_tx.send(attempt).ok(); }
+                        }
+                    }
+                }
+            }
+        });
+
+        for _ in 0..3 {
+            let beat = tokio::time::timeout(Duration::from_secs(5), beat_rx.recv())
+                .await
+                .expect("heartbeats resume after the panic");
+            assert_eq!(beat, Some(1));
+        }
+        shutdown_tx.send(()).expect("send shutdown");
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("supervisor stops on shutdown")
+            .expect("no panic after respawn");
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+    }
+
+    fn simulator_config(tmp: &std::path::Path, name: &str, sample: &std::path::Path) -> AppConfig {
+        let mut config = sinks_config(tmp);
+        config.service_name = name.to_string();
+        config.mode = ServiceMode::Simulator;
+        config.serial_port = Some(tmp.join("fifo").to_string_lossy().to_string());
+        config.sample_file = Some(sample.to_string_lossy().to_string());
+        config.file_stability_seconds = 1;
+        config
+    }
+
+    #[tokio::test]
+    async fn simulator_fails_fast_on_missing_sample() {
+        let tmp = tempdir().expect("temp dir");
+        let config = simulator_config(tmp.path(), "missing-sample-sim", &tmp.path().join("absent.data"));
+        let err = tokio::time::timeout(Duration::from_secs(5), Service::new(config).with_tmp_dir(tmp.path()).run())
+            .await
+            .expect("fails immediately")
+            .expect_err("missing sample is an error");
+        assert!(err.to_string().contains("does not exist"), "{err}");
+        assert!(
+            !tmp.path().join("adcp_missing-sample-sim_hb").exists(),
+            "no heartbeat for a simulator that cannot run"
+        );
+    }
+
+    #[tokio::test]
+    async fn orchestrator_fails_fast_without_the_child_binary() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.mode = ServiceMode::Orchestrator;
+        let missing = tmp.path().join("bin").join("adcp");
+        config.child_binary_path = missing.to_string_lossy().to_string();
+        let err = tokio::time::timeout(Duration::from_secs(5), Service::new(config).with_tmp_dir(tmp.path()).run())
+            .await
+            .expect("fails immediately")
+            .expect_err("missing child binary is an error");
+        let message = err.to_string();
+        assert!(message.contains(&*missing.to_string_lossy()), "{message}");
+        assert!(message.contains("cargo build --release"), "{message}");
+        assert!(message.contains("child_binary_path"), "{message}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn child_binary_bare_names_resolve_on_path() {
+        assert!(child_binary_exists(Path::new("sh")), "sh is on PATH");
+        assert!(!child_binary_exists(Path::new("adcp-no-such-binary")));
+        assert!(!child_binary_exists(Path::new("./sh")), "a relative path is not searched");
+    }
+
+    #[tokio::test]
+    async fn simulator_keeps_heartbeat_on_empty_sample() {
+        let tmp = tempdir().expect("temp dir");
+        let sample = tmp.path().join("empty.data");
+        std::fs::write(&sample, "\n").expect("write empty sample");
+        let config = simulator_config(tmp.path(), "empty-sample-sim", &sample);
+        let hb = tmp.path().join("adcp_empty-sample-sim_hb");
+        let (stop_tx, stop_rx) = watch::channel(());
+        let running = tokio::spawn(Service::new(config).with_tmp_dir(tmp.path()).with_shutdown_trigger(stop_rx).run());
+
+        sleep(Duration::from_millis(1500)).await;
+        assert!(!running.is_finished(), "simulator stays up with nothing to stream");
+        let beat = std::fs::metadata(&hb).and_then(|m| m.modified()).expect("heartbeat written");
+        assert!(beat.elapsed().unwrap_or_default() < Duration::from_secs(2), "heartbeat is fresh");
+
+        stop_tx.send(()).expect("request shutdown");
+        tokio::time::timeout(Duration::from_secs(5), running)
+            .await
+            .expect("stops on request")
+            .expect("join")
+            .expect("clean exit");
+    }
+
+    #[tokio::test]
+    async fn processing_health_probe_uses_the_configured_idle_threshold() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        async fn status(address: &str) -> String {
+            let mut stream = tokio::net::TcpStream::connect(address).await.expect("connect");
+            stream.write_all(b"GET /health HTTP/1.1\r\n\r\n").await.expect("send request");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.expect("read response");
+            response.lines().next().expect("status line").to_string()
+        }
+
+        let tmp = tempdir().expect("temp dir");
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("free port")
+            .to_string();
+        let mut config = sinks_config(tmp.path());
+        config.service_name = "processing-probe".to_string();
+        config.mode = ServiceMode::Processing;
+        config.idle_threshold_seconds = 1;
+        config.metrics_listen_addr = Some(address.clone());
+        let service = Service::new(config).with_tmp_dir(tmp.path());
+        let metrics = service.metrics.clone();
+        let (stop_tx, stop_rx) = watch::channel(());
+        let running = tokio::spawn(service.with_shutdown_trigger(stop_rx).run());
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while tokio::net::TcpStream::connect(&address).await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "probe never came up");
+            sleep(Duration::from_millis(20)).await;
+        }
+        metrics.record_frame();
+        assert_eq!(status(&address).await, "HTTP/1.1 200 OK");
+        sleep(Duration::from_millis(1200)).await;
+        assert_eq!(status(&address).await, "HTTP/1.1 503 Service Unavailable");
+
+        stop_tx.send(()).expect("request shutdown");
+        tokio::time::timeout(Duration::from_secs(5), running)
+            .await
+            .expect("stops on request")
+            .expect("join")
+            .expect("clean exit");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sighup_reloads_the_idle_threshold_and_survives_a_bad_file() {
+        use tokio::signal::unix::{signal as unix_signal, SignalKind};
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("adcp.toml");
+        let with_threshold = |threshold: &str| {
+            format!("service_name = \"sighup-test\"\nserial_port = \"/dev/ttyUSB0\"\nidle_threshold_seconds = {threshold}\n")
+        };
+        std::fs::write(&path, with_threshold("3600")).expect("write config");
+        let (live, mut config_rx) = watch::channel(Arc::new(AppConfig::load(&path).expect("load config")));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        // Installed before signalling, so the SIGHUP cannot hit the default (terminating) action
+        let hangup = unix_signal(SignalKind::hangup()).expect("install SIGHUP handler");
+        let reload = tokio::spawn(reload_on_hangup(hangup, path.clone(), live, shutdown_rx));
+
+        std::fs::write(&path, with_threshold("5")).expect("rewrite config");
+        unsafe { libc::kill(libc::getpid(), libc::SIGHUP) };
+        tokio::time::timeout(Duration::from_secs(5), config_rx.changed())
+            .await
+            .expect("config reloaded after SIGHUP")
+            .expect("live config open");
+        assert_eq!(config_rx.borrow_and_update().idle_threshold_seconds, 5);
+
+        std::fs::write(&path, with_threshold("\"soon\"")).expect("break config");
+        unsafe { libc::kill(libc::getpid(), libc::SIGHUP) };
+        sleep(Duration::from_millis(500)).await;
+        assert!(!config_rx.has_changed().expect("live config open"), "bad file is not applied");
+        assert_eq!(config_rx.borrow().idle_threshold_seconds, 5);
+
+        shutdown_tx.send(()).ok();
+        reload.await.expect("join reload task");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn recorder_reconnects_when_the_fifo_is_recreated() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let tmp = tempdir().expect("temp dir");
+        let fifo = tmp.path().join("adcp.fifo");
+        let mkfifo = || {
+            let c_path = std::ffi::CString::new(fifo.as_os_str().as_bytes()).expect("fifo path");
+            // SAFETY: `c_path` is a valid NUL-terminated path.
+            assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0, "mkfifo");
+        };
+        mkfifo();
+        let mut config = sinks_config(tmp.path());
+        config.service_name = "reconnect-test".to_string();
+        config.serial_port = Some(fifo.to_string_lossy().to_string());
+        config.fifo_open_timeout_seconds = 1;
+        config.reconnect_max_backoff_seconds = 1;
+        let backup_folder = std::path::PathBuf::from(&config.backup_folder);
+        let (stop_tx, stop_rx) = watch::channel(());
+
+        let first_writer = {
+            let fifo = fifo.clone();
+            tokio::task::spawn_blocking(move || std::fs::write(fifo, "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n"))
+        };
+        let running = tokio::spawn(Service::new(config).with_tmp_dir(tmp.path()).with_shutdown_trigger(stop_rx).run());
+        first_writer.await.expect("join").expect("first writer");
+
+        // The simulator goes away and a new one brings up a fresh FIFO
+        std::fs::remove_file(&fifo).expect("remove fifo");
+        mkfifo();
+        let second_writer = {
+            let fifo = fifo.clone();
+            tokio::task::spawn_blocking(move || std::fs::write(fifo, "$PNORI,4,Signature1000_100298,4,21,0.20,1.00,0*41\n"))
+        };
+        second_writer.await.expect("join").expect("second writer");
+
+        let captured = async {
+            loop {
+                let mut raw = String::new();
+                for entry in std::fs::read_dir(&backup_folder).into_iter().flatten().flatten() {
+                    raw.push_str(&std::fs::read_to_string(entry.path()).unwrap_or_default());
+                }
+                if raw.contains("Signature1000_100298") {
+                    return raw;
+                }
+                sleep(Duration::from_millis(50)).await;
+            }
+        };
+        let raw = tokio::time::timeout(Duration::from_secs(10), captured)
+            .await
+            .expect("recorder resumes on the recreated FIFO");
+        assert!(raw.contains("Signature1000_100297"), "{raw}");
+
+        stop_tx.send(()).expect("request shutdown");
+        tokio::time::timeout(Duration::from_secs(5), running)
+            .await
+            .expect("stops promptly while reconnecting")
+            .expect("join")
+            .expect("clean exit");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconnect_backoff_keeps_growing_while_the_port_closes_at_once() {
+        let tmp = tempdir().expect("temp dir");
+        let port = tmp.path().join("capture.data");
+        std::fs::write(&port, "").expect("write empty capture");
+        let port = port.to_string_lossy().to_string();
+        let (_stop_tx, mut stop_rx) = watch::channel(());
+        let mut backoff = RECONNECT_INITIAL_BACKOFF;
+
+        // Each reopen succeeds but yields no line, so the caller never resets the backoff
+        let started = tokio::time::Instant::now();
+        for _ in 0..3 {
+            reconnect_serial(
+                &port,
+                115200,
+                Duration::from_secs(1),
+                Duration::from_secs(3),
+                &mut backoff,
+                &mut stop_rx,
+                "backoff-test",
+            )
+            .await
+            .expect("reopened");
+        }
+        assert!(started.elapsed() >= Duration::from_secs(6), "waited 1s, 2s and 3s");
+        assert_eq!(backoff, Duration::from_secs(3), "capped at the maximum");
+    }
+
+    fn sinks_config(tmp: &std::path::Path) -> AppConfig {
+        AppConfig {
+            service_name: "sinks-test".to_string(),
+            data_directory: tmp.join("data").to_string_lossy().to_string(),
+            serial_port: Some("/dev/null".to_string()),
+            backup_folder: tmp.join("backup").to_string_lossy().to_string(),
+            data_process_folder: tmp.join("to_process").to_string_lossy().to_string(),
+            processed_folder: tmp.join("processed").to_string_lossy().to_string(),
+            processing_concurrency: 1,
+            ..Default::default()
+        }
+    }
+
+    async fn recording_sinks(config: &AppConfig) -> (RecordingSinks, Metrics) {
+        (RecordingSinks::new(config).await.expect("prepare sinks"), Metrics::new())
+    }
+
+    #[tokio::test]
+    async fn persistence_applies_a_reloaded_config_from_the_next_line() {
+        let tmp = tempdir().expect("temp dir");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+        let (sinks, metrics) = (Arc::new(sinks), Arc::new(metrics));
+        let inflight = Arc::new(InflightLines::new(config.max_inflight_bytes));
+        let (live, config_rx) = watch::channel(Arc::new(config.clone()));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let persist = tokio::spawn(persist_lines(
+            sinks,
+            inflight.clone(),
+            config_rx,
+            metrics.clone(),
+            shutdown_rx,
+        ));
+        let signature = "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41";
+
+        inflight.push(signature.to_string());
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while metrics.snapshot().frames < 1 {
+            assert!(tokio::time::Instant::now() < deadline, "first line never recorded");
+            sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(metrics.snapshot().instrument_mismatches, 0);
+
+        let reloaded = AppConfig { expected_instrument_type: Some(2), ..config };
+        reconfig::apply_update(&live, &reloaded, reconfig::RUNTIME_FIELDS).expect("apply reload");
+        inflight.push(signature.to_string());
+        shutdown_tx.send(()).expect("request shutdown");
+        persist.await.expect("persistence drains and stops");
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.frames, 2);
+        assert_eq!(snapshot.instrument_mismatches, 1, "reloaded expected_instrument_type applied");
+    }
+
+    #[tokio::test]
+    async fn counts_backup_errors_separately_from_persistence() {
+        let tmp = tempdir().expect("temp dir");
+        let backup_dir = tmp.path().join("backup");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        // Replace the backup folder with a plain file so every backup write fails.
+        std::fs::remove_dir_all(&backup_dir).expect("remove backup dir");
+        std::fs::write(&backup_dir, b"").expect("block backup dir");
+
+        sinks
+            .record_line(
+                "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+                &config,
+                &metrics,
+            )
+            .await;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.backup_errors, 1);
+        assert_eq!(snapshot.data_process_errors, 0);
+        assert_eq!(snapshot.persistence_errors, 0);
+        assert_eq!(snapshot.frames, 1);
+        assert!(tmp.path().join("data").join("adcp-2026-01-05.log").exists());
+    }
+
+    #[tokio::test]
+    async fn attached_sensor_cache_follows_recorded_frames() {
+        let tmp = tempdir().expect("temp dir");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+        let sensors = Arc::new(telemetry::SensorCache::new());
+        let sinks = sinks.with_sensor_cache(sensors.clone());
+
+        sinks
+            .record_line(
+                "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+                &config,
+                &metrics,
+            )
+            .await;
+
+        let latest = sensors.latest().expect("sensor reading cached");
+        assert_eq!(latest.heading_deg, Some(275.4));
+        assert_eq!(latest.temperature_c, Some(24.02));
+    }
+
+    #[tokio::test]
+    async fn frames_outside_the_horizon_are_not_counted_as_recorded() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.max_frame_age_days = Some(1);
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        sinks
+            .record_line(
+                "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+                &config,
+                &metrics,
+            )
+            .await;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.out_of_horizon, 1);
+        assert_eq!(snapshot.frames, 0, "matches the replay's count");
+        assert!(!tmp.path().join("data").join("adcp-2026-01-05.log").exists());
+    }
+
+    #[tokio::test]
+    async fn counts_empty_lines_and_warns_on_burst() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.empty_line_threshold = 3;
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        for raw in ["", "   ", "\t", ""] {
+            sinks.record_line(raw, &config, &metrics).await;
+        }
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.empty_lines, 4);
+        assert_eq!(snapshot.empty_line_bursts, 1);
+        assert_eq!(snapshot.parse_errors, 0, "empty lines are not parse errors");
+        for folder in ["backup", "to_process"] {
+            let copied: usize = std::fs::read_dir(tmp.path().join(folder))
+                .expect("list raw copies")
+                .map(|entry| entry.expect("entry").path())
+                .filter(|path| path.extension().is_none_or(|ext| ext != "writing"))
+                .map(|path| std::fs::read_to_string(path).expect("read raw copy").lines().count())
+                .sum();
+            assert_eq!(copied, 4, "blank lines kept in {folder}");
+        }
+
+        // A real frame ends the run, so the next burst warns again.
+        sinks
+            .record_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", &config, &metrics)
+            .await;
+        for _ in 0..4 {
+            sinks.record_line("", &config, &metrics).await;
+        }
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.empty_lines, 8);
+        assert_eq!(snapshot.empty_line_bursts, 2);
+    }
+
+    #[tokio::test]
+    async fn unframed_burst_after_clean_data_counts_as_suspected_overrun() {
+        let tmp = tempdir().expect("temp dir");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        let clean = "$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23";
+        let torn = [
+            "0,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23",
+            "26,220801,2,0.56,-0.80,-1.99,-1.33,0.98,305.2,C",
+            ".2,C,80,88,67,78,13,17,10,18*23",
+            "80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23",
+        ];
+        for _ in 0..3 {
+            sinks.record_line(clean, &config, &metrics).await;
+        }
+        for line in &torn[..2] {
+            sinks.record_line(line, &config, &metrics).await;
+        }
+        assert_eq!(metrics.snapshot().suspected_overruns, 0, "two stray lines are not a burst");
+        for line in &torn[2..] {
+            sinks.record_line(line, &config, &metrics).await;
+        }
+        assert_eq!(metrics.snapshot().suspected_overruns, 1, "one burst counts once");
+
+        // Clean framing re-arms the check for the next burst
+        sinks.record_line(clean, &config, &metrics).await;
+        for line in torn {
+            sinks.record_line(line, &config, &metrics).await;
+        }
+        assert_eq!(metrics.snapshot().suspected_overruns, 2);
+    }
+
+    #[tokio::test]
+    async fn persists_ingested_at_alongside_recorded_at() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.include_ingested_at = true;
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        sinks
+            .record_line(
+                "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+                &config,
+                &metrics,
+            )
+            .await;
+
+        let log = std::fs::read_to_string(tmp.path().join("data").join("adcp-2026-01-05.log"))
+            .expect("read log");
+        let persisted: serde_json::Value = serde_json::from_str(log.trim()).expect("json line");
+        let recorded_at = persisted["recorded_at"].as_str().expect("recorded_at");
+        let ingested_at = persisted["ingested_at"].as_str().expect("ingested_at");
+        assert_eq!(recorded_at, "2026-01-05T22:08:00Z");
+        assert_ne!(recorded_at, ingested_at);
+    }
+
+    #[tokio::test]
+    async fn corrects_velocities_for_measured_sound_speed() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.sound_speed_correction = true;
+        let (sinks, metrics) = recording_sinks(&config).await;
+        let current = "$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23";
+
+        // No sound speed known yet: persisted unchanged
+        sinks.record_line(current, &config, &metrics).await;
+        sinks
+            .record_line(
+                "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+                &config,
+                &metrics,
+            )
+            .await;
+        sinks.record_line(current, &config, &metrics).await;
+
+        let log = std::fs::read_to_string(tmp.path().join("data").join("adcp-2026-01-05.log"))
+            .expect("read log");
+        let frames: Vec<serde_json::Value> =
+            log.lines().map(|line| serde_json::from_str(line).expect("json line")).collect();
+        assert!(frames[0].get("sound_speed_ratio").is_none());
+        assert_eq!(frames[0]["payload"]["velocity_1_m_s"].as_f64(), Some(0.56));
+
+        let ratio = 1532.0 / 1500.0;
+        let corrected = &frames[2];
+        assert!((corrected["sound_speed_ratio"].as_f64().expect("ratio") - ratio).abs() < 1e-6);
+        let current = &corrected["payload"];
+        for (field, raw) in [("velocity_1_m_s", 0.56), ("velocity_3_m_s", -1.99), ("speed_m_s", 0.98)] {
+            let value = current[field].as_f64().expect(field);
+            assert!((value - raw * ratio).abs() < 1e-5, "{field}: {value}");
+        }
+        assert_eq!(current["direction_deg"].as_f64(), Some(305.2), "direction is unaffected");
+    }
+
+    #[tokio::test]
+    async fn persists_rejected_lines_when_enabled() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.persist_rejected = true;
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        sinks.record_line("@@garbage from the instrument@@", &config, &metrics).await;
+
+        assert_eq!(metrics.snapshot().parse_errors, 1);
+        let log = std::fs::read_to_string(
+            tmp.path()
+                .join("data")
+                .join(format!("rejected-{}.log", Utc::now().format("%Y-%m-%d"))),
+        )
+        .expect("read rejected log");
+        assert_eq!(log, "@@garbage from the instrument@@\n");
+    }
+    #[tokio::test]
+    async fn flags_frozen_instrument_clock() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.frozen_clock_threshold = 5;
+        let (sinks, metrics) = recording_sinks(&config).await;
+        let frozen = "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77";
+
+        for _ in 0..5 {
+            sinks.record_line(frozen, &config, &metrics).await;
+        }
+        assert!(!metrics.snapshot().clock_frozen, "threshold counts repeats after the first");
+        sinks.record_line(frozen, &config, &metrics).await;
+        assert!(metrics.snapshot().clock_frozen);
+
+        sinks
+            .record_line(
+                "$PNORS,010526,220802,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*75",
+                &config,
+                &metrics,
+            )
+            .await;
+        assert!(!metrics.snapshot().clock_frozen, "cleared once the clock advances");
+    }
+
+    #[tokio::test]
+    async fn inflight_budget_drops_large_lines_without_stalling() {
+        let inflight = Arc::new(InflightLines::new(1000));
+        let metrics = Metrics::new();
+        let line = "X".repeat(300);
+
+        // Nobody is draining: pushes must return immediately and drop past the byte budget
+        let pushed = tokio::time::timeout(Duration::from_secs(1), async {
+            for _ in 0..10 {
+                if !inflight.push(line.clone()) {
+                    metrics.record_line_dropped();
+                }
+            }
+        })
+        .await;
+        assert!(pushed.is_ok(), "reader stalled on a full budget");
+        assert_eq!(metrics.snapshot().lines_dropped, 7);
+        assert_eq!(inflight.bytes(), 900);
+
+        // Draining frees the budget again
+        assert_eq!(inflight.next().await.len(), 300);
+        assert!(inflight.push(line.clone()));
+        let mut drained = 0;
+        while inflight.try_next().is_some() {
+            drained += 1;
+        }
+        assert_eq!(drained, 3);
+        assert_eq!(inflight.bytes(), 0);
+
+        // A single line over the whole budget still gets through an empty queue
+        assert!(inflight.push("Y".repeat(5000)));
+        assert!(!inflight.push("Z".to_string()));
+    }
+
+    #[tokio::test]
+    async fn summarizes_recurring_discarded_junk() {
+        let tmp = tempdir().expect("temp dir");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        for _ in 0..5 {
+            sinks
+                .record_line(
+                    "0,-1.99,-1.33$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41",
+                    &config,
+                    &metrics,
+                )
+                .await;
+        }
+        sinks
+            .record_line("@@$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", &config, &metrics)
+            .await;
+
+        let top = metrics.snapshot().top_junk;
+        assert_eq!(top[0], ("0,-1.99,-1.33".to_string(), 5));
+        assert_eq!(top[1], ("@@".to_string(), 1));
+    }
+
+    #[tokio::test]
+    async fn flags_degraded_range_when_few_cells_report() {
+        let tmp = tempdir().expect("temp dir");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+        sinks
+            .record_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", &config, &metrics)
+            .await;
+
+        // 21 cells configured, but every ensemble stops at cell 3
+        let ensembles = [
+            ["*23", "*20", "*21"],
+            ["*21", "*22", "*23"],
+            ["*27", "*24", "*25"],
+            ["*25", "*26", "*27"],
+        ];
+        for (time, checksums) in ["220800", "220802", "220804", "220806"].iter().zip(ensembles) {
+            for (cell, checksum) in (1..=3).zip(checksums) {
+                let line = format!(
+                    "$PNORC,010526,{time},{cell},0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18{checksum}"
+                );
+                sinks.record_line(&line, &config, &metrics).await;
+            }
+            // An ensemble is judged when the next one starts, so the third closes at 220806
+            assert_eq!(metrics.snapshot().range_degraded, *time == "220806");
+        }
+    }
+
+    #[tokio::test]
+    async fn warns_when_no_config_frame_arrives_within_grace() {
+        let tmp = tempdir().expect("temp dir");
+        let config = sinks_config(tmp.path());
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        // Only currents: the instrument was already pinging when the recorder connected
+        for (time, missing) in [("220800", false), ("220830", false), ("220859", false), ("220900", true)] {
+            let body = format!("PNORC,010526,{time},1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18");
+            let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            sinks.record_line(&format!("${body}*{checksum:02X}"), &config, &metrics).await;
+            assert_eq!(metrics.snapshot().config_frame_missing, missing, "at {time}");
+        }
+
+        sinks
+            .record_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41", &config, &metrics)
+            .await;
+        assert!(!metrics.snapshot().config_frame_missing);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn full_data_directory_switches_to_fallback() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        let fallback_dir = tmp.path().join("fallback");
+        config.fallback_data_directory = Some(fallback_dir.to_string_lossy().to_string());
+        let (sinks, metrics) = recording_sinks(&config).await;
+        // Every write to the day's primary log fails with ENOSPC
+        std::os::unix::fs::symlink("/dev/full", tmp.path().join("data").join("adcp-2026-01-05.log"))
+            .expect("link primary log to /dev/full");
+
+        for cell in 1..=5 {
+            let body = format!("PNORC,010526,220800,{cell},0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18");
+            let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            sinks.record_line(&format!("${body}*{checksum:02X}"), &config, &metrics).await;
+        }
+
+        // The first two failures are dropped; the third triggers the switch and is retried
+        assert_eq!(metrics.snapshot().persistence_errors, 2);
+        let log = std::fs::read_to_string(fallback_dir.join("adcp-2026-01-05.log")).expect("fallback log");
+        let cells: Vec<u16> = log
+            .lines()
+            .map(|line| match serde_json::from_str::<parser::Frame>(line).expect("frame json").payload {
+                parser::Payload::Current(current) => current.cell_number,
+                other => panic!("unexpected payload {other:?}"),
+            })
+            .collect();
+        assert_eq!(cells, [3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn pegged_beam_flags_amplitude_saturation() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.saturation_window = 4;
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        let lines = [
+            "$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23",
+            "$PNORC,010526,220802,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*21",
+            "$PNORC,010526,220804,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,255,88,67,78,13,17,10,18*1D",
+            "$PNORC,010526,220806,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,255,88,67,78,13,17,10,18*1F",
+            "$PNORC,010526,220808,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,255,88,67,78,13,17,10,18*11",
+        ];
+        for (idx, line) in lines.iter().enumerate() {
+            sinks.record_line(line, &config, &metrics).await;
+            // Half the window pegged is tolerated; the fifth sample pushes beam 1 past it
+            assert_eq!(metrics.snapshot().amplitude_saturated, idx == 4);
+        }
+        assert_eq!(metrics.snapshot().saturated_samples, [3, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn interleaved_heads_persist_to_their_own_folders() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config = sinks_config(tmp.path());
+        config.split_by_head_id = true;
+        let (sinks, metrics) = recording_sinks(&config).await;
+
+        let sensor = "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77";
+        let lines = [
+            sensor,
+            "$PNORI,4,HeadA_000001,4,21,0.20,1.00,0*61",
+            sensor,
+            "$PNORI,4,HeadB_000002,4,21,0.20,1.00,0*61",
+            sensor,
+            "$PNORI,4,HeadA_000001,4,21,0.20,1.00,0*61",
+            sensor,
+        ];
+        for line in lines {
+            sinks.record_line(line, &config, &metrics).await;
+        }
+        assert_eq!(metrics.snapshot().persistence_errors, 0);
+
+        let data = tmp.path().join("data");
+        let lines_in = |head: &str| {
+            std::fs::read_to_string(data.join(head).join("adcp-2026-01-05.log"))
+                .unwrap_or_else(|err| panic!("read log of {head}: {err}"))
+                .lines()
+                .count()
+        };
+        assert_eq!(lines_in("unassigned"), 1);
+        assert_eq!(lines_in("HeadA_000001"), 4);
+        assert_eq!(lines_in("HeadB_000002"), 2);
+        assert!(!data.join("adcp-2026-01-05.log").exists(), "split frames leaked into the shared log");
+    }
+}
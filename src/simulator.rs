@@ -1,7 +1,19 @@
-use crate::{metrics::Metrics, parser::Frame, persistence::Persistence, AppConfig};
+use crate::{
+    manifest::RunManifest, metrics::{self, Metrics, ParseHistogram}, parser::{DialectConfig, Frame, SentenceKind}, persistence::Persistence,
+    processing::EnuConversion, validation, AppConfig,
+};
 use anyhow::{Context, Result};
-use std::{path::Path, sync::Arc};
-use tokio::fs;
+use chrono::{DateTime, Utc};
+use std::{collections::{HashMap, VecDeque}, fmt, path::Path, sync::{atomic::{AtomicUsize, Ordering}, Arc}, time::Duration};
+use tokio::{
+    fs,
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
+    sync::watch,
+    time::sleep,
+};
+
+/// Pause before lines the capture gives no timing for, e.g. `$PNORI` headers.
+const UNTIMED_LINE_DELAY: Duration = Duration::from_millis(10);
 
 /// Result of a replay operation, containing metrics and any failures.
 #[derive(Debug, Default)]
@@ -9,49 +21,237 @@ pub struct ReplayResult {
     pub frames_processed: usize,
     pub parse_errors: usize,
     pub persistence_errors: usize,
+    /// Frames routed to the quarantine log because they violated a validation rule.
+    pub quarantined: usize,
+    /// Rejected lines and discarded chunks; stays empty when `ReplayOptions::failures_path`
+    /// streams them to a file instead.
     pub failures: Vec<String>,
+    /// Number of failures, whether kept in `failures` or written to the failures file.
+    pub failure_count: usize,
+    /// Frames skipped because their `sent_at` fell outside the configured age horizon.
+    pub out_of_horizon: usize,
+    /// Parse time per sentence type, filled when `profile_parsing` is on.
+    pub parse_timings: HashMap<SentenceKind, ParseHistogram>,
+    /// Replay stopped early because parse errors exceeded `ReplayOptions::max_errors`.
+    pub aborted: bool,
+    /// Replay stopped early because `ReplayOptions::stop` fired; the counts cover the frames
+    /// handled until then, every one of them completely written.
+    pub interrupted: bool,
+}
+
+impl fmt::Display for ReplayResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "frames: {}", self.frames_processed)?;
+        writeln!(f, "parse errors: {}", self.parse_errors)?;
+        writeln!(f, "persistence errors: {}", self.persistence_errors)?;
+        writeln!(f, "quarantined: {}", self.quarantined)?;
+        if self.out_of_horizon > 0 {
+            writeln!(f, "out of horizon: {}", self.out_of_horizon)?;
+        }
+        if !self.parse_timings.is_empty() {
+            writeln!(f, "parse timings: {}", metrics::parse_timing_summary(&self.parse_timings))?;
+        }
+        if self.aborted {
+            writeln!(f, "aborted: error budget exceeded")?;
+        }
+        if self.interrupted {
+            writeln!(f, "interrupted: partial replay")?;
+        }
+        Ok(())
+    }
+}
+
+/// Per-run replay knobs that are not part of the service configuration.
+#[derive(Debug, Default, Clone)]
+pub struct ReplayOptions {
+    /// Stop once more than this many lines failed to parse; `None` replays the whole file.
+    pub max_errors: Option<usize>,
+    /// Sentence types that are parsed and counted but not persisted, e.g. currents when only
+    /// sensor data is being reprocessed.
+    pub exclude_types: Vec<SentenceKind>,
+    /// Credits the dated logs written by the replay to a run manifest.
+    pub manifest: Option<Arc<RunManifest>>,
+    /// Once this fires (e.g. on ctrl-c) the replay finishes the frame in hand and returns
+    /// what it did so far instead of reading on.
+    pub stop: Option<watch::Receiver<()>>,
+    /// Write failures to this file, one per line, as they occur instead of collecting them
+    /// in `ReplayResult::failures`, so a pathologically corrupt capture cannot exhaust memory.
+    pub failures_path: Option<String>,
+    /// Record the capture's basename as `source_file` on every persisted frame, so a merged
+    /// reprocessing output still tells which archive each frame came from.
+    pub tag_source_file: bool,
+    /// Capture records an earlier, failed replay of the same file already got through; they
+    /// are read past without being persisted again.
+    pub resume_after: usize,
+    /// Kept at the number of capture records fully handled, so a replay that fails can be
+    /// resumed from there (see `resume_after`) instead of duplicating what it persisted.
+    pub progress: Option<Arc<AtomicUsize>>,
+}
+
+/// Where replay failures go: kept in memory, or streamed to `ReplayOptions::failures_path`.
+struct Failures {
+    lines: Vec<String>,
+    file: Option<BufWriter<fs::File>>,
+    count: usize,
+}
+
+impl Failures {
+    async fn open(path: Option<&str>) -> Result<Self> {
+        let file = match path {
+            Some(path) => Some(BufWriter::new(
+                fs::File::create(path)
+                    .await
+                    .with_context(|| format!("create replay failures file {path}"))?,
+            )),
+            None => None,
+        };
+        Ok(Self { lines: Vec::new(), file, count: 0 })
+    }
+
+    async fn push(&mut self, line: String) -> Result<()> {
+        self.count += 1;
+        match &mut self.file {
+            Some(file) => {
+                file.write_all(line.as_bytes()).await.context("write replay failure")?;
+                file.write_all(b"\n").await.context("write replay failure")
+            }
+            None => {
+                self.lines.push(line);
+                Ok(())
+            }
+        }
+    }
+
+    async fn finish(mut self) -> Result<(Vec<String>, usize)> {
+        if let Some(file) = &mut self.file {
+            file.flush().await.context("flush replay failures file")?;
+        }
+        Ok((self.lines, self.count))
+    }
 }
 
 /// Replays a newline-delimited capture file through the parser and persistence pipeline.
 pub async fn replay_sample(sample_path: impl AsRef<Path>, config: &AppConfig) -> Result<ReplayResult> {
+    replay_sample_with_options(sample_path, config, &ReplayOptions::default()).await
+}
+
+/// Like `replay_sample`, but honours `options`, e.g. aborting a hopelessly corrupt file early.
+pub async fn replay_sample_with_options(
+    sample_path: impl AsRef<Path>,
+    config: &AppConfig,
+    options: &ReplayOptions,
+) -> Result<ReplayResult> {
     let data_dir = &config.data_directory;
     let persistence = Arc::new(
         Persistence::new(data_dir)
             .await
-            .context("prepare persistence backend")?,
+            .context("prepare persistence backend")?
+            .with_manifest(options.manifest.clone())
+            .with_date_format(&config.filename_date_format)
+            .with_split_mode(config.split_mode)
+            .with_dir_sync(config.fsync_dir_on_rotate)
+            .with_output_format(config.output_format),
     );
+    let quarantine = Persistence::with_prefix(data_dir, "quarantine")
+        .await
+        .context("prepare quarantine backend")?
+        .with_manifest(options.manifest.clone())
+        .with_date_format(&config.filename_date_format)
+        .with_split_mode(config.split_mode)
+        .with_dir_sync(config.fsync_dir_on_rotate)
+        .with_output_format(config.output_format);
     let metrics = Metrics::new();
-    let mut failures = Vec::new();
+    let mut failures = Failures::open(options.failures_path.as_deref()).await?;
+    let mut quarantined = 0;
+    let mut aborted = false;
+    let mut interrupted = false;
 
-    let raw = fs::read_to_string(sample_path.as_ref())
+    // Stream the capture so multi-GB archives replay in bounded memory.
+    let file = fs::File::open(sample_path.as_ref())
         .await
         .with_context(|| format!("open sample capture {}", sample_path.as_ref().display()))?;
+    let mut capture = CaptureReader::new(BufReader::new(file));
+    let source_file = options
+        .tag_source_file
+        .then(|| sample_path.as_ref().file_name().map(|name| name.to_string_lossy().into_owned()))
+        .flatten();
+    let mut enu = config.convert_to_enu.then(EnuConversion::new);
+    let mut records = 0;
 
-    for raw_line in normalize_capture(&raw) {
-        match Frame::from_line(&raw_line) {
-            Ok(frame) => {
+    loop {
+        // Every record read so far is fully handled: an error returns before persisting
+        if let Some(progress) = options.progress.as_ref().filter(|_| records >= options.resume_after) {
+            progress.store(records, Ordering::Relaxed);
+        }
+        let Some(raw_line) = capture
+            .next_frame()
+            .await
+            .with_context(|| format!("read sample capture {}", sample_path.as_ref().display()))?
+        else {
+            break;
+        };
+        records += 1;
+        if records <= options.resume_after {
+            continue;
+        }
+        if options.stop.as_ref().is_some_and(|stop| matches!(stop.has_changed(), Ok(true))) {
+            tracing::warn!("replay interrupted; stopping after the last complete frame");
+            interrupted = true;
+            break;
+        }
+        let parse_started = config.profile_parsing.then(std::time::Instant::now);
+        match Frame::from_line_with(&raw_line, &config.dialect) {
+            Ok(mut frame) => {
+                if let Some(started) = parse_started {
+                    metrics.record_parse_time(frame.payload.kind(), started.elapsed());
+                }
+                frame.source_file.clone_from(&source_file);
+                if let Some(enu) = &mut enu {
+                    enu.apply(&mut frame);
+                }
                 // Task: .failed files should include discarded parts even if the line partially parsed.
                 for discarded in &frame.discarded {
-                    failures.push(discarded.clone());
+                    failures.push(discarded.clone()).await?;
                 }
-                
-                if let Err(err) = persistence.append(&frame).await {
+
+                if !validation::check_horizon(config.min_frame_age_days, config.max_frame_age_days, &frame, Utc::now(), &metrics) {
+                    continue;
+                }
+                if options.exclude_types.contains(&frame.payload.kind()) {
+                    metrics.record_frame();
+                    continue;
+                }
+                let valid = validation::check(&config.validation_rules, &frame, &metrics);
+                let quarantining = !valid && config.quarantine_invalid_frames;
+                let target = if quarantining { &quarantine } else { persistence.as_ref() };
+                if let Err(err) = target.append(&frame).await {
                     metrics.record_persistence_error();
                     tracing::error!(error = %err, "persistence failed during replay");
                     // If persistence fails, we consider the whole frame a failure in terms of processing
-                    failures.push(raw_line);
+                    failures.push(raw_line).await?;
                 } else {
                     metrics.record_frame();
+                    if quarantining {
+                        quarantined += 1;
+                    }
                 }
             }
             Err(err) => {
                 metrics.record_parse_error();
                 tracing::warn!(error = %err, frame = %raw_line, "sample frame rejected");
-                failures.push(raw_line);
+                failures.push(raw_line).await?;
+                if let Some(max_errors) = options.max_errors {
+                    if metrics.snapshot().parse_errors as usize > max_errors {
+                        tracing::warn!(max_errors, "replay error budget exceeded; aborting");
+                        aborted = true;
+                        break;
+                    }
+                }
             }
         }
     }
 
+    let (failures, failure_count) = failures.finish().await?;
     let snapshot = metrics.snapshot();
     tracing::info!(
         frames = snapshot.frames,
@@ -65,11 +265,126 @@ pub async fn replay_sample(sample_path: impl AsRef<Path>, config: &AppConfig) ->
         frames_processed: snapshot.frames as usize,
         parse_errors: snapshot.parse_errors as usize,
         persistence_errors: snapshot.persistence_errors as usize,
+        quarantined,
         failures,
+        failure_count,
+        out_of_horizon: snapshot.out_of_horizon as usize,
+        parse_timings: snapshot.parse_timings,
+        aborted,
+        interrupted,
     })
 }
 
-fn normalize_capture(raw: &str) -> Vec<String> {
+/// Keeps the capture lines that parse under `dialect`, so a passthrough never forwards
+/// corrupt frames; rejects are logged and counted.
+pub fn valid_lines<'a>(lines: &[&'a str], dialect: &DialectConfig) -> (Vec<&'a str>, usize) {
+    let mut rejected = 0;
+    let valid = lines
+        .iter()
+        .copied()
+        .filter(|line| !line.trim().is_empty())
+        .filter(|line| match Frame::from_line_with(line, dialect) {
+            Ok(_) => true,
+            Err(err) => {
+                rejected += 1;
+                tracing::warn!(error = %err, frame = %line, "simulator dropped invalid line");
+                false
+            }
+        })
+        .collect();
+    (valid, rejected)
+}
+
+/// Writes capture lines to `out`, pacing them by the gap between consecutive frame
+/// timestamps divided by `speed`, so replayed timing matches the original recording.
+/// Untimed, unparseable (under `dialect`) or out-of-order lines use a short fixed delay instead.
+pub async fn stream_capture<W: AsyncWrite + Unpin>(
+    lines: &[&str],
+    out: &mut W,
+    speed: f64,
+    dialect: &DialectConfig,
+) -> Result<()> {
+    let mut previous: Option<DateTime<Utc>> = None;
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sent_at = Frame::from_line_with(line, dialect).ok().and_then(|f| f.payload.sent_at());
+        let gap = match (previous, sent_at) {
+            (Some(prev), Some(current)) => (current - prev)
+                .to_std()
+                .map(|gap| Duration::from_secs_f64(gap.as_secs_f64() / speed))
+                .unwrap_or(UNTIMED_LINE_DELAY),
+            _ => UNTIMED_LINE_DELAY,
+        };
+        if sent_at.is_some() {
+            previous = sent_at;
+        }
+        sleep(gap).await;
+        out.write_all(line.as_bytes()).await?;
+        out.write_all(b"\n").await?;
+        out.flush().await?;
+    }
+    Ok(())
+}
+
+/// Incremental counterpart of `normalize_capture`: reads a capture line by line and yields
+/// the same `$`-delimited frames, so memory is bounded by the longest frame, not the file.
+pub(crate) struct CaptureReader<R> {
+    reader: R,
+    line: String,
+    /// Text since the last `$`, possibly spanning several physical lines.
+    chunk: String,
+    ready: VecDeque<String>,
+    eof: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> CaptureReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            chunk: String::new(),
+            ready: VecDeque::new(),
+            eof: false,
+        }
+    }
+
+    pub(crate) async fn next_frame(&mut self) -> Result<Option<String>> {
+        loop {
+            if let Some(frame) = self.ready.pop_front() {
+                return Ok(Some(frame));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            self.line.clear();
+            if self.reader.read_line(&mut self.line).await? == 0 {
+                self.eof = true;
+                let last = std::mem::take(&mut self.chunk);
+                self.emit(&last);
+                continue;
+            }
+            // A literal "\\r\\n" never contains a real newline, so it cannot straddle reads.
+            let line = self.line.replace("\\r\\n", "\n").replace('\r', "\n");
+            let mut parts = line.split('$');
+            self.chunk.push_str(parts.next().unwrap_or_default());
+            for part in parts {
+                let done = std::mem::replace(&mut self.chunk, part.to_string());
+                self.emit(&done);
+            }
+        }
+    }
+
+    fn emit(&mut self, chunk: &str) {
+        let trimmed = chunk.trim();
+        if !trimmed.is_empty() {
+            self.ready.push_back(format!("${}", trimmed));
+        }
+    }
+}
+
+pub(crate) fn normalize_capture(raw: &str) -> Vec<String> {
     // The bundled sample uses literal "\\r\\n" sequences; treat both literal and actual CRLF
     // as frame delimiters and rebuild clean lines that start with '$'.
     let normalized = raw.replace("\\r\\n", "\n").replace('\r', "\n");
@@ -88,7 +403,10 @@ fn normalize_capture(raw: &str) -> Vec<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::normalize_capture;
+    use super::*;
+    use crate::config::ServiceMode;
+    use crate::validation::ValidationRule;
+    use tempfile::tempdir;
 
     #[test]
     fn normalizes_literal_crlf_sequences() {
@@ -98,4 +416,356 @@ mod tests {
         assert_eq!(lines[0], "$PNORI,4*41");
         assert_eq!(lines[1], "$PNORS,010526,220800*77");
     }
+
+    #[tokio::test]
+    async fn streaming_reader_matches_in_memory_normalization() {
+        let mut raw = String::from("leading junk\n");
+        for i in 0..20_000 {
+            match i % 4 {
+                0 => raw.push_str("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\\r\\n"),
+                1 => raw.push_str("$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\r\n"),
+                2 => raw.push_str("noise without a marker\n\n"),
+                _ => raw.push_str("$PNORC,010526,220800,4,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*26 $PNORI,4*41\r"),
+            }
+        }
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("large.data");
+        fs::write(&path, &raw).await.expect("write capture");
+
+        let file = fs::File::open(&path).await.expect("open capture");
+        let mut reader = CaptureReader::new(BufReader::new(file));
+        let mut streamed = Vec::new();
+        while let Some(frame) = reader.next_frame().await.expect("read frame") {
+            streamed.push(frame);
+        }
+        assert_eq!(streamed, normalize_capture(&raw));
+    }
+
+    #[tokio::test]
+    async fn aborts_replay_beyond_error_budget() {
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("corrupt.data");
+        let mut raw = String::from("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n");
+        for _ in 0..1000 {
+            raw.push_str("$GARBAGE,not,a,frame*00\n");
+        }
+        fs::write(&path, raw).await.expect("write capture");
+        let mut config: AppConfig = toml::from_str("service_name = \"budget\"").expect("parse config");
+        config.data_directory = tmp.path().join("data").to_string_lossy().to_string();
+
+        let options = ReplayOptions {
+            max_errors: Some(5),
+            ..ReplayOptions::default()
+        };
+        let result = replay_sample_with_options(&path, &config, &options)
+            .await
+            .expect("replay");
+        assert!(result.aborted);
+        assert_eq!(result.frames_processed, 1);
+        assert_eq!(result.parse_errors, 6);
+        assert_eq!(result.failures.len(), 6);
+        assert_eq!(result.failure_count, 6);
+
+        let full = replay_sample(&path, &config).await.expect("replay without budget");
+        assert!(!full.aborted);
+        assert_eq!(full.parse_errors, 1000);
+    }
+
+    #[tokio::test]
+    async fn streams_failures_to_a_file_instead_of_memory() {
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("corrupt.data");
+        let mut raw = String::from("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41\n");
+        for idx in 0..50 {
+            raw.push_str(&format!("$GARBAGE,{idx},not,a,frame*00\n"));
+        }
+        fs::write(&path, raw).await.expect("write capture");
+        let mut config: AppConfig = toml::from_str("service_name = \"failures\"").expect("parse config");
+        config.data_directory = tmp.path().join("data").to_string_lossy().to_string();
+        let failures_path = tmp.path().join("replay.failed");
+
+        let options = ReplayOptions {
+            failures_path: Some(failures_path.to_string_lossy().to_string()),
+            ..ReplayOptions::default()
+        };
+        let result = replay_sample_with_options(&path, &config, &options)
+            .await
+            .expect("replay");
+        assert!(result.failures.is_empty(), "nothing accumulates in memory");
+        assert_eq!(result.failure_count, 50);
+        assert_eq!(result.frames_processed, 1);
+
+        let written = fs::read_to_string(&failures_path).await.expect("read failures file");
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 50);
+        assert_eq!(lines[0], "$GARBAGE,0,not,a,frame*00");
+        assert_eq!(lines[49], "$GARBAGE,49,not,a,frame*00");
+    }
+
+    #[tokio::test]
+    async fn excluded_types_are_counted_but_not_persisted() {
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("mixed.data");
+        fs::write(
+            &path,
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41
+$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77
+$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23
+$PNORC,010526,220800,2,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*20
+",
+        )
+        .await
+        .expect("write capture");
+        let mut config: AppConfig = toml::from_str("service_name = \"exclude\"").expect("parse config");
+        config.data_directory = tmp.path().join("data").to_string_lossy().to_string();
+
+        let options = ReplayOptions {
+            exclude_types: vec![SentenceKind::Current],
+            ..ReplayOptions::default()
+        };
+        let result = replay_sample_with_options(&path, &config, &options)
+            .await
+            .expect("replay");
+        assert_eq!(result.frames_processed, 4, "excluded frames still count");
+
+        let log = fs::read_to_string(tmp.path().join("data").join("adcp-2026-01-05.log"))
+            .await
+            .expect("read log");
+        let kinds: Vec<SentenceKind> = log
+            .lines()
+            .map(|line| serde_json::from_str::<Frame>(line).expect("frame json").payload.kind())
+            .collect();
+        assert_eq!(kinds, vec![SentenceKind::Config, SentenceKind::Sensor]);
+    }
+
+    #[tokio::test]
+    async fn names_logs_with_the_configured_date_format() {
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("compact.data");
+        fs::write(
+            &path,
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\n",
+        )
+        .await
+        .expect("write capture");
+        let mut config: AppConfig =
+            toml::from_str("service_name = \"compact\"\nfilename_date_format = \"%Y%m%d\"").expect("parse config");
+        let data_dir = tmp.path().join("data");
+        config.data_directory = data_dir.to_string_lossy().to_string();
+
+        replay_sample(&path, &config).await.expect("replay");
+        assert!(data_dir.join("adcp-20260105.log").exists());
+        assert!(!data_dir.join("adcp-2026-01-05.log").exists());
+    }
+
+    #[tokio::test]
+    async fn skips_frames_older_than_the_age_horizon() {
+        let tmp = tempdir().expect("temp dir");
+        let sensor = |stamp: &str| {
+            let body = format!("PNORS,{stamp},00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0");
+            let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            format!("${body}*{checksum:02X}\n")
+        };
+        let recent = Utc::now().format("%m%d%y,%H%M%S").to_string();
+        let path = tmp.path().join("mixed.data");
+        fs::write(&path, sensor("010519,220800") + &sensor(&recent))
+            .await
+            .expect("write capture");
+        let mut config: AppConfig = toml::from_str("service_name = \"horizon\"").expect("parse config");
+        let data_dir = tmp.path().join("data");
+        config.data_directory = data_dir.to_string_lossy().to_string();
+        config.max_frame_age_days = Some(30);
+
+        let result = replay_sample(&path, &config).await.expect("replay");
+        assert_eq!(result.out_of_horizon, 1);
+        assert_eq!(result.frames_processed, 1);
+        assert!(result.to_string().contains("out of horizon: 1"));
+        let logs: Vec<String> = std::fs::read_dir(&data_dir)
+            .expect("list data dir")
+            .map(|entry| entry.expect("entry").file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("adcp-"))
+            .collect();
+        assert!(!logs.iter().any(|name| name.contains("2019")), "{logs:?}");
+    }
+
+    #[tokio::test]
+    async fn profiling_times_every_parsed_sentence_type() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config: AppConfig = toml::from_str("service_name = \"profile\"").expect("parse config");
+        config.data_directory = tmp.path().join("data").to_string_lossy().to_string();
+
+        let result = replay_sample("tests/sample.data", &config).await.expect("replay");
+        assert!(result.parse_timings.is_empty(), "off by default");
+
+        config.profile_parsing = true;
+        let result = replay_sample("tests/sample.data", &config).await.expect("replay");
+        let samples = |kind| result.parse_timings.get(&kind).map_or(0, |h| h.samples);
+        assert_eq!(samples(SentenceKind::Config), 2);
+        assert_eq!(samples(SentenceKind::Sensor), 2);
+        assert_eq!(samples(SentenceKind::Current), 42);
+        for histogram in result.parse_timings.values() {
+            assert_eq!(histogram.buckets.iter().sum::<u64>(), histogram.samples);
+        }
+        assert!(result.to_string().contains("parse timings: config n=2"), "{result}");
+    }
+
+    #[tokio::test]
+    async fn tags_persisted_frames_with_the_source_capture() {
+        let tmp = tempdir().expect("temp dir");
+        let mut config: AppConfig = toml::from_str("service_name = \"provenance\"").expect("parse config");
+        let data_dir = tmp.path().join("data");
+        config.data_directory = data_dir.to_string_lossy().to_string();
+
+        let options = ReplayOptions {
+            tag_source_file: true,
+            ..ReplayOptions::default()
+        };
+        let result = replay_sample_with_options("tests/sample.data", &config, &options)
+            .await
+            .expect("replay");
+        assert!(result.frames_processed > 0);
+
+        let mut persisted = 0;
+        for entry in std::fs::read_dir(&data_dir).expect("list data dir") {
+            let path = entry.expect("entry").path();
+            if path.extension().is_none_or(|ext| ext != "log") {
+                continue;
+            }
+            for line in std::fs::read_to_string(&path).expect("read log").lines() {
+                let frame: Frame = serde_json::from_str(line).expect("frame json");
+                assert_eq!(frame.source_file.as_deref(), Some("sample.data"), "{line}");
+                persisted += 1;
+            }
+        }
+        assert_eq!(persisted, result.frames_processed);
+    }
+
+    #[tokio::test]
+    async fn manifest_lists_the_dated_log_with_its_frame_count() {
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("manifest.data");
+        fs::write(
+            &path,
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41
+$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77
+$GARBAGE*00
+$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23
+",
+        )
+        .await
+        .expect("write capture");
+        let mut config: AppConfig = toml::from_str("service_name = \"manifest\"").expect("parse config");
+        let data_dir = tmp.path().join("data");
+        config.data_directory = data_dir.to_string_lossy().to_string();
+
+        let manifest = Arc::new(RunManifest::new());
+        let options = ReplayOptions {
+            manifest: Some(manifest.clone()),
+            ..ReplayOptions::default()
+        };
+        let result = replay_sample_with_options(&path, &config, &options)
+            .await
+            .expect("replay");
+        assert_eq!(result.frames_processed, 3);
+
+        let written = manifest.write(&data_dir).expect("write manifest");
+        let document: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&written).expect("read manifest")).expect("manifest json");
+        let files = document["files"].as_array().expect("files");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0]["kind"], "persisted");
+        assert_eq!(files[0]["path"], data_dir.join("adcp-2026-01-05.log").display().to_string());
+        assert_eq!(files[0]["lines"], 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paces_lines_by_capture_timestamps() {
+        let lines = [
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+            "$PNORS,010526,220802,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*75",
+        ];
+        let mut out = Vec::new();
+        let started = tokio::time::Instant::now();
+        stream_capture(&lines, &mut out, 2.0, &DialectConfig::default()).await.expect("stream capture");
+        let elapsed = started.elapsed();
+
+        // 2s apart in the capture, replayed at 2x: ~1s plus the initial untimed delay.
+        assert_eq!(elapsed, Duration::from_secs(1) + UNTIMED_LINE_DELAY);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn filtered_passthrough_drops_corrupt_lines() {
+        let lines = [
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41",
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*00",
+            "$PNORS,010526,220802,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*75",
+        ];
+        let (valid, rejected) = valid_lines(&lines, &DialectConfig::default());
+        assert_eq!(rejected, 1);
+
+        let mut out = Vec::new();
+        stream_capture(&valid, &mut out, 100.0, &DialectConfig::default()).await.expect("stream capture");
+        let forwarded = String::from_utf8(out).unwrap();
+        assert_eq!(forwarded.lines().collect::<Vec<_>>(), vec![lines[0], lines[2]]);
+    }
+
+    #[tokio::test]
+    async fn quarantines_frames_violating_validation_rules() {
+        let tmp = tempdir().expect("temp dir");
+        let config = AppConfig {
+            service_name: "quarantine-test".into(),
+            data_directory: tmp.path().to_string_lossy().to_string(),
+            mode: ServiceMode::Processing,
+            backup_folder: "./backup".into(),
+            data_process_folder: "./to_process".into(),
+            processed_folder: "./processed".into(),
+            validation_rules: vec![ValidationRule {
+                field: "temperature_c".into(),
+                min: Some(-5.0),
+                max: Some(20.0),
+            }],
+            quarantine_invalid_frames: true,
+            processing_concurrency: 1,
+            ..Default::default()
+        };
+
+        let result = replay_sample("tests/fixtures/small.data", &config)
+            .await
+            .expect("replay small fixture");
+        assert_eq!(result.quarantined, 1);
+        assert_eq!(result.frames_processed, 1);
+
+        let quarantine = tmp.path().join("quarantine-2026-01-05.log");
+        let content = std::fs::read_to_string(quarantine).expect("read quarantine log");
+        assert!(content.contains("PNORS"));
+        assert!(!tmp.path().join("adcp-2026-01-05.log").exists());
+    }
+
+    #[tokio::test]
+    async fn excluded_types_skip_validation() {
+        let tmp = tempdir().expect("temp dir");
+        let config = AppConfig {
+            service_name: "quarantine-test".into(),
+            data_directory: tmp.path().to_string_lossy().to_string(),
+            validation_rules: vec![ValidationRule {
+                field: "temperature_c".into(),
+                min: Some(-5.0),
+                max: Some(20.0),
+            }],
+            quarantine_invalid_frames: true,
+            ..Default::default()
+        };
+        let options = ReplayOptions {
+            exclude_types: vec![SentenceKind::Sensor],
+            ..ReplayOptions::default()
+        };
+
+        let result = replay_sample_with_options("tests/fixtures/small.data", &config, &options)
+            .await
+            .expect("replay small fixture");
+        assert_eq!(result.quarantined, 0);
+        assert_eq!(result.frames_processed, 1);
+        assert!(!tmp.path().join("quarantine-2026-01-05.log").exists());
+    }
 }
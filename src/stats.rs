@@ -0,0 +1,213 @@
+use crate::{
+    parser::{DialectConfig, Frame, Payload},
+    simulator::CaptureReader,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use tokio::{fs, io::BufReader};
+
+/// Hour bucket for lines seen before any dated frame and never followed by one.
+const UNDATED_BUCKET: &str = "undated";
+
+/// Distributions over one capture file, for characterizing a deployment.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct CaptureStats {
+    pub total_frames: u64,
+    pub parse_errors: u64,
+    /// Parsed frames per UTC hour (`YYYY-MM-DDTHH:00Z`); undated frames such as `$PNORI`
+    /// count towards the hour of the next dated frame.
+    pub frames_per_hour: BTreeMap<String, u64>,
+    /// Lines that failed to parse, bucketed the same way as `frames_per_hour`.
+    pub parse_errors_per_hour: BTreeMap<String, u64>,
+    /// Number of ensembles keyed by how many `$PNORC` cells they reported.
+    pub cells_per_ensemble: BTreeMap<u16, u64>,
+}
+
+#[derive(Default)]
+struct Pending {
+    frames: u64,
+    errors: u64,
+}
+
+impl CaptureStats {
+    fn settle(&mut self, hour: &str, pending: &mut Pending) {
+        if pending.frames > 0 {
+            *self.frames_per_hour.entry(hour.to_string()).or_insert(0) += pending.frames;
+        }
+        if pending.errors > 0 {
+            *self.parse_errors_per_hour.entry(hour.to_string()).or_insert(0) += pending.errors;
+        }
+        *pending = Pending::default();
+    }
+}
+
+/// Parses a capture and collects per-hour frame and error counts plus a cells-per-ensemble
+/// histogram. The file is streamed, so large archives are summarized in bounded memory.
+pub async fn capture_stats(input: impl AsRef<Path>) -> Result<CaptureStats> {
+    let input = input.as_ref();
+    let file = fs::File::open(input)
+        .await
+        .with_context(|| format!("open capture {}", input.display()))?;
+    let mut capture = CaptureReader::new(BufReader::new(file));
+
+    let mut stats = CaptureStats::default();
+    let mut pending = Pending::default();
+    let mut last_hour: Option<String> = None;
+    let mut ensemble: Option<(DateTime<Utc>, u16)> = None;
+
+    while let Some(line) = capture
+        .next_frame()
+        .await
+        .with_context(|| format!("read capture {}", input.display()))?
+    {
+        let frame = match Frame::from_line(&line) {
+            Ok(frame) => frame,
+            Err(_) => {
+                stats.parse_errors += 1;
+                pending.errors += 1;
+                continue;
+            }
+        };
+        stats.total_frames += 1;
+        pending.frames += 1;
+
+        if let Payload::Current(current) = &frame.payload {
+            match &mut ensemble {
+                Some((sent_at, cells)) if *sent_at == current.sent_at => *cells += 1,
+                _ => {
+                    if let Some((_, cells)) = ensemble.replace((current.sent_at, 1)) {
+                        *stats.cells_per_ensemble.entry(cells).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        if let Some(sent_at) = frame.payload.sent_at() {
+            let hour = sent_at.format("%Y-%m-%dT%H:00Z").to_string();
+            stats.settle(&hour, &mut pending);
+            last_hour = Some(hour);
+        }
+    }
+    if let Some((_, cells)) = ensemble {
+        *stats.cells_per_ensemble.entry(cells).or_insert(0) += 1;
+    }
+    // Trailing undated lines stay with the last hour seen
+    let tail = last_hour.unwrap_or_else(|| UNDATED_BUCKET.to_string());
+    stats.settle(&tail, &mut pending);
+    Ok(stats)
+}
+
+/// Frames per UTC day of their `sent_at`, for sizing a reprocess before committing to it.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct DailyCounts {
+    pub frames_per_day: BTreeMap<NaiveDate, u64>,
+    /// Parsed frames that carry no timestamp, such as `$PNORI`.
+    pub undated: u64,
+    pub parse_errors: u64,
+}
+
+/// Counts the frames of a capture per day without persisting anything. Unexpected dates
+/// in the table usually point at a drifting instrument clock.
+pub async fn frames_per_day(input: impl AsRef<Path>, dialect: &DialectConfig) -> Result<DailyCounts> {
+    let input = input.as_ref();
+    let file = fs::File::open(input)
+        .await
+        .with_context(|| format!("open capture {}", input.display()))?;
+    let mut capture = CaptureReader::new(BufReader::new(file));
+    let mut counts = DailyCounts::default();
+    while let Some(line) = capture
+        .next_frame()
+        .await
+        .with_context(|| format!("read capture {}", input.display()))?
+    {
+        match Frame::from_line_with(&line, dialect) {
+            Ok(frame) => match frame.payload.sent_at() {
+                Some(sent_at) => *counts.frames_per_day.entry(sent_at.date_naive()).or_insert(0) += 1,
+                None => counts.undated += 1,
+            },
+            Err(_) => counts.parse_errors += 1,
+        }
+    }
+    Ok(counts)
+}
+
+impl fmt::Display for DailyCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "date        frames")?;
+        for (day, frames) in &self.frames_per_day {
+            writeln!(f, "{day}  {frames:>6}")?;
+        }
+        writeln!(f, "undated     {:>6}", self.undated)?;
+        writeln!(f, "parse errors: {}", self.parse_errors)
+    }
+}
+
+impl fmt::Display for CaptureStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "frames: {}", self.total_frames)?;
+        writeln!(f, "parse errors: {}", self.parse_errors)?;
+        writeln!(f, "frames per hour:")?;
+        for (hour, frames) in &self.frames_per_hour {
+            let errors = self.parse_errors_per_hour.get(hour).copied().unwrap_or(0);
+            writeln!(f, "  {hour}  {frames:>8} frames  {errors:>6} errors")?;
+        }
+        for (hour, errors) in &self.parse_errors_per_hour {
+            if !self.frames_per_hour.contains_key(hour) {
+                writeln!(f, "  {hour}  {:>8} frames  {errors:>6} errors", 0)?;
+            }
+        }
+        writeln!(f, "cells per ensemble:")?;
+        let widest = self.cells_per_ensemble.values().copied().max().unwrap_or(0);
+        for (cells, ensembles) in &self.cells_per_ensemble {
+            // Bars scaled to 40 columns so long deployments stay readable
+            let bar = "#".repeat(((ensembles * 40).div_ceil(widest.max(1))) as usize);
+            writeln!(f, "  {cells:>4}  {ensembles:>8}  {bar}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn summarizes_the_sample_capture() {
+        let stats = capture_stats("tests/sample.data").await.expect("stats");
+        assert_eq!(stats.total_frames, 46);
+        assert_eq!(stats.parse_errors, 0);
+        assert_eq!(stats.frames_per_hour.values().sum::<u64>(), stats.total_frames);
+        assert_eq!(stats.cells_per_ensemble, BTreeMap::from([(21, 2)]));
+    }
+
+    #[tokio::test]
+    async fn buckets_frames_and_errors_by_hour() {
+        let tmp = tempdir().expect("temp dir");
+        let path = tmp.path().join("hours.data");
+        fs::write(
+            &path,
+            "$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41
+$PNORC,010526,220800,1,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*23
+$PNORC,010526,220800,2,0.56,-0.80,-1.99,-1.33,0.98,305.2,C,80,88,67,78,13,17,10,18*20
+$GARBAGE*00
+$PNORS,010526,230800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*76
+",
+        )
+        .await
+        .expect("write capture");
+
+        let stats = capture_stats(&path).await.expect("stats");
+        assert_eq!(stats.total_frames, 4);
+        assert_eq!(
+            stats.frames_per_hour,
+            BTreeMap::from([("2026-01-05T22:00Z".to_string(), 3), ("2026-01-05T23:00Z".to_string(), 1)])
+        );
+        assert_eq!(stats.frames_per_hour.values().sum::<u64>(), stats.total_frames);
+        assert_eq!(stats.parse_errors_per_hour, BTreeMap::from([("2026-01-05T23:00Z".to_string(), 1)]));
+        assert_eq!(stats.cells_per_ensemble, BTreeMap::from([(2, 1)]));
+    }
+}
@@ -1,4 +1,11 @@
+use crate::parser::{Frame, Payload, SensorSentence};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+use tokio::sync::Notify;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RecorderStats {
@@ -9,4 +16,172 @@ pub struct RecorderStats {
     pub rotation_count: u64,
     pub last_packet_time: Option<u64>, // Unix timestamp in seconds or milliseconds
     pub uptime_seconds: u64,
+    /// Bus messages dropped because the publish queue was full (slow or stalled broker).
+    #[serde(default)]
+    pub publishes_dropped: u64,
+    /// Partial lines dropped for running past the recorder's line cap without a delimiter.
+    #[serde(default)]
+    pub oversized_lines: u64,
+    /// Set on the final message a recorder publishes while shutting down, so monitors can
+    /// mark it offline instead of showing its last values forever.
+    #[serde(default)]
+    pub stopped: bool,
+}
+
+impl RecorderStats {
+    /// The terminal state published on shutdown: no throughput and `stopped` set.
+    pub fn terminal(&self) -> Self {
+        Self {
+            bytes_per_second: 0,
+            stopped: true,
+            ..self.clone()
+        }
+    }
+}
+
+/// Bounded drop-oldest queue between producers and the single task that publishes to the
+/// bus. A slow broker then costs stale messages rather than stalling capture or growing
+/// memory without bound.
+#[derive(Debug)]
+pub struct PublishQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    depth: usize,
+    ready: Notify,
+    dropped: AtomicU64,
+}
+
+impl<T> PublishQueue<T> {
+    pub fn new(depth: usize) -> Self {
+        let depth = depth.max(1);
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(depth)),
+            depth,
+            ready: Notify::new(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Enqueues `item`, evicting the oldest queued message when the queue is full.
+    pub fn push(&self, item: T) {
+        if let Ok(mut items) = self.items.lock() {
+            if items.len() >= self.depth {
+                items.pop_front();
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            items.push_back(item);
+        }
+        self.ready.notify_one();
+    }
+
+    /// Waits for the next message to publish.
+    pub async fn next(&self) -> T {
+        loop {
+            if let Some(item) = self.items.lock().ok().and_then(|mut items| items.pop_front()) {
+                return item;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    /// Takes every queued message, e.g. to publish them synchronously on shutdown.
+    pub fn drain(&self) -> Vec<T> {
+        self.items
+            .lock()
+            .map(|mut items| items.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().map(|items| items.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Last-value cache of the most recent `$PNORS` reading, served over the
+/// `cmd.recorder.sensors` RPC so dashboards don't need to stream every frame.
+#[derive(Debug, Default)]
+pub struct SensorCache {
+    latest: Mutex<Option<SensorSentence>>,
+}
+
+impl SensorCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the payload of `frame` when it is a sensor sentence; other frames are ignored.
+    pub fn update(&self, frame: &Frame) {
+        if let Payload::Sensor(sensor) = &frame.payload {
+            if let Ok(mut guard) = self.latest.lock() {
+                *guard = Some(sensor.clone());
+            }
+        }
+    }
+
+    pub fn latest(&self) -> Option<SensorSentence> {
+        self.latest.lock().ok().and_then(|guard| guard.clone())
+    }
+
+    /// Serializes the cached reading as JSON (`null` until the first sensor frame arrives).
+    pub fn to_json(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(&self.latest())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensor_cache_serves_latest_reading() {
+        let cache = SensorCache::new();
+        assert_eq!(cache.to_json().expect("serialize empty"), b"null");
+
+        let sensor = Frame::from_line(
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+        )
+        .expect("parse sensor");
+        cache.update(&sensor);
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&cache.to_json().expect("serialize")).expect("valid json");
+        assert_eq!(json["heading_deg"].as_f64().map(|v| v as f32), Some(275.4));
+        assert_eq!(json["temperature_c"].as_f64().map(|v| v as f32), Some(24.02));
+    }
+
+    #[tokio::test]
+    async fn publish_queue_drops_oldest_when_publisher_is_blocked() {
+        let queue = std::sync::Arc::new(PublishQueue::new(2));
+        let stalled = std::sync::Arc::new(Notify::new());
+
+        // The publisher takes one message and then hangs on the "broker".
+        let publisher = tokio::spawn({
+            let queue = queue.clone();
+            let stalled = stalled.clone();
+            async move {
+                let first = queue.next().await;
+                stalled.notified().await;
+                first
+            }
+        });
+        queue.push(1);
+        tokio::task::yield_now().await;
+        for msg in 2..=6 {
+            queue.push(msg);
+        }
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 3);
+        stalled.notify_one();
+        assert_eq!(publisher.await.expect("publisher"), 1);
+        assert_eq!(queue.next().await, 5);
+        assert_eq!(queue.next().await, 6);
+    }
 }
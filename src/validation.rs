@@ -0,0 +1,221 @@
+use crate::{
+    metrics::Metrics,
+    parser::{Frame, Payload},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Field names a validation rule may reference, grouped by the sentence carrying them.
+pub const KNOWN_FIELDS: &[&str] = &[
+    // $PNORI
+    "beams",
+    "cells",
+    "blanking_m",
+    "cell_size_m",
+    // $PNORS
+    "battery_voltage_v",
+    "sound_speed_m_s",
+    "heading_deg",
+    "pitch_deg",
+    "roll_deg",
+    "pressure_dbar",
+    "temperature_c",
+    "analog_input_1",
+    "analog_input_2",
+    // $PNORC
+    "cell_number",
+    "velocity_1_m_s",
+    "velocity_2_m_s",
+    "velocity_3_m_s",
+    "velocity_4_m_s",
+    "speed_m_s",
+    "direction_deg",
+    "amplitude_beam_1",
+    "amplitude_beam_2",
+    "amplitude_beam_3",
+    "amplitude_beam_4",
+    "correlation_beam_1_pct",
+    "correlation_beam_2_pct",
+    "correlation_beam_3_pct",
+    "correlation_beam_4_pct",
+    // $PNORE
+    "distance_m",
+    "quality",
+    "range_beam_1_m",
+    "range_beam_2_m",
+    "range_beam_3_m",
+    "range_beam_4_m",
+];
+
+/// A site-specific QC bound on a single payload field, e.g. `temperature_c` within `-5..40`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ValidationRule {
+    pub field: String,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl ValidationRule {
+    pub fn is_known_field(&self) -> bool {
+        KNOWN_FIELDS.contains(&self.field.as_str())
+    }
+
+    /// Returns true when the frame carries this field and its value falls outside the bounds.
+    /// Frames without the field, or with the field marked invalid by the instrument, pass.
+    pub fn is_violated_by(&self, frame: &Frame) -> bool {
+        match field_value(&frame.payload, &self.field) {
+            Some(value) => {
+                self.min.is_some_and(|min| value < min) || self.max.is_some_and(|max| value > max)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Applies every rule to `frame`, counting each violation per field in `metrics`.
+/// Returns true when the frame passed all rules.
+pub fn check(rules: &[ValidationRule], frame: &Frame, metrics: &Metrics) -> bool {
+    let mut valid = true;
+    for rule in rules {
+        if rule.is_violated_by(frame) {
+            metrics.record_rule_violation(&rule.field);
+            tracing::warn!(field = %rule.field, min = ?rule.min, max = ?rule.max, frame = %frame.raw, "validation rule violated");
+            valid = false;
+        }
+    }
+    valid
+}
+
+/// Warns and counts when a `$PNORI` frame reports an instrument type other than `expected`,
+/// e.g. the wrong device cabled to this port. Returns true when the frame matches or no
+/// type is expected.
+pub fn check_instrument(expected: Option<u8>, frame: &Frame, metrics: &Metrics) -> bool {
+    let (Some(expected), Payload::Config(config)) = (expected, &frame.payload) else {
+        return true;
+    };
+    let actual = config.instrument_type.code();
+    if actual == expected {
+        return true;
+    }
+    metrics.record_instrument_mismatch();
+    tracing::warn!(
+        expected,
+        actual,
+        head_id = %config.head_id,
+        "unexpected instrument type; is the right device on this port?"
+    );
+    false
+}
+
+/// Counts `frame` as out of horizon when its `sent_at` is more than `max_age_days` or less
+/// than `min_age_days` before `now`, e.g. ancient test data mixed into a reprocessed archive.
+/// Returns true when the frame should be kept; frames without a timestamp always are.
+pub fn check_horizon(
+    min_age_days: Option<u64>,
+    max_age_days: Option<u64>,
+    frame: &Frame,
+    now: DateTime<Utc>,
+    metrics: &Metrics,
+) -> bool {
+    let Some(sent_at) = frame.payload.sent_at() else {
+        return true;
+    };
+    let age = now - sent_at;
+    // Horizons too large for a TimeDelta are as good as infinite
+    let days = |days: u64| i64::try_from(days).ok().and_then(TimeDelta::try_days);
+    let too_old = max_age_days.is_some_and(|max| days(max).is_some_and(|max| age > max));
+    let too_new = min_age_days.is_some_and(|min| days(min).is_none_or(|min| age < min));
+    if !(too_old || too_new) {
+        return true;
+    }
+    metrics.record_out_of_horizon();
+    tracing::debug!(sent_at = %sent_at, frame = %frame.raw, "frame outside the age horizon skipped");
+    false
+}
+
+fn field_value(payload: &Payload, field: &str) -> Option<f64> {
+    let value = match (payload, field) {
+        (Payload::Config(c), "beams") => Some(c.beams as f32),
+        (Payload::Config(c), "cells") => Some(c.cells as f32),
+        (Payload::Config(c), "blanking_m") => Some(c.blanking_m),
+        (Payload::Config(c), "cell_size_m") => Some(c.cell_size_m),
+        (Payload::Sensor(s), "battery_voltage_v") => s.battery_voltage_v,
+        (Payload::Sensor(s), "sound_speed_m_s") => s.sound_speed_m_s,
+        (Payload::Sensor(s), "heading_deg") => s.heading_deg,
+        (Payload::Sensor(s), "pitch_deg") => s.pitch_deg,
+        (Payload::Sensor(s), "roll_deg") => s.roll_deg,
+        (Payload::Sensor(s), "pressure_dbar") => s.pressure_dbar,
+        (Payload::Sensor(s), "temperature_c") => s.temperature_c,
+        (Payload::Sensor(s), "analog_input_1") => s.analog_input_1,
+        (Payload::Sensor(s), "analog_input_2") => s.analog_input_2,
+        (Payload::Current(c), "cell_number") => Some(c.cell_number as f32),
+        (Payload::Current(c), "velocity_1_m_s") => c.velocity_1_m_s,
+        (Payload::Current(c), "velocity_2_m_s") => c.velocity_2_m_s,
+        (Payload::Current(c), "velocity_3_m_s") => c.velocity_3_m_s,
+        (Payload::Current(c), "velocity_4_m_s") => c.velocity_4_m_s,
+        (Payload::Current(c), "speed_m_s") => c.speed_m_s,
+        (Payload::Current(c), "direction_deg") => c.direction_deg,
+        (Payload::Current(c), "amplitude_beam_1") => c.amplitude_beam_1.map(f32::from),
+        (Payload::Current(c), "amplitude_beam_2") => c.amplitude_beam_2.map(f32::from),
+        (Payload::Current(c), "amplitude_beam_3") => c.amplitude_beam_3.map(f32::from),
+        (Payload::Current(c), "amplitude_beam_4") => c.amplitude_beam_4.map(f32::from),
+        (Payload::Current(c), "correlation_beam_1_pct") => c.correlation_beam_1_pct.map(f32::from),
+        (Payload::Current(c), "correlation_beam_2_pct") => c.correlation_beam_2_pct.map(f32::from),
+        (Payload::Current(c), "correlation_beam_3_pct") => c.correlation_beam_3_pct.map(f32::from),
+        (Payload::Current(c), "correlation_beam_4_pct") => c.correlation_beam_4_pct.map(f32::from),
+        (Payload::Echo(e), "distance_m") => e.distance_m,
+        (Payload::Echo(e), "quality") => e.quality.map(f32::from),
+        (Payload::BottomTrack(b), "range_beam_1_m") => b.range_beam_1_m,
+        (Payload::BottomTrack(b), "range_beam_2_m") => b.range_beam_2_m,
+        (Payload::BottomTrack(b), "range_beam_3_m") => b.range_beam_3_m,
+        (Payload::BottomTrack(b), "range_beam_4_m") => b.range_beam_4_m,
+        (Payload::BottomTrack(b), "velocity_1_m_s") => b.velocity_1_m_s,
+        (Payload::BottomTrack(b), "velocity_2_m_s") => b.velocity_2_m_s,
+        (Payload::BottomTrack(b), "velocity_3_m_s") => b.velocity_3_m_s,
+        (Payload::BottomTrack(b), "velocity_4_m_s") => b.velocity_4_m_s,
+        _ => None,
+    };
+    value.map(f64::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_violations_per_field() {
+        let rules = vec![
+            ValidationRule { field: "temperature_c".into(), min: Some(-5.0), max: Some(20.0) },
+            ValidationRule { field: "heading_deg".into(), min: Some(0.0), max: Some(360.0) },
+        ];
+        let metrics = Metrics::new();
+        let sensor = Frame::from_line(
+            "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
+        )
+        .expect("parse sensor");
+        let config = Frame::from_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41")
+            .expect("parse config");
+
+        assert!(!check(&rules, &sensor, &metrics));
+        assert!(check(&rules, &config, &metrics), "rules on absent fields are skipped");
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.rule_violations.get("temperature_c"), Some(&1));
+        assert_eq!(snapshot.rule_violations.get("heading_deg"), None);
+    }
+    #[test]
+    fn flags_unexpected_instrument_type() {
+        let metrics = Metrics::new();
+        let other = Frame::from_line("$PNORI,2,Signature1000_100297,4,21,0.20,1.00,0*47")
+            .expect("parse config");
+        let signature = Frame::from_line("$PNORI,4,Signature1000_100297,4,21,0.20,1.00,0*41")
+            .expect("parse config");
+
+        assert!(!check_instrument(Some(4), &other, &metrics));
+        assert!(check_instrument(Some(4), &signature, &metrics));
+        assert!(check_instrument(None, &other, &metrics), "no expectation configured");
+        assert_eq!(metrics.snapshot().instrument_mismatches, 1);
+    }
+}
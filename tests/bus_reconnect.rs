@@ -0,0 +1,94 @@
+#![cfg(unix)]
+
+use adcp::bus::{BusLink, LinkState};
+use async_trait::async_trait;
+use busrt::client::AsyncClient;
+use busrt::ipc::{Client, Config};
+use busrt::rpc::{RpcEvent, RpcHandlers, RpcResult};
+use busrt::QoS;
+use std::process::{Child, Command, Stdio};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout, Duration, Instant};
+
+const BUS: &str = "127.0.0.1:7777";
+
+struct Forward(mpsc::UnboundedSender<Vec<u8>>);
+
+#[async_trait]
+impl RpcHandlers for Forward {
+    async fn handle_call(&self, _event: RpcEvent) -> RpcResult {
+        Ok(None)
+    }
+    async fn handle_notification(&self, _event: RpcEvent) {}
+    async fn handle_frame(&self, frame: busrt::Frame) {
+        let _ = self.0.send(frame.payload().to_vec());
+    }
+}
+
+fn start_broker() -> Child {
+    Command::new(assert_cmd::cargo::cargo_bin!("adcp-core-broker"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn broker")
+}
+
+/// Publishes `payload` from a throwaway client, retrying until the broker accepts connections.
+async fn publish(payload: &[u8]) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut publisher = loop {
+        match Client::connect(&Config::new(BUS, "adcp.test.reconnect-publisher")).await {
+            Ok(client) => break client,
+            Err(_) if Instant::now() < deadline => sleep(Duration::from_millis(100)).await,
+            Err(err) => panic!("broker never accepted the publisher: {err}"),
+        }
+    };
+    let confirm = publisher
+        .publish("stat/recorder/ttyTEST", payload.to_vec().into(), QoS::Processed)
+        .await
+        .expect("publish");
+    if let Some(confirm) = confirm {
+        confirm.await.expect("confirm channel").expect("publish confirmed");
+    }
+}
+
+#[tokio::test]
+async fn resubscribes_after_the_broker_restarts() {
+    let mut broker = start_broker();
+    let link = BusLink::new(BUS, "adcp.test.reconnect")
+        .with_topic("stat/recorder/#")
+        .with_topic("stat/recorder/#")
+        .with_max_backoff(Duration::from_millis(500));
+    let state = link.state();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let rpc = timeout(Duration::from_secs(10), link.connect_with_retry(|| Forward(tx.clone())))
+        .await
+        .expect("connects to the first broker");
+    assert_eq!(*state.borrow(), LinkState::Connected);
+    publish(b"before").await;
+    assert_eq!(rx.recv().await.expect("frame"), b"before");
+
+    broker.kill().expect("stop broker");
+    broker.wait().expect("reap broker");
+    timeout(Duration::from_secs(10), link.disconnected(&rpc))
+        .await
+        .expect("notices the broker went away");
+    assert_eq!(*state.borrow(), LinkState::Reconnecting);
+    drop(rpc);
+
+    let mut broker = start_broker();
+    let rpc = timeout(Duration::from_secs(10), link.connect_with_retry(|| Forward(tx.clone())))
+        .await
+        .expect("reconnects to the restarted broker");
+    assert_eq!(*state.borrow(), LinkState::Connected);
+    publish(b"after").await;
+    assert_eq!(rx.recv().await.expect("frame"), b"after");
+    // The repeated topic was subscribed once, so the frame is delivered once
+    sleep(Duration::from_millis(200)).await;
+    assert!(rx.try_recv().is_err());
+
+    drop(rpc);
+    broker.kill().ok();
+    broker.wait().ok();
+}
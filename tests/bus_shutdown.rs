@@ -0,0 +1,78 @@
+use adcp::{control, AppConfig, Service};
+use busrt::broker::Broker;
+use busrt::rpc::RpcClient;
+use std::path::Path;
+use tempfile::tempdir;
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout, Duration};
+
+fn writing_markers(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|rd| {
+            rd.filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| name.ends_with(".writing"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn shutdown_rpc_stops_a_running_service_cleanly() {
+    let tmp = tempdir().expect("temp dir");
+    let capture = tmp.path().join("capture.data");
+    std::fs::write(
+        &capture,
+        "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\n",
+    )
+    .expect("write capture");
+    let data_process_dir = tmp.path().join("to_process");
+    let config_path = tmp.path().join("adcp.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "service_name = \"bus-shutdown-test\"
+serial_port = {:?}
+data_directory = {:?}
+backup_folder = {:?}
+data_process_folder = {:?}
+processed_folder = {:?}",
+            capture,
+            tmp.path().join("data"),
+            tmp.path().join("backup"),
+            data_process_dir,
+            tmp.path().join("processed"),
+        ),
+    )
+    .expect("write config");
+    let config = AppConfig::load(&config_path).expect("load config");
+
+    // In-process broker: the service answers on the bus, a second client asks it to stop
+    let broker = Broker::new();
+    let (stop, stop_rx) = watch::channel(());
+    let _service_rpc = RpcClient::new(
+        broker.register_client("adcp.test.recorder").await.expect("register service"),
+        control::ShutdownHandlers::new(stop),
+    );
+    let caller = RpcClient::new0(broker.register_client("adcp.test.caller").await.expect("register caller"));
+
+    let service = tokio::spawn(Service::new(config).with_tmp_dir(tmp.path()).with_shutdown_trigger(stop_rx).run());
+
+    let mut waited = Duration::ZERO;
+    while writing_markers(&data_process_dir).is_empty() {
+        assert!(waited < Duration::from_secs(10), "recorder never wrote a marker");
+        sleep(Duration::from_millis(50)).await;
+        waited += Duration::from_millis(50);
+    }
+
+    control::request_shutdown(&caller, "adcp.test.recorder", Duration::from_secs(5))
+        .await
+        .expect("shutdown rpc answered");
+
+    let result = timeout(Duration::from_secs(10), service)
+        .await
+        .expect("service stops after the shutdown rpc")
+        .expect("join service");
+    assert!(result.is_ok(), "clean shutdown: {:?}", result);
+    assert!(writing_markers(&data_process_dir).is_empty(), "writer markers removed");
+}
@@ -0,0 +1,57 @@
+use adcp::AppConfig;
+use busrt::broker::{Broker, ServerConfig};
+use busrt::rpc::{Rpc, RpcClient};
+use busrt::QoS;
+use tempfile::tempdir;
+use tokio::time::{sleep, Duration, Instant};
+
+#[tokio::test]
+async fn get_toml_round_trips_the_effective_config() {
+    let tmp = tempdir().expect("temp dir");
+    std::fs::create_dir_all(tmp.path().join("config")).expect("config dir");
+    let config_path = tmp.path().join("config").join("adcp.toml");
+    std::fs::write(
+        &config_path,
+        "service_name = \"conf-toml-test\"
+serial_port = \"/dev/ttyADCP\"
+reference_sound_speed_m_s = 1490.5
+retention_action = { Archive = \"./deployment/archive\" }
+validation_rules = [{ field = \"temperature_c\", min = -5.0, max = 40.0 }]
+",
+    )
+    .expect("write config");
+    let expected = AppConfig::load(&config_path).expect("load config");
+
+    let mut broker = Broker::new();
+    broker
+        .spawn_tcp_server("127.0.0.1:7777", ServerConfig::default())
+        .await
+        .expect("broker listening");
+    let caller = RpcClient::new0(broker.register_client("adcp.test.conf-caller").await.expect("register caller"));
+
+    let mut manager = std::process::Command::new(assert_cmd::cargo::cargo_bin!("adcp-conf-manager"))
+        .current_dir(tmp.path())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("spawn conf manager");
+
+    // The manager registers on the bus shortly after start; retry until it answers
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let reply = loop {
+        match caller
+            .call("adcp.conf.manager", "cmd.conf.get_toml", Vec::new().into(), QoS::Processed)
+            .await
+        {
+            Ok(reply) => break reply,
+            Err(_) if Instant::now() < deadline => sleep(Duration::from_millis(100)).await,
+            Err(err) => panic!("conf manager never answered: {err:?}"),
+        }
+    };
+    manager.kill().ok();
+    manager.wait().ok();
+
+    let rendered = std::str::from_utf8(reply.payload()).expect("utf-8 toml");
+    let parsed: AppConfig = toml::from_str(rendered).expect("reply parses as config");
+    assert_eq!(parsed, expected);
+}
@@ -23,6 +23,140 @@ fn e2e_table_driven_fixtures() {
     }
 }
 
+#[test]
+fn count_by_day_reports_frames_per_date_without_persisting() {
+    let tmp = tempfile::tempdir().expect("create tempdir");
+    let data_dir = tmp.path().join("data");
+    let cfg_path = tmp.path().join("adcp.toml");
+    std::fs::write(
+        &cfg_path,
+        format!(
+            "service_name = 'count-by-day'\nserial_port = '/dev/null'\ndata_directory = '{}'\n",
+            data_dir.display().to_string().replace('\\', "/")
+        ),
+    )
+    .expect("write config");
+
+    let output = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("adcp-legacy"))
+        .arg("--config")
+        .arg(&cfg_path)
+        .arg("--replay")
+        .arg("tests/sample2.data")
+        .arg("--count-by-day")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).expect("utf-8 output");
+    let counts: Vec<(&str, &str)> = stdout
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(date, count)| (date, count.trim()))
+        .filter(|(date, _)| date.starts_with("2026-"))
+        .collect();
+    assert_eq!(counts, [("2026-01-05", "2"), ("2026-02-05", "4")], "{stdout}");
+    assert!(!data_dir.exists(), "nothing persisted");
+}
+
+#[test]
+fn processing_run_logs_a_shutdown_summary() {
+    let tmp = tempfile::tempdir().expect("create tempdir");
+    let to_process = tmp.path().join("to_process");
+    std::fs::create_dir_all(&to_process).expect("create processing folder");
+    std::fs::copy("tests/sample2.data", to_process.join("sample2.data")).expect("stage capture");
+    let cfg_path = tmp.path().join("adcp.toml");
+    let dir = |name: &str| tmp.path().join(name).display().to_string().replace('\\', "/");
+    std::fs::write(
+        &cfg_path,
+        format!(
+            "service_name = 'shutdown-summary'\nmode = 'Processing'\nrun_once = true\nfile_stability_seconds = 0\nlog_level = 'info'\n\
+             data_directory = '{}'\ndata_process_folder = '{}'\nprocessed_folder = '{}'\n",
+            dir("data"),
+            dir("to_process"),
+            dir("processed"),
+        ),
+    )
+    .expect("write config");
+
+    assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("adcp-legacy"))
+        .current_dir(tmp.path())
+        .env_remove("RUST_LOG")
+        .arg("--config")
+        .arg(&cfg_path)
+        .assert()
+        .success();
+
+    let log_dir = tmp.path().join("deployment/log");
+    let log: String = std::fs::read_dir(&log_dir)
+        .expect("read log dir")
+        .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path()).ok())
+        .collect();
+    let summary = log
+        .lines()
+        .find(|line| line.contains("shutdown summary"))
+        .unwrap_or_else(|| panic!("no shutdown summary in {log}"));
+    assert!(summary.contains("frames=8"), "{summary}");
+    assert!(summary.contains("parse_errors=2"), "{summary}");
+    // The processed capture plus one dated log per day
+    assert!(summary.contains("files_produced=3"), "{summary}");
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_drains_replay_leaving_complete_lines() {
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let tmp = tempfile::tempdir().expect("create tempdir");
+    let data_dir = tmp.path().join("data");
+    let cfg_path = tmp.path().join("adcp.toml");
+    std::fs::write(
+        &cfg_path,
+        format!(
+            "service_name = 'replay-drain'\nserial_port = '/dev/null'\ndata_directory = '{}'\n",
+            data_dir.display()
+        ),
+    )
+    .expect("write config");
+    // Far more than replays before the signal lands, so the replay is cut short
+    let capture = tmp.path().join("huge.data");
+    let line = "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\n";
+    std::fs::write(&capture, line.repeat(500_000)).expect("write capture");
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("adcp-legacy"))
+        .current_dir(tmp.path())
+        .arg("--config")
+        .arg(&cfg_path)
+        .arg("--replay")
+        .arg(&capture)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn replay");
+
+    let log = data_dir.join("adcp-2026-01-05.log");
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while std::fs::metadata(&log).map_or(true, |meta| meta.len() == 0) {
+        assert!(Instant::now() < deadline, "replay never persisted a frame");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    unsafe { libc::kill(child.id() as i32, libc::SIGINT) };
+
+    let output = child.wait_with_output().expect("wait for replay");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "interrupted replay exits cleanly: {stdout}");
+    assert!(stdout.contains("replay interrupted"), "{stdout}");
+
+    let content = std::fs::read_to_string(&log).expect("read log");
+    assert!(content.ends_with('\n'), "last line torn");
+    let lines: Vec<&str> = content.lines().collect();
+    assert!(lines.len() < 500_000, "replay ran to completion");
+    for line in lines {
+        serde_json::from_str::<serde_json::Value>(line).expect("complete JSON line");
+    }
+}
+
 #[tokio::test]
 async fn concurrent_recording_and_processing() {
     use adcp::{backup, config::{AppConfig, ServiceMode}, processing};
@@ -44,21 +178,15 @@ async fn concurrent_recording_and_processing() {
 
     let config = Arc::new(AppConfig {
         service_name: "test-processor".to_string(),
-        log_level: "info".to_string(),
         data_directory: data_output_dir.to_string_lossy().to_string(),
         serial_port: Some("/dev/null".to_string()),
-        baud_rate: 115200,
-        idle_threshold_seconds: 30,
-        alert_webhook: None,
         mode: ServiceMode::Processing,
         backup_folder: backup_dir.to_string_lossy().to_string(),
         data_process_folder: data_process_dir.to_string_lossy().to_string(),
         processed_folder: processed_dir.to_string_lossy().to_string(),
-        split_mode: adcp::config::SplitMode::Daily,
-        max_backup_files: None,
-        max_backup_age_days: None,
         file_stability_seconds: 1, // Short for test
-        sample_file: None,
+        processing_concurrency: 1,
+        ..Default::default()
     });
 
     let (shutdown_tx, shutdown_rx) = watch::channel(());
@@ -0,0 +1,28 @@
+use assert_cmd::Command;
+
+#[test]
+fn list_ports_succeeds_without_ports() {
+    // The sandbox/CI usually has no serial adapters; an empty list must still exit 0.
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("adcp-legacy"))
+        .arg("list-ports")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).expect("utf-8 output");
+    assert!(!stdout.trim().is_empty(), "either ports or an explicit 'none' line is printed");
+}
+
+#[test]
+fn list_ports_rejects_trailing_arguments() {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("adcp-legacy"))
+        .args(["list-ports", "--verbose"])
+        .assert()
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(output).expect("utf-8 output");
+    assert!(stderr.contains("list-ports takes no arguments"), "{stderr}");
+}
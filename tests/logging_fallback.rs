@@ -0,0 +1,19 @@
+use adcp::{logging, AppConfig};
+use tempfile::tempdir;
+
+#[test]
+fn unwritable_log_dir_falls_back_to_stdout() {
+    let tmp = tempdir().expect("temp dir");
+    let config_path = tmp.path().join("adcp.toml");
+    std::fs::write(&config_path, "service_name = \"logging-fallback\"").expect("write config");
+    let config = AppConfig::load(&config_path).expect("load config");
+
+    // A regular file where the log directory should go cannot be turned into a directory,
+    // even when the tests run as root
+    let blocker = tmp.path().join("not-a-dir");
+    std::fs::write(&blocker, "").expect("write blocker");
+
+    let guard = logging::init_with_dir(&config, &blocker.join("log")).expect("init still succeeds");
+    assert!(guard.is_none(), "no file writer without a log directory");
+    tracing::info!("stdout logging still works");
+}
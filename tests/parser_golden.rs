@@ -0,0 +1,78 @@
+use adcp::parser::Frame;
+use std::fs;
+
+const FIXTURES: &[&str] = &[
+    "tests/sample.data",
+    "tests/sample2.data",
+    "tests/fixtures/corrupt.data",
+    "tests/fixtures/literal.data",
+    "tests/fixtures/merge_a.data",
+    "tests/fixtures/merge_b.data",
+    "tests/fixtures/small.data",
+];
+
+const GOLDEN: &str = "tests/golden/parser_output.txt";
+
+/// Parses every fixture line as-is (junk and literal `\r\n` included) and again with the
+/// literal terminator stripped (the clean path), rendering the persistence line or the
+/// error for each. Undated frames take `recorded_at` from the host clock, so it is masked.
+fn render_fixtures() -> String {
+    let mut out = String::new();
+    for path in FIXTURES {
+        let raw = fs::read_to_string(path).unwrap_or_else(|e| panic!("read {path}: {e}"));
+        out.push_str(&format!("# {path}\n"));
+        let variants = raw.lines().flat_map(|line| [line, line.trim_end_matches("\\r\\n")]);
+        for line in variants {
+            match Frame::from_line(line) {
+                Ok(frame) => {
+                    let mut rendered = frame.to_persistence_line();
+                    if frame.payload.sent_at().is_none() {
+                        let stamp = serde_json::to_string(&frame.recorded_at).unwrap();
+                        rendered = rendered.replace(&stamp, "\"<host-clock>\"");
+                    }
+                    out.push_str(&rendered);
+                }
+                Err(err) => out.push_str(&format!("error: {err:#}")),
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[test]
+fn parser_output_matches_golden() {
+    let rendered = render_fixtures();
+    if std::env::var_os("UPDATE_PARSER_GOLDEN").is_some() {
+        fs::write(GOLDEN, &rendered).expect("write golden");
+        return;
+    }
+    let golden = fs::read_to_string(GOLDEN).expect("read golden");
+    assert!(rendered == golden, "parser output drifted from {GOLDEN}; rerun with UPDATE_PARSER_GOLDEN=1 only if the change is intended");
+}
+
+#[test]
+fn borrowed_parser_matches_owned_on_fixtures() {
+    for path in FIXTURES {
+        let raw = fs::read_to_string(path).unwrap_or_else(|e| panic!("read {path}: {e}"));
+        let variants = raw.lines().flat_map(|line| [line, line.trim_end_matches("\\r\\n")]);
+        for line in variants {
+            match (Frame::parse_borrowed(line), Frame::from_line(line)) {
+                (Ok(borrowed), Ok(owned)) => {
+                    assert!(matches!(borrowed.raw, std::borrow::Cow::Borrowed(_)), "{path}: raw copied for {line:?}");
+                    assert_eq!(borrowed.raw, owned.raw, "{path}: {line:?}");
+                    assert_eq!(borrowed.checksum.provided, owned.checksum.provided, "{path}: {line:?}");
+                    assert_eq!(borrowed.checksum.computed, owned.checksum.computed, "{path}: {line:?}");
+                    assert_eq!(borrowed.checksum.valid, owned.checksum.valid, "{path}: {line:?}");
+                    assert_eq!(borrowed.payload, owned.payload, "{path}: {line:?}");
+                    assert_eq!(borrowed.discarded, owned.discarded, "{path}: {line:?}");
+                    if owned.payload.sent_at().is_some() {
+                        assert_eq!(borrowed.into_owned(), owned, "{path}: {line:?}");
+                    }
+                }
+                (Err(borrowed), Err(owned)) => assert_eq!(format!("{borrowed:#}"), format!("{owned:#}"), "{path}: {line:?}"),
+                (borrowed, owned) => panic!("{path}: {line:?} parsed differently: {borrowed:?} vs {owned:?}"),
+            }
+        }
+    }
+}
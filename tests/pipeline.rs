@@ -11,7 +11,7 @@ mod pipeline_linux {
         let persistence = Persistence::new(tmp.path()).await.expect("persistence");
         let metrics = Metrics::new();
 
-        let lines = vec![
+        let lines = [
             "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77",
             "$PNORC,010526,220800,1,-32.77,-32.77,-32.77,-32.77,46.34,225.0,C,65,64,61,59,40,37,14,22*35",
         ];
@@ -63,7 +63,7 @@ mod pipeline_windows {
         let persistence = Persistence::new(tmp.path()).await.expect("persistence");
         let metrics = Metrics::new();
 
-        let lines = vec![
+        let lines = [
             "$PNORS,010526,220900,00000000,3ED40002,23.7,1532.0,275.9,-49.1,83.0,0.000,24.01,0,0*78",
             "$PNORC,010526,220900,1,-32.77,-32.77,-32.77,-32.77,46.34,225.0,C,65,65,60,60,42,41,13,24*3C",
         ];
@@ -84,7 +84,7 @@ mod pipeline_windows {
 }
 
     mod sample_replay {
-        use adcp::{config::{ServiceMode, SplitMode}, simulator, AppConfig};
+        use adcp::{simulator, AppConfig};
         use std::fs;
         use tempfile::tempdir;
 
@@ -93,21 +93,13 @@ mod pipeline_windows {
             let tmp = tempdir().expect("temp dir");
             let cfg = AppConfig {
                 service_name: "sample-supervisor".into(),
-                log_level: "info".into(),
                 data_directory: tmp.path().to_string_lossy().to_string(),
                 serial_port: Some("/dev/null".into()),
-                baud_rate: 115200,
-                idle_threshold_seconds: 30,
-                alert_webhook: None,
-                mode: ServiceMode::Recording,
                 backup_folder: "./backup".into(),
                 data_process_folder: "./to_process".into(),
                 processed_folder: "./processed".into(),
-                split_mode: SplitMode::Daily,
-                max_backup_files: None,
-                max_backup_age_days: None,
-                file_stability_seconds: 5,
-                sample_file: None,
+                processing_concurrency: 1,
+                ..Default::default()
             };
 
             simulator::replay_sample("tests/sample.data", &cfg)
@@ -131,21 +123,13 @@ mod pipeline_windows {
             let tmp = tempdir().expect("temp dir");
             let cfg = AppConfig {
                 service_name: "sample2-supervisor".into(),
-                log_level: "info".into(),
                 data_directory: tmp.path().to_string_lossy().to_string(),
                 serial_port: Some("/dev/null".into()),
-                baud_rate: 115200,
-                idle_threshold_seconds: 30,
-                alert_webhook: None,
-                mode: ServiceMode::Recording,
                 backup_folder: "./backup".into(),
                 data_process_folder: "./to_process".into(),
                 processed_folder: "./processed".into(),
-                split_mode: SplitMode::Daily,
-                max_backup_files: None,
-                max_backup_age_days: None,
-                file_stability_seconds: 5,
-                sample_file: None,
+                processing_concurrency: 1,
+                ..Default::default()
             };
 
             simulator::replay_sample("tests/sample2.data", &cfg)
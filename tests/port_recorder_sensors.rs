@@ -0,0 +1,110 @@
+#![cfg(target_os = "linux")]
+
+use adcp::parser::SensorSentence;
+use adcp::telemetry::RecorderStats;
+use busrt::broker::{Broker, ServerConfig};
+use busrt::client::AsyncClient;
+use busrt::rpc::{Rpc, RpcClient};
+use busrt::QoS;
+use std::io::Write;
+use std::os::fd::FromRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tempfile::tempdir;
+use tokio::time::{sleep, timeout, Duration, Instant};
+
+/// Opens a pseudo-terminal: the master end the test writes to, and the slave path the
+/// recorder opens as its serial port.
+fn open_pty() -> (std::fs::File, String) {
+    unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        assert!(master >= 0, "open pty master");
+        assert_eq!(libc::grantpt(master), 0, "grant pty");
+        assert_eq!(libc::unlockpt(master), 0, "unlock pty");
+        let mut name = [0 as libc::c_char; 128];
+        assert_eq!(libc::ptsname_r(master, name.as_mut_ptr(), name.len()), 0, "pty slave name");
+        let slave = std::ffi::CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned();
+        (std::fs::File::from_raw_fd(master), slave)
+    }
+}
+
+#[tokio::test]
+async fn sensors_rpc_serves_the_last_reading_after_an_oversized_line() {
+    let tmp = tempdir().expect("temp dir");
+    let (mut master, slave) = open_pty();
+    std::fs::create_dir_all(tmp.path().join("config")).expect("config dir");
+    std::fs::write(
+        tmp.path().join("config").join("adcp.toml"),
+        format!("service_name = \"recorder-sensors-test\"\nserial_port = {slave:?}\n"),
+    )
+    .expect("write config");
+
+    let mut broker = Broker::new();
+    broker
+        .spawn_tcp_server("127.0.0.1:7777", ServerConfig::default())
+        .await
+        .expect("broker listening");
+    let mut monitor = broker.register_client("adcp.test.monitor").await.expect("register monitor");
+    monitor.subscribe("stat/recorder/#", QoS::Processed).await.expect("subscribe");
+    let events = monitor.take_event_channel().expect("event channel");
+    let caller = RpcClient::new0(broker.register_client("adcp.test.caller").await.expect("register caller"));
+
+    let mut recorder = std::process::Command::new(assert_cmd::cargo::cargo_bin!("adcp-port-recorder"))
+        .current_dir(tmp.path())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("spawn recorder");
+    let target = format!("adcp.recorder.{}", recorder.id());
+
+    // A run of junk past the line cap, then a sensor frame; repeated until the recorder has
+    // opened the port and answers with the reading
+    let stop_writing = Arc::new(AtomicBool::new(false));
+    let writer_stop = stop_writing.clone();
+    std::thread::spawn(move || {
+        let mut burst = vec![b'x'; 70 * 1024];
+        burst.extend_from_slice(b"\n$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\n");
+        while !writer_stop.load(Ordering::Relaxed) && master.write_all(&burst).is_ok() {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+        }
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let sensor = loop {
+        assert!(Instant::now() < deadline, "sensors rpc never served a reading");
+        let reply = timeout(
+            Duration::from_secs(2),
+            caller.call(&target, "cmd.recorder.sensors", Vec::new().into(), QoS::Processed),
+        )
+        .await;
+        if let Ok(Ok(reply)) = reply {
+            if let Some(sensor) = serde_json::from_slice::<Option<SensorSentence>>(reply.payload()).expect("sensors json") {
+                break sensor;
+            }
+        }
+        sleep(Duration::from_millis(100)).await;
+    };
+    assert_eq!(sensor.heading_deg, Some(275.4));
+    assert_eq!(sensor.temperature_c, Some(24.02));
+
+    let stats = loop {
+        let frame = timeout(Duration::from_secs(10), events.recv())
+            .await
+            .expect("stats published in time")
+            .expect("bus open");
+        let stats: RecorderStats = serde_json::from_slice(frame.payload()).expect("stats json");
+        if stats.oversized_lines > 0 {
+            break stats;
+        }
+    };
+    assert!(stats.oversized_lines >= 1);
+
+    stop_writing.store(true, Ordering::Relaxed);
+    unsafe { libc::kill(recorder.id() as libc::pid_t, libc::SIGINT) };
+    let status = timeout(Duration::from_secs(10), tokio::task::spawn_blocking(move || recorder.wait()))
+        .await
+        .expect("recorder exits after ctrl-c")
+        .expect("join wait")
+        .expect("wait for recorder");
+    assert!(status.success());
+}
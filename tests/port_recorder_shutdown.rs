@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use adcp::telemetry::RecorderStats;
+use busrt::broker::{Broker, ServerConfig};
+use busrt::client::AsyncClient;
+use busrt::QoS;
+use tempfile::tempdir;
+use tokio::time::{timeout, Duration};
+
+#[tokio::test]
+async fn publishes_terminal_stats_on_ctrl_c() {
+    let tmp = tempdir().expect("temp dir");
+    std::fs::create_dir_all(tmp.path().join("config")).expect("config dir");
+    std::fs::write(
+        tmp.path().join("config").join("adcp.toml"),
+        "service_name = \"recorder-shutdown-test\"\nserial_port = \"/nonexistent/ttyADCP\"\n",
+    )
+    .expect("write config");
+
+    let mut broker = Broker::new();
+    broker
+        .spawn_tcp_server("127.0.0.1:7777", ServerConfig::default())
+        .await
+        .expect("broker listening");
+    let mut monitor = broker.register_client("adcp.test.monitor").await.expect("register monitor");
+    monitor.subscribe("stat/recorder/#", QoS::Processed).await.expect("subscribe");
+    let events = monitor.take_event_channel().expect("event channel");
+
+    let mut recorder = std::process::Command::new(assert_cmd::cargo::cargo_bin!("adcp-port-recorder"))
+        .current_dir(tmp.path())
+        .env("ADCP_SIMULATE_SERIAL", "1")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("spawn recorder");
+
+    let next_stats = || async {
+        let frame = timeout(Duration::from_secs(10), events.recv())
+            .await
+            .expect("stats published in time")
+            .expect("bus open");
+        serde_json::from_slice::<RecorderStats>(frame.payload()).expect("stats json")
+    };
+
+    // Wait until the simulation loop (and its ctrl-c listener) is running
+    loop {
+        let stats = next_stats().await;
+        assert!(!stats.stopped);
+        if stats.bytes_read_total >= 300 {
+            break;
+        }
+    }
+    unsafe { libc::kill(recorder.id() as libc::pid_t, libc::SIGINT) };
+
+    let terminal = loop {
+        let stats = next_stats().await;
+        if stats.stopped {
+            break stats;
+        }
+    };
+    assert_eq!(terminal.port_name, "/nonexistent/ttyADCP");
+    assert_eq!(terminal.bytes_per_second, 0);
+    // Wait off the runtime: the in-process broker must keep confirming the recorder's publishes
+    let status = timeout(Duration::from_secs(10), tokio::task::spawn_blocking(move || recorder.wait()))
+        .await
+        .expect("recorder exits after ctrl-c")
+        .expect("join wait")
+        .expect("wait for recorder");
+    assert!(status.success());
+}
@@ -0,0 +1,17 @@
+use assert_cmd::Command;
+
+#[test]
+fn selftest_passes_without_config_or_hardware() {
+    // Run from an empty directory so no config/adcp.toml can be picked up by accident.
+    let tmp = tempfile::tempdir().expect("temp dir");
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("adcp-legacy"))
+        .current_dir(tmp.path())
+        .arg("selftest")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).expect("utf-8 output");
+    assert!(stdout.starts_with("PASS"), "{stdout}");
+}
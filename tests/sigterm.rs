@@ -0,0 +1,68 @@
+#![cfg(unix)]
+
+use adcp::{AppConfig, Service};
+use std::path::Path;
+use tempfile::tempdir;
+use tokio::time::{sleep, timeout, Duration};
+
+fn writing_markers(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|rd| {
+            rd.filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| name.ends_with(".writing"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn sigterm_shuts_recording_down_cleanly() {
+    let tmp = tempdir().expect("temp dir");
+    let capture = tmp.path().join("capture.data");
+    std::fs::write(
+        &capture,
+        "$PNORS,010526,220800,00000000,3ED40002,23.7,1532.0,275.4,-49.1,83.0,0.000,24.02,0,0*77\n",
+    )
+    .expect("write capture");
+    let data_process_dir = tmp.path().join("to_process");
+    let config_path = tmp.path().join("adcp.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "service_name = \"sigterm-test\"
+serial_port = {:?}
+data_directory = {:?}
+backup_folder = {:?}
+data_process_folder = {:?}
+processed_folder = {:?}",
+            capture,
+            tmp.path().join("data"),
+            tmp.path().join("backup"),
+            data_process_dir,
+            tmp.path().join("processed"),
+        ),
+    )
+    .expect("write config");
+    let config = AppConfig::load(&config_path).expect("load config");
+
+    let service = tokio::spawn(Service::new(config).with_tmp_dir(tmp.path()).run());
+
+    // Wait until the recorder has written the line and left its writer marker behind
+    let mut waited = Duration::ZERO;
+    while writing_markers(&data_process_dir).is_empty() {
+        assert!(waited < Duration::from_secs(10), "recorder never wrote a marker");
+        sleep(Duration::from_millis(50)).await;
+        waited += Duration::from_millis(50);
+    }
+
+    unsafe { libc::kill(libc::getpid(), libc::SIGTERM) };
+
+    let result = timeout(Duration::from_secs(10), service)
+        .await
+        .expect("service stops after SIGTERM")
+        .expect("join service");
+    assert!(result.is_ok(), "clean shutdown: {:?}", result);
+    assert!(writing_markers(&data_process_dir).is_empty(), "writer markers removed");
+    assert!(tmp.path().join("data").join("adcp-2026-01-05.log").exists());
+}